@@ -0,0 +1,256 @@
+// 每条指令消耗的计算单元
+// 先运行 cargo build-sbf 生成 target/deploy/blueshift_native_amm.so 再运行 cargo bench
+// 结果写入 benches/compute_units.md 和上一次的结果对比 提交这个文件就能在评审时看到变化
+use blueshift_native_amm::{Config, ID};
+use mollusk_svm::Mollusk;
+use mollusk_svm_bencher::MolluskComputeUnitBencher;
+use solana_account::Account;
+use solana_address::Address;
+use solana_instruction::{AccountMeta, Instruction};
+use solana_pubkey::Pubkey;
+
+const SEED: u64 = 1;
+const FEE: u16 = 30;
+const DECIMALS: u8 = 6;
+const RESERVE: u64 = 1_000_000_000_000;
+const LP_SUPPLY: u64 = 1_000_000_000_000;
+const BALANCE: u64 = 1_000_000_000;
+
+fn pubkey(address: &Address) -> Pubkey {
+    Pubkey::new_from_array(address.to_bytes())
+}
+
+// 和关联代币账户程序相同的推导方式
+fn ata(wallet: &Address, mint: &Address) -> Address {
+    Address::find_program_address(
+        &[wallet.as_ref(), pinocchio_token::ID.as_ref(), mint.as_ref()],
+        &pinocchio_associated_token_account::ID,
+    )
+    .0
+}
+
+fn account(mollusk: &Mollusk, data: Vec<u8>, owner: &Address) -> Account {
+    Account {
+        lamports: mollusk.sysvars.rent.minimum_balance(data.len()),
+        data,
+        owner: pubkey(owner),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+// SPL Token 的铸币账户布局 mint_authority | supply | decimals | is_initialized | freeze_authority
+fn mint(mollusk: &Mollusk, authority: &Address, supply: u64) -> Account {
+    let mut data = vec![0u8; 82];
+    data[0..4].copy_from_slice(&1u32.to_le_bytes());
+    data[4..36].copy_from_slice(authority.as_ref());
+    data[36..44].copy_from_slice(&supply.to_le_bytes());
+    data[44] = DECIMALS;
+    data[45] = 1;
+    account(mollusk, data, &pinocchio_token::ID)
+}
+
+// SPL Token 的代币账户布局 mint | owner | amount | delegate | state | ...
+fn token_account(mollusk: &Mollusk, mint: &Address, owner: &Address, amount: u64) -> Account {
+    let mut data = vec![0u8; 165];
+    data[0..32].copy_from_slice(mint.as_ref());
+    data[32..64].copy_from_slice(owner.as_ref());
+    data[64..72].copy_from_slice(&amount.to_le_bytes());
+    data[108] = 1;
+    account(mollusk, data, &pinocchio_token::ID)
+}
+
+fn meta(address: &Address, writable: bool, signer: bool) -> AccountMeta {
+    match writable {
+        true => AccountMeta::new(pubkey(address), signer),
+        false => AccountMeta::new_readonly(pubkey(address), signer),
+    }
+}
+
+// 一个已经注入流动性的恒定乘积池 用户持有两种代币和 LP
+struct Pool {
+    user: Address,
+    config: Address,
+    mint_x: Address,
+    mint_y: Address,
+    mint_lp: Address,
+    vault_x: Address,
+    vault_y: Address,
+    user_x: Address,
+    user_y: Address,
+    user_lp: Address,
+    locked_lp: Address,
+    accounts: Vec<(Pubkey, Account)>,
+}
+
+impl Pool {
+    fn new(mollusk: &Mollusk) -> Self {
+        let user = Address::new_from_array([9; 32]);
+        let mint_x = Address::new_from_array([1; 32]);
+        let mint_y = Address::new_from_array([2; 32]);
+        let (config, bump) = Address::find_program_address(
+            &[
+                b"config",
+                &SEED.to_le_bytes(),
+                mint_x.as_ref(),
+                mint_y.as_ref(),
+            ],
+            &ID,
+        );
+        let (mint_lp, _) = Address::find_program_address(&[b"mint_lp", config.as_ref()], &ID);
+        let (vault_x, vault_y) = (ata(&config, &mint_x), ata(&config, &mint_y));
+        let (user_x, user_y, user_lp) = (
+            ata(&user, &mint_x),
+            ata(&user, &mint_y),
+            ata(&user, &mint_lp),
+        );
+        let locked_lp = ata(&config, &mint_lp);
+
+        // Config 只由字节数组组成 全零就是一个合法的值
+        let mut state: Config = unsafe { core::mem::zeroed() };
+        state
+            .set_inner(SEED, user, mint_x, mint_y, FEE, [bump])
+            .unwrap();
+        state.set_vaults(vault_x, vault_y);
+        let config_data = unsafe {
+            core::slice::from_raw_parts(&state as *const Config as *const u8, Config::LEN)
+        }
+        .to_vec();
+
+        let accounts = vec![
+            (
+                pubkey(&user),
+                Account::new(10_000_000_000, 0, &pubkey(&pinocchio_system::ID)),
+            ),
+            (pubkey(&config), account(mollusk, config_data, &ID)),
+            (pubkey(&mint_x), mint(mollusk, &user, u64::MAX / 2)),
+            (pubkey(&mint_y), mint(mollusk, &user, u64::MAX / 2)),
+            (pubkey(&mint_lp), mint(mollusk, &config, LP_SUPPLY)),
+            (
+                pubkey(&vault_x),
+                token_account(mollusk, &mint_x, &config, RESERVE),
+            ),
+            (
+                pubkey(&vault_y),
+                token_account(mollusk, &mint_y, &config, RESERVE),
+            ),
+            (
+                pubkey(&user_x),
+                token_account(mollusk, &mint_x, &user, BALANCE),
+            ),
+            (
+                pubkey(&user_y),
+                token_account(mollusk, &mint_y, &user, BALANCE),
+            ),
+            (
+                pubkey(&user_lp),
+                token_account(mollusk, &mint_lp, &user, BALANCE),
+            ),
+            (
+                pubkey(&locked_lp),
+                token_account(mollusk, &mint_lp, &config, 0),
+            ),
+            mollusk_svm_programs_token::token::keyed_account(),
+        ];
+        Self {
+            user,
+            config,
+            mint_x,
+            mint_y,
+            mint_lp,
+            vault_x,
+            vault_y,
+            user_x,
+            user_y,
+            user_lp,
+            locked_lp,
+            accounts,
+        }
+    }
+
+    fn swap(&self, is_x: bool) -> Instruction {
+        let mut data = vec![3, is_x as u8];
+        data.extend_from_slice(&1_000_000u64.to_le_bytes());
+        data.extend_from_slice(&1u64.to_le_bytes());
+        data.extend_from_slice(&i64::MAX.to_le_bytes());
+        Instruction::new_with_bytes(
+            pubkey(&ID),
+            &data,
+            vec![
+                meta(&self.user, true, true),
+                meta(&self.user_x, true, false),
+                meta(&self.user_y, true, false),
+                meta(&self.vault_x, true, false),
+                meta(&self.vault_y, true, false),
+                meta(&self.config, true, false),
+                meta(&self.mint_x, false, false),
+                meta(&self.mint_y, false, false),
+                meta(&pinocchio_token::ID, false, false),
+                meta(&pinocchio_token::ID, false, false),
+            ],
+        )
+    }
+
+    fn deposit(&self) -> Instruction {
+        let mut data = vec![1];
+        data.extend_from_slice(&1_000_000u64.to_le_bytes());
+        data.extend_from_slice(&u64::MAX.to_le_bytes());
+        data.extend_from_slice(&u64::MAX.to_le_bytes());
+        data.extend_from_slice(&i64::MAX.to_le_bytes());
+        Instruction::new_with_bytes(pubkey(&ID), &data, self.liquidity_metas(true))
+    }
+
+    fn withdraw(&self) -> Instruction {
+        let mut data = vec![2];
+        data.extend_from_slice(&1_000_000u64.to_le_bytes());
+        data.extend_from_slice(&1u64.to_le_bytes());
+        data.extend_from_slice(&1u64.to_le_bytes());
+        data.extend_from_slice(&i64::MAX.to_le_bytes());
+        Instruction::new_with_bytes(pubkey(&ID), &data, self.liquidity_metas(false))
+    }
+
+    // Deposit 和 Withdraw 的账户顺序相同 Deposit 多出锁定 LP 的账户和创建它需要的程序
+    fn liquidity_metas(&self, deposit: bool) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            meta(&self.user, true, true),
+            meta(&self.mint_lp, true, false),
+            meta(&self.vault_x, true, false),
+            meta(&self.vault_y, true, false),
+            meta(&self.user_x, true, false),
+            meta(&self.user_y, true, false),
+            meta(&self.user_lp, true, false),
+            meta(&self.config, true, false),
+            meta(&pinocchio_token::ID, false, false),
+            meta(&self.mint_x, false, false),
+            meta(&self.mint_y, false, false),
+            meta(&pinocchio_token::ID, false, false),
+            meta(&pinocchio_token::ID, false, false),
+        ];
+        if deposit {
+            metas.push(meta(&self.locked_lp, true, false));
+            metas.push(meta(&pinocchio_system::ID, false, false));
+            metas.push(meta(&pinocchio_associated_token_account::ID, false, false));
+        }
+        metas
+    }
+}
+
+fn main() {
+    let mut mollusk = Mollusk::new(&pubkey(&ID), "blueshift_native_amm");
+    mollusk_svm_programs_token::token::add_program(&mut mollusk);
+
+    let pool = Pool::new(&mollusk);
+    let swap_x = pool.swap(true);
+    let swap_y = pool.swap(false);
+    let deposit = pool.deposit();
+    let withdraw = pool.withdraw();
+
+    MolluskComputeUnitBencher::new(mollusk)
+        .bench(("swap_x_for_y", &swap_x, &pool.accounts))
+        .bench(("swap_y_for_x", &swap_y, &pool.accounts))
+        .bench(("deposit", &deposit, &pool.accounts))
+        .bench(("withdraw", &withdraw, &pool.accounts))
+        .must_pass(true)
+        .out_dir("benches")
+        .execute();
+}
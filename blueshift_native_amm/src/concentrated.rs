@@ -0,0 +1,445 @@
+use crate::{
+    mul_div, mul_wide, AmmError, ClPool, ConstantProduct, LiquidityPair, Position, Tick, TickArray,
+    BPS_DENOMINATOR, TICKS_PER_ARRAY,
+};
+use pinocchio::{error::ProgramError, Address};
+
+// 集中流动性 (Uniswap v3 风格)
+// 每个 tick 对应价格 1.0001^tick 池子只保存 sqrt(price) 的 Q64.64 定点数
+// 头寸只在 [tick_lower, tick_upper) 区间内提供流动性 价格穿过区间边界时池子的流动性随之变化
+// 在一个区间内交换的计算和恒定乘积一样 L = sqrt(x * y)
+// 取整方向和 ConstantProduct 一致：用户得到的向下取整 需要支付的向上取整
+
+// tick 的范围 对应的 sqrt(price) 大约在 2^-32 到 2^32 之间
+pub const MIN_TICK: i32 = -443_636;
+pub const MAX_TICK: i32 = 443_636;
+// sqrt_price_at_tick(MIN_TICK) 和 sqrt_price_at_tick(MAX_TICK)
+pub const MIN_SQRT_PRICE: u128 = 4_295_048_016;
+pub const MAX_SQRT_PRICE: u128 = 79_226_673_521_066_979_257_578_248_091;
+// Q64.64 中的 1
+pub const Q64: u128 = 1 << 64;
+// 一次交换最多可以传入的 tick 数组数量
+pub const MAX_TICK_ARRAYS: usize = 3;
+
+// floor(2^64 / sqrt(1.0001)^(2^i)) 按 tick 的二进制位逐个相乘
+const RATIOS: [u128; 19] = [
+    18_445_821_805_675_392_311,
+    18_444_899_583_751_176_498,
+    18_443_055_278_223_354_162,
+    18_439_367_220_385_604_838,
+    18_431_993_317_065_449_817,
+    18_417_254_355_718_160_513,
+    18_387_811_781_193_591_352,
+    18_329_067_761_203_520_168,
+    18_212_142_134_806_087_854,
+    17_980_523_815_641_551_639,
+    17_526_086_738_831_147_013,
+    16_651_378_430_235_024_244,
+    15_030_750_278_693_429_944,
+    12_247_334_978_882_834_399,
+    8_131_365_268_884_726_200,
+    3_584_323_654_723_342_297,
+    696_457_651_847_595_233,
+    26_294_789_957_452_057,
+    37_481_735_321_082,
+];
+
+// sqrt(1.0001^tick) 的 Q64.64 表示
+// 先计算 |tick| 对应的倒数 tick 为正时再取倒数 结果随 tick 单调递增
+pub fn sqrt_price_at_tick(tick: i32) -> Result<u128, ProgramError> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(AmmError::InvalidTick.into());
+    }
+    let abs = tick.unsigned_abs();
+    let mut ratio = Q64;
+    for (i, r) in RATIOS.iter().enumerate() {
+        if abs & (1 << i) != 0 {
+            ratio = (ratio * r) >> 64;
+        }
+    }
+    if tick > 0 {
+        ratio = u128::MAX / ratio;
+    }
+    Ok(ratio)
+}
+
+// sqrt_price_at_tick(tick) <= sqrt_price 的最大 tick 二分查找
+pub fn tick_at_sqrt_price(sqrt_price: u128) -> Result<i32, ProgramError> {
+    if !(MIN_SQRT_PRICE..=MAX_SQRT_PRICE).contains(&sqrt_price) {
+        return Err(AmmError::InvalidTick.into());
+    }
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        if sqrt_price_at_tick(mid)? <= sqrt_price {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    Ok(low)
+}
+
+// ceil(a * b / c)
+#[inline(always)]
+fn mul_div_round_up(a: u128, b: u128, c: u128) -> Result<u128, ProgramError> {
+    let q = mul_div(a, b, c)?;
+    // q * c 等于 a * b 时没有余数
+    if mul_wide(q, c) == mul_wide(a, b) {
+        return Ok(q);
+    }
+    q.checked_add(1).ok_or(AmmError::CurveOverflow.into())
+}
+
+// 价格在 [sqrt_price_a, sqrt_price_b] 之间移动时需要的代币 X
+// x = L * (sqrt_b - sqrt_a) / (sqrt_a * sqrt_b)
+pub fn amount_x_delta(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u128, ProgramError> {
+    let (low, high) = match sqrt_price_a < sqrt_price_b {
+        true => (sqrt_price_a, sqrt_price_b),
+        false => (sqrt_price_b, sqrt_price_a),
+    };
+    if low == 0 {
+        return Err(AmmError::CurveOverflow.into());
+    }
+    match round_up {
+        true => mul_div_round_up(mul_div_round_up(liquidity, high - low, high)?, Q64, low),
+        false => mul_div(mul_div(liquidity, high - low, high)?, Q64, low),
+    }
+}
+
+// 价格在 [sqrt_price_a, sqrt_price_b] 之间移动时需要的代币 Y
+// y = L * (sqrt_b - sqrt_a)
+pub fn amount_y_delta(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u128, ProgramError> {
+    let diff = sqrt_price_a.abs_diff(sqrt_price_b);
+    match round_up {
+        true => mul_div_round_up(liquidity, diff, Q64),
+        false => mul_div(liquidity, diff, Q64),
+    }
+}
+
+// 支付 amount 之后的价格 取整让价格朝不利于用户的方向偏
+// 支付 X 价格下降: sqrt_p' = L * sqrt_p / (L + x * sqrt_p)
+// 支付 Y 价格上升: sqrt_p' = sqrt_p + y / L
+pub fn next_sqrt_price_from_input(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount: u64,
+    p: LiquidityPair,
+) -> Result<u128, ProgramError> {
+    if liquidity == 0 {
+        return Err(AmmError::InsufficientLiquidity.into());
+    }
+    match p {
+        LiquidityPair::X => {
+            let product = mul_div(amount as u128, sqrt_price, Q64)?;
+            let denominator = liquidity
+                .checked_add(product)
+                .ok_or(AmmError::CurveOverflow)?;
+            mul_div_round_up(liquidity, sqrt_price, denominator)
+        }
+        LiquidityPair::Y => sqrt_price
+            .checked_add(mul_div(amount as u128, Q64, liquidity)?)
+            .ok_or(AmmError::CurveOverflow.into()),
+    }
+}
+
+// 在当前流动性下向 sqrt_price_target 交换一步
+// 返回 (新价格, 进入池子的数量, 用户得到的数量, 手续费) 进入池子的数量不含手续费
+pub fn compute_swap_step(
+    sqrt_price: u128,
+    sqrt_price_target: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    fee: u16,
+) -> Result<(u128, u64, u64, u64), ProgramError> {
+    if fee as u64 >= BPS_DENOMINATOR {
+        return Err(AmmError::InvalidFee.into());
+    }
+    let p = match sqrt_price_target <= sqrt_price {
+        true => LiquidityPair::X,
+        false => LiquidityPair::Y,
+    };
+    let amount_less_fee = (amount_remaining as u128 * (BPS_DENOMINATOR - fee as u64) as u128
+        / BPS_DENOMINATOR as u128) as u64;
+    // 到达目标价格需要的数量 超出 u128 时一定够不到
+    let amount_to_target = match p {
+        LiquidityPair::X => amount_x_delta(sqrt_price_target, sqrt_price, liquidity, true),
+        LiquidityPair::Y => amount_y_delta(sqrt_price, sqrt_price_target, liquidity, true),
+    }
+    .unwrap_or(u128::MAX);
+
+    let (sqrt_price_next, amount_in) = match amount_less_fee as u128 >= amount_to_target {
+        true => (sqrt_price_target, amount_to_target as u64),
+        false => {
+            let next = next_sqrt_price_from_input(sqrt_price, liquidity, amount_less_fee, p)?;
+            // 取整之后的价格不会越过目标
+            let next = match p {
+                LiquidityPair::X => next.max(sqrt_price_target),
+                LiquidityPair::Y => next.min(sqrt_price_target),
+            };
+            let amount_in = match p {
+                LiquidityPair::X => amount_x_delta(next, sqrt_price, liquidity, true)?,
+                LiquidityPair::Y => amount_y_delta(sqrt_price, next, liquidity, true)?,
+            };
+            (next, (amount_in as u64).min(amount_less_fee))
+        }
+    };
+    let amount_out = match p {
+        LiquidityPair::X => amount_y_delta(sqrt_price_next, sqrt_price, liquidity, false)?,
+        LiquidityPair::Y => amount_x_delta(sqrt_price, sqrt_price_next, liquidity, false)?,
+    };
+    let amount_out = u64::try_from(amount_out).map_err(|_| AmmError::CurveOverflow)?;
+    // 没有到达目标价格说明 amount 已经用完 剩下的全部算作手续费
+    let fee_amount = match sqrt_price_next == sqrt_price_target {
+        true => (amount_in as u128 * fee as u128).div_ceil((BPS_DENOMINATOR - fee as u64) as u128)
+            as u64,
+        false => amount_remaining - amount_in,
+    };
+    Ok((sqrt_price_next, amount_in, amount_out, fee_amount))
+}
+
+// 区间 [lower, upper) 内累计的单位流动性手续费
+// 区间外的部分由两个边界 tick 的 fee_growth_outside 推出 所有运算都允许回绕
+pub fn fee_growth_inside(
+    tick_lower: i32,
+    lower: &Tick,
+    tick_upper: i32,
+    upper: &Tick,
+    tick_current: i32,
+    fee_growth_global_x: u128,
+    fee_growth_global_y: u128,
+) -> (u128, u128) {
+    let (below_x, below_y) = match tick_current >= tick_lower {
+        true => (lower.fee_growth_outside_x(), lower.fee_growth_outside_y()),
+        false => (
+            fee_growth_global_x.wrapping_sub(lower.fee_growth_outside_x()),
+            fee_growth_global_y.wrapping_sub(lower.fee_growth_outside_y()),
+        ),
+    };
+    let (above_x, above_y) = match tick_current < tick_upper {
+        true => (upper.fee_growth_outside_x(), upper.fee_growth_outside_y()),
+        false => (
+            fee_growth_global_x.wrapping_sub(upper.fee_growth_outside_x()),
+            fee_growth_global_y.wrapping_sub(upper.fee_growth_outside_y()),
+        ),
+    };
+    (
+        fee_growth_global_x
+            .wrapping_sub(below_x)
+            .wrapping_sub(above_x),
+        fee_growth_global_y
+            .wrapping_sub(below_y)
+            .wrapping_sub(above_y),
+    )
+}
+
+// 交换时第一个 tick 数组的起始 tick 之后的数组沿交换方向依次相邻
+// 价格上升时从下一个对齐的 tick 开始找 所以当前 tick 在数组最后一格时直接从下一个数组开始
+#[inline(always)]
+pub fn first_tick_array_start(tick: i32, tick_spacing: u16, p: LiquidityPair) -> i32 {
+    let search = match p {
+        LiquidityPair::X => tick,
+        LiquidityPair::Y => (tick.div_euclid(tick_spacing as i32) + 1) * tick_spacing as i32,
+    };
+    TickArray::start_tick_of(search, tick_spacing)
+}
+
+// 沿交换方向第 index 个 tick 数组的起始 tick
+#[inline(always)]
+pub fn tick_array_start(first: i32, index: usize, tick_spacing: u16, p: LiquidityPair) -> i32 {
+    let offset = index as i32 * tick_spacing as i32 * TICKS_PER_ARRAY as i32;
+    match p {
+        LiquidityPair::X => first - offset,
+        LiquidityPair::Y => first + offset,
+    }
+}
+
+// tick 数组的 PDA: [b"tick_array", config, start_tick]
+#[inline(always)]
+pub fn tick_array_address(config: &Address, start_tick: i32) -> (Address, u8) {
+    Address::find_program_address(
+        &[b"tick_array", config.as_ref(), &start_tick.to_le_bytes()],
+        &crate::ID,
+    )
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClSwapResult {
+    // 用户得到的数量
+    pub amount_out: u64,
+    // 手续费总额 包含协议费
+    pub fee: u64,
+    // 手续费中属于协议的部分
+    pub protocol_fee: u64,
+}
+
+impl ClPool {
+    // ExactIn 交换 amount 是金库实际收到的数量
+    // arrays 按交换方向排列 第一个包含 first_tick_array_start 未初始化的数组传 None
+    // 逐个穿过有流动性的 tick 穿过时按 liquidity_net 调整流动性并翻转 fee_growth_outside
+    pub fn swap(
+        &mut self,
+        arrays: &mut [Option<&mut TickArray>],
+        p: LiquidityPair,
+        amount: u64,
+        fee: u16,
+        protocol_fee: u16,
+    ) -> Result<ClSwapResult, ProgramError> {
+        let spacing = self.tick_spacing();
+        let first = first_tick_array_start(self.tick(), spacing, p);
+        let (mut fee_growth_x, mut fee_growth_y) =
+            (self.fee_growth_global_x(), self.fee_growth_global_y());
+        let (mut sqrt_price, mut tick, mut liquidity) =
+            (self.sqrt_price(), self.tick(), self.liquidity());
+        let mut remaining = amount;
+        let mut result = ClSwapResult {
+            amount_out: 0,
+            fee: 0,
+            protocol_fee: 0,
+        };
+
+        while remaining > 0 {
+            // 找到这一步的目标 tick 数组里没有有流动性的 tick 时走到数组的边界
+            let start = first_tick_array_start(tick, spacing, p);
+            let index =
+                (start.abs_diff(first) / (spacing as u32 * TICKS_PER_ARRAY as u32)) as usize;
+            let array = arrays.get_mut(index).ok_or(AmmError::InvalidTickArray)?;
+            let next = array
+                .as_ref()
+                .and_then(|array| array.next_initialized(tick, spacing, p == LiquidityPair::X));
+            let (target, initialized) = match (next, p) {
+                (Some(next), _) => (next, true),
+                (None, LiquidityPair::X) => (start.max(MIN_TICK), false),
+                (None, LiquidityPair::Y) => (
+                    (start + (TICKS_PER_ARRAY as i32 - 1) * spacing as i32).min(MAX_TICK),
+                    false,
+                ),
+            };
+            let sqrt_price_target = sqrt_price_at_tick(target)?;
+
+            let (sqrt_price_next, amount_in, amount_out, fee_amount) =
+                compute_swap_step(sqrt_price, sqrt_price_target, liquidity, remaining, fee)?;
+            remaining -= amount_in + fee_amount;
+            result.amount_out = result
+                .amount_out
+                .checked_add(amount_out)
+                .ok_or(AmmError::CurveOverflow)?;
+            result.fee += fee_amount;
+
+            // LP 的手续费按当前流动性累加到 fee_growth_global 没有流动性时这一步不收手续费
+            let (lp_fee, protocol) = ConstantProduct::split_fee(fee_amount, protocol_fee)?;
+            result.protocol_fee += protocol;
+            if lp_fee > 0 && liquidity > 0 {
+                let growth = mul_div(lp_fee as u128, Q64, liquidity)?;
+                match p {
+                    LiquidityPair::X => fee_growth_x = fee_growth_x.wrapping_add(growth),
+                    LiquidityPair::Y => fee_growth_y = fee_growth_y.wrapping_add(growth),
+                }
+            }
+
+            sqrt_price = sqrt_price_next;
+            if sqrt_price_next == sqrt_price_target {
+                if initialized {
+                    let array = array.as_mut().ok_or(AmmError::InvalidTickArray)?;
+                    let net = array
+                        .tick_mut(target, spacing)?
+                        .cross(fee_growth_x, fee_growth_y);
+                    // 价格下降时从右向左穿过 流动性的变化方向相反
+                    let delta = match p {
+                        LiquidityPair::X => net.checked_neg().ok_or(AmmError::CurveOverflow)?,
+                        LiquidityPair::Y => net,
+                    };
+                    liquidity = liquidity
+                        .checked_add_signed(delta)
+                        .ok_or(AmmError::InsufficientLiquidity)?;
+                }
+                // 价格已经到了边界还有剩余 池子没有足够的流动性
+                if remaining > 0 && (target == MIN_TICK || target == MAX_TICK) {
+                    return Err(AmmError::InsufficientLiquidity.into());
+                }
+                tick = match p {
+                    LiquidityPair::X => target - 1,
+                    LiquidityPair::Y => target,
+                };
+            } else {
+                tick = tick_at_sqrt_price(sqrt_price)?;
+            }
+        }
+
+        self.set_price(sqrt_price, tick);
+        self.set_liquidity(liquidity);
+        self.set_fee_growth_global(fee_growth_x, fee_growth_y);
+        Ok(result)
+    }
+
+    // 按 delta 增减头寸的流动性 并更新两个边界 tick 和当前区间的流动性
+    // 返回需要存入 (向上取整) 或者可以取出 (向下取整) 的代币数量
+    pub fn modify_position(
+        &mut self,
+        position: &mut Position,
+        lower: &mut Tick,
+        upper: &mut Tick,
+        delta: i128,
+    ) -> Result<(u64, u64), ProgramError> {
+        let (tick_lower, tick_upper) = (position.tick_lower(), position.tick_upper());
+        let (tick, global_x, global_y) = (
+            self.tick(),
+            self.fee_growth_global_x(),
+            self.fee_growth_global_y(),
+        );
+        lower.update(tick_lower, tick, delta, global_x, global_y, false)?;
+        upper.update(tick_upper, tick, delta, global_x, global_y, true)?;
+        let (inside_x, inside_y) = fee_growth_inside(
+            tick_lower, lower, tick_upper, upper, tick, global_x, global_y,
+        );
+        position.update(delta, inside_x, inside_y)?;
+        // 没有头寸再使用的 tick 恢复成未初始化 之后交换不会再穿过它
+        if delta < 0 {
+            if !lower.is_initialized() {
+                lower.clear();
+            }
+            if !upper.is_initialized() {
+                upper.clear();
+            }
+        }
+
+        let round_up = delta > 0;
+        let liquidity = delta.unsigned_abs();
+        let sqrt_lower = sqrt_price_at_tick(tick_lower)?;
+        let sqrt_upper = sqrt_price_at_tick(tick_upper)?;
+        let (amount_x, amount_y) = if tick < tick_lower {
+            // 价格在区间下方 头寸全部是 X
+            (
+                amount_x_delta(sqrt_lower, sqrt_upper, liquidity, round_up)?,
+                0,
+            )
+        } else if tick < tick_upper {
+            // 价格在区间内 头寸同时持有两种代币 并且参与当前的交换
+            self.add_liquidity(delta)?;
+            (
+                amount_x_delta(self.sqrt_price(), sqrt_upper, liquidity, round_up)?,
+                amount_y_delta(sqrt_lower, self.sqrt_price(), liquidity, round_up)?,
+            )
+        } else {
+            // 价格在区间上方 头寸全部是 Y
+            (
+                0,
+                amount_y_delta(sqrt_lower, sqrt_upper, liquidity, round_up)?,
+            )
+        };
+        Ok((
+            u64::try_from(amount_x).map_err(|_| AmmError::CurveOverflow)?,
+            u64::try_from(amount_y).map_err(|_| AmmError::CurveOverflow)?,
+        ))
+    }
+}
@@ -0,0 +1,380 @@
+use crate::{AmmError, StableSwap};
+use pinocchio::error::ProgramError;
+
+// 恒定乘积曲线 x * y = k
+// Swap、Deposit、Withdraw 以及报价用到的所有数学计算都在这里 链上和链下共用同一份实现
+// 所有中间结果都在 u128 中计算 每一次除法都明确了取整方向 并且总是让池子占便宜：
+// - 用户得到的数量向下取整
+// - 用户需要支付的数量向上取整
+// 这里只依赖 core 没有任何分配 可以在 no_std 环境中使用
+
+// 基点的分母 1 基点 = 0.01%
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+// 首次注入流动性时永久锁定的 LP 数量
+// 锁定的这部分 LP 不属于任何人 首个 LP 无法通过操纵极小的供应量抬高 LP 的单价
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LiquidityPair {
+    // 支付 X 获取 Y
+    X,
+    // 支付 Y 获取 X
+    Y,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ConstantProduct {
+    x: u64,   // Balance of Token X
+    y: u64,   // Balance of Token Y
+    fee: u16, // Fee in basis points, ie: 100 = 1%
+}
+
+impl ConstantProduct {
+    // Create a new Constant Product Curve
+    pub fn init(x: u64, y: u64, fee: u16) -> Result<ConstantProduct, ProgramError> {
+        if x == 0 || y == 0 {
+            return Err(AmmError::InsufficientLiquidity.into());
+        }
+        if fee as u64 >= BPS_DENOMINATOR {
+            return Err(AmmError::InvalidFee.into());
+        }
+        Ok(ConstantProduct { x, y, fee })
+    }
+
+    #[inline(always)]
+    pub fn x(&self) -> u64 {
+        self.x
+    }
+    #[inline(always)]
+    pub fn y(&self) -> u64 {
+        self.y
+    }
+    #[inline(always)]
+    pub fn k(&self) -> u128 {
+        self.x as u128 * self.y as u128
+    }
+
+    // 按交换方向返回 (支付一侧的储备, 获取一侧的储备)
+    #[inline(always)]
+    fn reserves(&self, p: LiquidityPair) -> (u64, u64) {
+        match p {
+            LiquidityPair::X => (self.x, self.y),
+            LiquidityPair::Y => (self.y, self.x),
+        }
+    }
+
+    #[inline(always)]
+    fn set_reserves(&mut self, p: LiquidityPair, reserve_in: u64, reserve_out: u64) {
+        match p {
+            LiquidityPair::X => {
+                self.x = reserve_in;
+                self.y = reserve_out;
+            }
+            LiquidityPair::Y => {
+                self.x = reserve_out;
+                self.y = reserve_in;
+            }
+        }
+    }
+
+    // p: x代表 支付x 获取y；y代表 支付y 获取x
+    // a: 愿意支付的代币数量
+    // min: 愿意接受的最小代币数量
+    // 返回 (支付的数量, 手续费, 得到的数量)
+    // 手续费留在池子里归 LP 所以支付一侧的储备增加的是完整的 a
+    pub fn swap(
+        &mut self,
+        p: LiquidityPair,
+        a: u64,
+        min: u64,
+    ) -> Result<(u64, u64, u64), ProgramError> {
+        let (reserve_in, reserve_out) = self.reserves(p);
+        // 扣掉手续费之后实际参与定价的数量 向下取整 相当于手续费向上取整
+        let a2 = mul_div_floor(a, BPS_DENOMINATOR - self.fee as u64, BPS_DENOMINATOR)?;
+        let out = Self::delta_out(reserve_in, reserve_out, a2)?;
+        if out < min {
+            return Err(AmmError::SlippageExceeded.into());
+        }
+        let fee = a - a2;
+        let new_in = reserve_in.checked_add(a).ok_or(AmmError::CurveOverflow)?;
+        self.set_reserves(p, new_in, reserve_out - out);
+
+        Ok((a, fee, out))
+    }
+
+    // p: x代表 支付x 获取y；y代表 支付y 获取x
+    // out: 希望得到的代币数量
+    // max_in: 愿意支付的最大代币数量
+    // 和 swap 相反 由得到的数量反推需要支付的数量
+    // 返回值和 swap 一样是 (支付的数量, 手续费, 得到的数量)
+    pub fn swap_exact_out(
+        &mut self,
+        p: LiquidityPair,
+        out: u64,
+        max_in: u64,
+    ) -> Result<(u64, u64, u64), ProgramError> {
+        let (reserve_in, reserve_out) = self.reserves(p);
+        let a2 = Self::delta_in(reserve_in, reserve_out, out)?;
+        // 加上手续费之后用户需要支付的数量 向上取整
+        let a = mul_div_ceil(a2, BPS_DENOMINATOR, BPS_DENOMINATOR - self.fee as u64)?;
+        // 如果需要支付的数量大于用户愿意支付的最大值 报错返回
+        if a > max_in {
+            return Err(AmmError::SlippageExceeded.into());
+        }
+        let fee = a - a2;
+        let new_in = reserve_in.checked_add(a).ok_or(AmmError::CurveOverflow)?;
+        self.set_reserves(p, new_in, reserve_out - out);
+
+        Ok((a, fee, out))
+    }
+
+    // 单边注入 a 个支付一侧的代币时 需要先在池子里换成另一侧代币的数量 见 Curve::zap_swap_amount
+    pub fn zap_swap_amount(&self, p: LiquidityPair, a: u64) -> Result<u64, ProgramError> {
+        Curve::ConstantProduct(*self).zap_swap_amount(p, a)
+    }
+
+    // 支付 a (已扣除手续费) 能得到的数量
+    // 新的储备 reserve_out' = ceil(k / (reserve_in + a)) 向上取整 保证交换之后 k 不会变小
+    // out = reserve_out - reserve_out'
+    pub fn delta_out(reserve_in: u64, reserve_out: u64, a: u64) -> Result<u64, ProgramError> {
+        let k = Self::k_from_xy(reserve_in, reserve_out)?;
+        let new_in = reserve_in as u128 + a as u128;
+        let new_out = k.div_ceil(new_in) as u64;
+        Ok(reserve_out - new_out)
+    }
+
+    // 得到 out 需要支付的数量 (未加手续费)
+    // (reserve_in + a) * (reserve_out - out) >= reserve_in * reserve_out
+    // a = ceil(reserve_in * out / (reserve_out - out))
+    pub fn delta_in(reserve_in: u64, reserve_out: u64, out: u64) -> Result<u64, ProgramError> {
+        Self::k_from_xy(reserve_in, reserve_out)?;
+        // 不能把池子里的代币全部取走
+        if out >= reserve_out {
+            return Err(AmmError::InsufficientLiquidity.into());
+        }
+        mul_div_ceil(reserve_in, out, reserve_out - out)
+    }
+
+    // 铸造 a 个 LP 代币需要存入的 (x, y) 向上取整
+    // x: 库存的x代币数量
+    // y: 库存的y代币数量
+    // l: 流动性代币lp的流通量
+    // a: 用户希望接收的 LP 代币数量
+    pub fn xy_deposit_amounts_from_l(
+        x: u64,
+        y: u64,
+        l: u64,
+        a: u64,
+    ) -> Result<(u64, u64), ProgramError> {
+        if l == 0 {
+            return Err(AmmError::InsufficientLiquidity.into());
+        }
+        Ok((mul_div_ceil(x, a, l)?, mul_div_ceil(y, a, l)?))
+    }
+
+    // 首次注入 (x, y) 时铸造给用户的 LP 数量 向下取整
+    // LP 总量为 sqrt(x * y) 其中 MINIMUM_LIQUIDITY 永久锁定 剩下的归用户
+    pub fn l_from_initial_xy(x: u64, y: u64) -> Result<u64, ProgramError> {
+        let l = isqrt(Self::k_from_xy(x, y)?);
+        if l <= MINIMUM_LIQUIDITY {
+            return Err(AmmError::InsufficientLiquidity.into());
+        }
+        Ok(l - MINIMUM_LIQUIDITY)
+    }
+
+    // 存入 (dx, dy) 可以铸造的 LP 数量 按两侧中较少的一侧计算 向下取整
+    // 单边注入时两侧的比例不可能完全精确 多出来的零头留在池子里归 LP
+    pub fn l_from_xy(x: u64, y: u64, l: u64, dx: u64, dy: u64) -> Result<u64, ProgramError> {
+        if x == 0 || y == 0 || l == 0 {
+            return Err(AmmError::InsufficientLiquidity.into());
+        }
+        Ok(mul_div_floor(dx, l, x)?.min(mul_div_floor(dy, l, y)?))
+    }
+
+    // 销毁 a 个 LP 代币可以取回的 (x, y) 向下取整
+    // x: 库存的x代币数量
+    // y: 库存的y代币数量
+    // l: 流动性代币lp的流通量
+    // a: 用户希望销毁的 LP 代币数量
+    pub fn xy_withdraw_amounts_from_l(
+        x: u64,
+        y: u64,
+        l: u64,
+        a: u64,
+    ) -> Result<(u64, u64), ProgramError> {
+        if l == 0 {
+            return Err(AmmError::InsufficientLiquidity.into());
+        }
+        if a > l {
+            return Err(AmmError::CurveOverflow.into());
+        }
+        Ok((mul_div_floor(x, a, l)?, mul_div_floor(y, a, l)?))
+    }
+
+    // 把交换费拆分成 (LP 部分, 协议部分)
+    // protocol_fee 是协议从交换费中抽取的基点 协议部分向下取整 零头留给 LP
+    pub fn split_fee(fee: u64, protocol_fee: u16) -> Result<(u64, u64), ProgramError> {
+        if protocol_fee as u64 > BPS_DENOMINATOR {
+            return Err(AmmError::InvalidFee.into());
+        }
+        let protocol = mul_div_floor(fee, protocol_fee as u64, BPS_DENOMINATOR)?;
+        Ok((fee - protocol, protocol))
+    }
+
+    // 价格影响 以基点表示
+    // 按交换前的现货价格 net_in 应该换到的数量和实际得到的 out 之差 占应得数量的比例
+    // net_in 是扣掉手续费之后参与定价的数量 所以结果只反映曲线本身的滑点
+    pub fn price_impact(
+        reserve_in: u64,
+        reserve_out: u64,
+        net_in: u64,
+        out: u64,
+    ) -> Result<u64, ProgramError> {
+        if reserve_in == 0 {
+            return Err(AmmError::InsufficientLiquidity.into());
+        }
+        let spot_out = net_in as u128 * reserve_out as u128 / reserve_in as u128;
+        if spot_out == 0 {
+            return Ok(0);
+        }
+        Ok((spot_out.saturating_sub(out as u128) * BPS_DENOMINATOR as u128 / spot_out) as u64)
+    }
+
+    // Static Invariant calculation
+    pub fn k_from_xy(x: u64, y: u64) -> Result<u128, ProgramError> {
+        if x == 0 || y == 0 {
+            return Err(AmmError::InsufficientLiquidity.into());
+        }
+        Ok(x as u128 * y as u128)
+    }
+}
+
+// 池子使用的曲线 Swap、Quote、RouteSwap 和 Zap 通过它分发到具体的曲线
+// Deposit 和 Withdraw 按储备比例计算 和曲线类型无关
+#[derive(Clone, Copy, Debug)]
+pub enum Curve {
+    ConstantProduct(ConstantProduct),
+    StableSwap(StableSwap),
+}
+
+impl Curve {
+    #[inline(always)]
+    pub fn x(&self) -> u64 {
+        match self {
+            Curve::ConstantProduct(c) => c.x(),
+            Curve::StableSwap(c) => c.x(),
+        }
+    }
+    #[inline(always)]
+    pub fn y(&self) -> u64 {
+        match self {
+            Curve::ConstantProduct(c) => c.y(),
+            Curve::StableSwap(c) => c.y(),
+        }
+    }
+
+    #[inline(always)]
+    fn reserves(&self, p: LiquidityPair) -> (u64, u64) {
+        match p {
+            LiquidityPair::X => (self.x(), self.y()),
+            LiquidityPair::Y => (self.y(), self.x()),
+        }
+    }
+
+    pub fn swap(
+        &mut self,
+        p: LiquidityPair,
+        a: u64,
+        min: u64,
+    ) -> Result<(u64, u64, u64), ProgramError> {
+        match self {
+            Curve::ConstantProduct(c) => c.swap(p, a, min),
+            Curve::StableSwap(c) => c.swap(p, a, min),
+        }
+    }
+
+    pub fn swap_exact_out(
+        &mut self,
+        p: LiquidityPair,
+        out: u64,
+        max_in: u64,
+    ) -> Result<(u64, u64, u64), ProgramError> {
+        match self {
+            Curve::ConstantProduct(c) => c.swap_exact_out(p, out, max_in),
+            Curve::StableSwap(c) => c.swap_exact_out(p, out, max_in),
+        }
+    }
+
+    // 按交换之前的储备计算价格影响 以基点表示
+    pub fn price_impact(
+        &self,
+        p: LiquidityPair,
+        net_in: u64,
+        out: u64,
+    ) -> Result<u64, ProgramError> {
+        match self {
+            Curve::ConstantProduct(_) => {
+                let (reserve_in, reserve_out) = self.reserves(p);
+                ConstantProduct::price_impact(reserve_in, reserve_out, net_in, out)
+            }
+            Curve::StableSwap(c) => c.price_impact(p, net_in, out),
+        }
+    }
+
+    // 单边注入 a 个支付一侧的代币时 需要先在池子里换成另一侧代币的数量
+    // 找到最大的 s 使得换完之后剩下的 a - s 和换到的 out 满足 (a - s) / reserve_in' >= out / reserve_out'
+    // 这样剩下的两种代币按交换之后的比例注入 没有哪一侧明显多余
+    // 直接二分查找 每一步都走一遍 swap 保证取整和真实交换完全一致
+    pub fn zap_swap_amount(&self, p: LiquidityPair, a: u64) -> Result<u64, ProgramError> {
+        let (mut low, mut high) = (0u64, a);
+        while low < high {
+            let s = low + (high - low).div_ceil(2);
+            let mut curve = *self;
+            let (_, _, out) = curve.swap(p, s, 0)?;
+            let (reserve_in, reserve_out) = curve.reserves(p);
+            if (a - s) as u128 * reserve_out as u128 >= out as u128 * reserve_in as u128 {
+                low = s;
+            } else {
+                high = s - 1;
+            }
+        }
+        Ok(low)
+    }
+}
+
+// floor(a * b / c) 两个 u64 相乘不会超出 u128 结果超出 u64 时报错
+#[inline(always)]
+pub fn mul_div_floor(a: u64, b: u64, c: u64) -> Result<u64, ProgramError> {
+    if c == 0 {
+        return Err(AmmError::CurveOverflow.into());
+    }
+    u64::try_from(a as u128 * b as u128 / c as u128).map_err(|_| AmmError::CurveOverflow.into())
+}
+
+// ceil(a * b / c) 两个 u64 相乘不会超出 u128 结果超出 u64 时报错
+#[inline(always)]
+pub fn mul_div_ceil(a: u64, b: u64, c: u64) -> Result<u64, ProgramError> {
+    if c == 0 {
+        return Err(AmmError::CurveOverflow.into());
+    }
+    u64::try_from((a as u128 * b as u128).div_ceil(c as u128))
+        .map_err(|_| AmmError::CurveOverflow.into())
+}
+
+// floor(sqrt(n)) 牛顿迭代 两个 u64 的乘积开方后一定在 u64 范围内
+#[inline(always)]
+pub fn isqrt(n: u128) -> u64 {
+    if n < 2 {
+        return n as u64;
+    }
+    // 初始值取不小于 sqrt(n) 的 2 的幂 迭代单调递减直到收敛
+    let mut x = 1u128 << (128 - n.leading_zeros()).div_ceil(2);
+    loop {
+        let y = (x + n / x) / 2;
+        if y >= x {
+            return x as u64;
+        }
+        x = y;
+    }
+}
@@ -0,0 +1,88 @@
+use pinocchio::error::ProgramError;
+
+// AMM 的自定义错误 以 ProgramError::Custom(code) 的形式返回给客户端
+// 错误码一旦发布就不能再修改 新的错误只能追加在末尾
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum AmmError {
+    // 池子还没有初始化
+    PoolNotInitialized = 0,
+    // 池子已被禁用 所有交易都被拒绝
+    PoolDisabled = 1,
+    // 池子处于只允许提取的状态 不能再交换或存入
+    PoolWithdrawOnly = 2,
+    // 不存在的池子状态
+    InvalidPoolState = 3,
+    // 实际得到的代币数量不满足用户设置的滑点限制
+    SlippageExceeded = 4,
+    // 订单已经过期
+    Expired = 5,
+    // 传入的金库账户不是池子的金库
+    InvalidVault = 6,
+    // 金额不能为0
+    ZeroAmount = 7,
+    // 曲线计算溢出
+    CurveOverflow = 8,
+    // 池子的流动性不足（储备为0）
+    InsufficientLiquidity = 9,
+    // 费率必须小于 10_000 基点
+    InvalidFee = 10,
+    // 两次价格观测之间没有经过时间
+    StaleObservation = 11,
+    // 价格预言机账户不属于这个池子
+    InvalidOracle = 12,
+    // 路由的账户数量不对 或者相邻两跳的代币接不上
+    InvalidRoute = 13,
+    // 不存在的曲线类型
+    InvalidCurve = 14,
+    // 放大系数超出范围 或者调整得太快
+    InvalidAmp = 15,
+    // 闪电贷还没有归还 不能进行按储备计算的操作
+    FlashLoanActive = 16,
+    // 闪电贷和归还指令不匹配 或者归还的数量不足
+    InvalidFlashLoan = 17,
+    // mint_x 必须按地址字节小于 mint_y
+    InvalidMintOrder = 18,
+    // config 账户不是规范的 PDA
+    InvalidConfig = 19,
+    // LP 铸币账户不是规范的 PDA
+    InvalidLpMint = 20,
+    // LP 的小数位数超出范围
+    InvalidLpDecimals = 21,
+    // LP 元数据格式不对 或者和代币自带的元数据不一致
+    InvalidMetadata = 22,
+    // 池子工厂已暂停 不能创建新池子
+    FactoryPaused = 23,
+    // 传入的工厂账户不是规范的 PDA
+    InvalidFactory = 24,
+    // 交易对记录或注册表分页不是规范的 PDA 或者登记的位置不对
+    InvalidRegistry = 25,
+    // 订单还没到下一次执行的时间
+    OrderNotReady = 26,
+    // 订单的所有执行次数都已经完成
+    OrderFilled = 27,
+    // 订单参数不对 或者传入的账户和订单不匹配
+    InvalidOrder = 28,
+    // tick 超出范围 或者没有对齐 tick_spacing
+    InvalidTick = 29,
+    // tick 数组不是规范的 PDA 或者交换需要的 tick 数组没有全部传入
+    InvalidTickArray = 30,
+    // 集中流动性的头寸不属于这个池子或者这个用户
+    InvalidPosition = 31,
+    // 传入的农场账户不是这个池子的 或者奖励代币和 LP 不匹配
+    InvalidFarm = 32,
+    // 取回的 LP 超过了质押的数量
+    InsufficientStake = 33,
+    // Config 还是没有版本号的旧布局 需要先调用 MigrateConfig 升级
+    ConfigOutdated = 34,
+    // Config 的版本比当前程序支持的更新
+    UnsupportedConfigVersion = 35,
+    // 白名单池子的用户没有传入自己的白名单账户
+    NotAllowListed = 36,
+}
+
+impl From<AmmError> for ProgramError {
+    fn from(e: AmmError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
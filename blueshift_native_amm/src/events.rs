@@ -0,0 +1,376 @@
+use pinocchio::{error::ProgramError, Address};
+
+// AMM 的结构化事件
+// 事件通过 sol_log_data 写入交易日志 在日志里显示为 "Program data: <base64>"
+// 编码格式：版本 u8 | 事件类型 u8 | 各字段按声明顺序排列 整数都是小端
+// 索引器可以直接用 AmmEvent::from_logs 解析交易日志 不需要再去对比代币余额
+// 任何程序都能写出同样格式的 "Program data:" 日志 所以只解析本程序调用帧内的日志行
+// 字段只能追加在末尾 追加字段或修改含义时需要递增 EVENT_VERSION
+
+// 当前的事件格式版本 解码时拒绝不认识的版本
+pub const EVENT_VERSION: u8 = 1;
+// sol_log_data 写入的日志行前缀
+pub const EVENT_LOG_PREFIX: &str = "Program data: ";
+// 运行时记录程序调用和返回的日志行前缀 "Program <id> invoke [n]" "Program <id> success"
+const PROGRAM_LOG_PREFIX: &str = "Program ";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum EventKind {
+    Initialize = 0,
+    Swap = 1,
+    Deposit = 2,
+    Withdraw = 3,
+}
+
+impl TryFrom<u8> for EventKind {
+    type Error = ProgramError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(EventKind::Initialize),
+            1 => Ok(EventKind::Swap),
+            2 => Ok(EventKind::Deposit),
+            3 => Ok(EventKind::Withdraw),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+// 池子创建
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InitializeEvent {
+    // 池子的 config 账户
+    pub pool: Address,
+    // 池子的权限持有者 全零表示池子不可变
+    pub authority: Address,
+    pub mint_x: Address,
+    pub mint_y: Address,
+    pub mint_lp: Address,
+    pub seed: u64,
+    // 交换费 基点
+    pub fee: u16,
+}
+
+// 一次交换 金额都是用户一侧的数量
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapEvent {
+    pub pool: Address,
+    pub user: Address,
+    // true 表示支付 X 获取 Y
+    pub is_x: bool,
+    // 用户支付的数量
+    pub amount_in: u64,
+    // 用户收到的数量
+    pub amount_out: u64,
+    // 交换费 包括 LP 和协议两部分
+    pub fee: u64,
+    // 交换之后 LP 的储备
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+}
+
+// 注入流动性
+// 闪电贷归还时也会发出 金额是留在池子里的手续费 lp_minted 为 0
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DepositEvent {
+    pub pool: Address,
+    pub user: Address,
+    // 用户支付的数量
+    pub amount_x: u64,
+    pub amount_y: u64,
+    // 铸造给用户的 LP 数量
+    pub lp_minted: u64,
+    // 注入之后 LP 的储备
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+}
+
+// 提取流动性
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WithdrawEvent {
+    pub pool: Address,
+    pub user: Address,
+    // 从金库转出的数量
+    pub amount_x: u64,
+    pub amount_y: u64,
+    // 销毁的 LP 数量
+    pub lp_burned: u64,
+    // 提取之后 LP 的储备
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AmmEvent {
+    Initialize(InitializeEvent),
+    Swap(SwapEvent),
+    Deposit(DepositEvent),
+    Withdraw(WithdrawEvent),
+}
+
+impl AmmEvent {
+    // 最长的事件是 Initialize：2 + 32 * 5 + 8 + 2
+    pub const MAX_LEN: usize = 172;
+
+    pub fn kind(&self) -> EventKind {
+        match self {
+            AmmEvent::Initialize(_) => EventKind::Initialize,
+            AmmEvent::Swap(_) => EventKind::Swap,
+            AmmEvent::Deposit(_) => EventKind::Deposit,
+            AmmEvent::Withdraw(_) => EventKind::Withdraw,
+        }
+    }
+
+    // 编码到 buf 返回写入的长度
+    pub fn encode(&self, buf: &mut [u8; Self::MAX_LEN]) -> usize {
+        let mut w = Writer { buf, len: 0 };
+        w.u8(EVENT_VERSION);
+        w.u8(self.kind() as u8);
+        match self {
+            AmmEvent::Initialize(e) => {
+                w.address(&e.pool);
+                w.address(&e.authority);
+                w.address(&e.mint_x);
+                w.address(&e.mint_y);
+                w.address(&e.mint_lp);
+                w.u64(e.seed);
+                w.u16(e.fee);
+            }
+            AmmEvent::Swap(e) => {
+                w.address(&e.pool);
+                w.address(&e.user);
+                w.u8(e.is_x as u8);
+                w.u64(e.amount_in);
+                w.u64(e.amount_out);
+                w.u64(e.fee);
+                w.u64(e.reserve_x);
+                w.u64(e.reserve_y);
+            }
+            AmmEvent::Deposit(e) => {
+                w.address(&e.pool);
+                w.address(&e.user);
+                w.u64(e.amount_x);
+                w.u64(e.amount_y);
+                w.u64(e.lp_minted);
+                w.u64(e.reserve_x);
+                w.u64(e.reserve_y);
+            }
+            AmmEvent::Withdraw(e) => {
+                w.address(&e.pool);
+                w.address(&e.user);
+                w.u64(e.amount_x);
+                w.u64(e.amount_y);
+                w.u64(e.lp_burned);
+                w.u64(e.reserve_x);
+                w.u64(e.reserve_y);
+            }
+        }
+        w.len
+    }
+
+    // 把事件写入交易日志
+    pub fn emit(&self) {
+        let mut buf = [0u8; Self::MAX_LEN];
+        let len = self.encode(&mut buf);
+        sol_log_data(&[&buf[..len]]);
+    }
+
+    // 解码 sol_log_data 写入的原始字节
+    pub fn decode(data: &[u8]) -> Result<Self, ProgramError> {
+        let mut r = Reader { data };
+        if r.u8()? != EVENT_VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let event = match EventKind::try_from(r.u8()?)? {
+            EventKind::Initialize => AmmEvent::Initialize(InitializeEvent {
+                pool: r.address()?,
+                authority: r.address()?,
+                mint_x: r.address()?,
+                mint_y: r.address()?,
+                mint_lp: r.address()?,
+                seed: r.u64()?,
+                fee: r.u16()?,
+            }),
+            EventKind::Swap => AmmEvent::Swap(SwapEvent {
+                pool: r.address()?,
+                user: r.address()?,
+                is_x: r.u8()? != 0,
+                amount_in: r.u64()?,
+                amount_out: r.u64()?,
+                fee: r.u64()?,
+                reserve_x: r.u64()?,
+                reserve_y: r.u64()?,
+            }),
+            EventKind::Deposit => AmmEvent::Deposit(DepositEvent {
+                pool: r.address()?,
+                user: r.address()?,
+                amount_x: r.u64()?,
+                amount_y: r.u64()?,
+                lp_minted: r.u64()?,
+                reserve_x: r.u64()?,
+                reserve_y: r.u64()?,
+            }),
+            EventKind::Withdraw => AmmEvent::Withdraw(WithdrawEvent {
+                pool: r.address()?,
+                user: r.address()?,
+                amount_x: r.u64()?,
+                amount_y: r.u64()?,
+                lp_burned: r.u64()?,
+                reserve_x: r.u64()?,
+                reserve_y: r.u64()?,
+            }),
+        };
+        if !r.data.is_empty() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(event)
+    }
+
+    // 解析一笔交易的全部日志 program_id 是本程序地址的 base58 字符串
+    // 按 invoke / success / failed 记录调用栈 只解析栈顶是本程序时写入的 "Program data:" 行
+    // 其他程序 包括通过 CPI 被本程序调用的程序 写出的同样格式的数据都会被忽略
+    pub fn from_logs<'a>(program_id: &str, logs: impl IntoIterator<Item = &'a str>) -> Vec<Self> {
+        let mut stack: Vec<&str> = Vec::new();
+        let mut events = Vec::new();
+        for line in logs {
+            if let Some(encoded) = line.strip_prefix(EVENT_LOG_PREFIX) {
+                if stack.last() == Some(&program_id) {
+                    events.extend(Self::decode_log_data(encoded));
+                }
+                continue;
+            }
+            // 日志被截断之后无法知道后面的行属于哪个程序
+            if line == "Log truncated" {
+                break;
+            }
+            // "Program log: ..." 之类的行第一个词以 ':' 结尾 程序自己写的日志都带这样的前缀 不能伪造调用记录
+            let Some((id, rest)) = line
+                .strip_prefix(PROGRAM_LOG_PREFIX)
+                .and_then(|line| line.split_once(' '))
+            else {
+                continue;
+            };
+            if id.ends_with(':') {
+                continue;
+            }
+            if rest.starts_with("invoke [") {
+                stack.push(id);
+            } else if rest == "success" || rest.starts_with("failed") {
+                stack.pop();
+            }
+        }
+        events
+    }
+
+    // 解码 "Program data: " 之后的部分
+    fn decode_log_data(encoded: &str) -> Option<Self> {
+        // sol_log_data 只传了一段数据 所以日志里只有一段 base64
+        if encoded.contains(' ') {
+            return None;
+        }
+        let mut buf = [0u8; Self::MAX_LEN];
+        let len = base64_decode(encoded.as_bytes(), &mut buf)?;
+        Self::decode(&buf[..len]).ok()
+    }
+}
+
+#[inline(always)]
+fn sol_log_data(data: &[&[u8]]) {
+    #[cfg(target_os = "solana")]
+    unsafe {
+        pinocchio::syscalls::sol_log_data(data.as_ptr() as *const u8, data.len() as u64);
+    }
+    #[cfg(not(target_os = "solana"))]
+    core::hint::black_box(data);
+}
+
+struct Writer<'a> {
+    buf: &'a mut [u8; AmmEvent::MAX_LEN],
+    len: usize,
+}
+
+impl Writer<'_> {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+    fn u8(&mut self, v: u8) {
+        self.bytes(&[v]);
+    }
+    fn u16(&mut self, v: u16) {
+        self.bytes(&v.to_le_bytes());
+    }
+    fn u64(&mut self, v: u64) {
+        self.bytes(&v.to_le_bytes());
+    }
+    fn address(&mut self, v: &Address) {
+        self.bytes(v.as_ref());
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], ProgramError> {
+        if self.data.len() < N {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (head, tail) = self.data.split_at(N);
+        self.data = tail;
+        Ok(head.try_into().unwrap())
+    }
+    fn u8(&mut self) -> Result<u8, ProgramError> {
+        Ok(self.take::<1>()?[0])
+    }
+    fn u16(&mut self) -> Result<u16, ProgramError> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+    fn u64(&mut self) -> Result<u64, ProgramError> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+    fn address(&mut self) -> Result<Address, ProgramError> {
+        Ok(Address::new_from_array(self.take()?))
+    }
+}
+
+// 标准 base64 解码 (带 = 填充) 结果超出 out 的长度时返回 None
+fn base64_decode(input: &[u8], out: &mut [u8]) -> Option<usize> {
+    fn value(c: u8) -> Option<u32> {
+        match c {
+            b'A'..=b'Z' => Some((c - b'A') as u32),
+            b'a'..=b'z' => Some((c - b'a' + 26) as u32),
+            b'0'..=b'9' => Some((c - b'0' + 52) as u32),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
+    if !input.len().is_multiple_of(4) {
+        return None;
+    }
+    let last = input.len() / 4;
+    let mut len = 0;
+    for (i, chunk) in input.chunks_exact(4).enumerate() {
+        // 只有最后一组可以有填充
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && i + 1 != last) {
+            return None;
+        }
+        let mut n = 0u32;
+        for &c in &chunk[..4 - padding] {
+            n = (n << 6) | value(c)?;
+        }
+        n <<= 6 * padding as u32;
+        let bytes = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        let count = 3 - padding;
+        if len + count > out.len() {
+            return None;
+        }
+        out[len..len + count].copy_from_slice(&bytes[..count]);
+        len += count;
+    }
+    Some(len)
+}
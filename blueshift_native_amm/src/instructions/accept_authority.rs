@@ -0,0 +1,59 @@
+use crate::instructions::helper::{AccountCheck, ProgramAccount, SignerAccount};
+use crate::Config;
+use pinocchio::{error::ProgramError, AccountView, Address, ProgramResult};
+
+pub struct AcceptAuthorityAccounts<'a> {
+    // 通过 TransferAuthority 被提名的新权限持有者。
+    pub pending_authority: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for AcceptAuthorityAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [pending_authority, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(pending_authority)?;
+        ProgramAccount::check(config)?;
+        Ok(Self {
+            pending_authority,
+            config,
+        })
+    }
+}
+
+// 两步转移权限的第二步：被提名的地址签名确认后 正式成为新的权限持有者
+pub struct AcceptAuthority<'a> {
+    pub accounts: AcceptAuthorityAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for AcceptAuthority<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let accounts = AcceptAuthorityAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> AcceptAuthority<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+        // 没有设置权限的池子不允许通过提名获得权限
+        if config.has_authority().is_none() {
+            return Err(ProgramError::Immutable);
+        }
+        let pending_authority = config
+            .has_pending_authority()
+            .ok_or(ProgramError::InvalidAccountData)?;
+        if pending_authority.ne(self.accounts.pending_authority.address()) {
+            return Err(ProgramError::IncorrectAuthority);
+        }
+        config.set_authority(pending_authority)?;
+        config.set_pending_authority(Address::from([0u8; 32]))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use crate::instructions::helper::{
+    AccountCheck, ProgramAccount, ProgramAccountInit, SignerAccount,
+};
+use crate::{AllowListEntry, AmmError, Config, ProgramState};
+use pinocchio::{cpi::Seed, error::ProgramError, AccountView, Address, ProgramResult};
+
+pub struct AddToAllowListAccounts<'a> {
+    // AMM 池的权限持有者 支付白名单账户的租金。
+    pub authority: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+    // 要创建的白名单账户。
+    pub entry: &'a AccountView,
+    // 系统程序账户。
+    pub system_program: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for AddToAllowListAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [authority, config, entry, system_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        ProgramAccount::check(config)?;
+        Ok(Self {
+            authority,
+            config,
+            entry,
+            system_program,
+        })
+    }
+}
+
+pub struct AddToAllowListInstructionData {
+    // 加入白名单的用户地址。
+    pub user: Address,
+}
+
+impl TryFrom<&[u8]> for AddToAllowListInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let user: [u8; 32] = data
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        Ok(Self {
+            user: Address::new_from_array(user),
+        })
+    }
+}
+
+// 把用户加入池子的白名单 白名单只在池子开启 permissioned 时生效
+pub struct AddToAllowList<'a> {
+    pub accounts: AddToAllowListAccounts<'a>,
+    pub instruction_data: AddToAllowListInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for AddToAllowList<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = AddToAllowListAccounts::try_from(accounts)?;
+        let instruction_data = AddToAllowListInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> AddToAllowList<'a> {
+    pub fn process(&self) -> ProgramResult {
+        Config::load(self.accounts.config)?.check_authority(self.accounts.authority.address())?;
+
+        let user = &self.instruction_data.user;
+        let (entry, bump) = Address::find_program_address(
+            &[
+                b"allow",
+                self.accounts.config.address().as_ref(),
+                user.as_ref(),
+            ],
+            &crate::ID,
+        );
+        if entry.ne(self.accounts.entry.address()) {
+            return Err(AmmError::NotAllowListed.into());
+        }
+        let bump_binding = [bump];
+        let entry_seeds = [
+            Seed::from(b"allow"),
+            Seed::from(self.accounts.config.address().as_ref()),
+            Seed::from(user.as_ref()),
+            Seed::from(&bump_binding),
+        ];
+        // 账户已经存在时创建会失败 重复加入白名单直接报错
+        ProgramAccount::init::<AllowListEntry>(
+            self.accounts.authority,
+            self.accounts.entry,
+            &entry_seeds,
+            AllowListEntry::LEN,
+        )?;
+        AllowListEntry::load_mut(self.accounts.entry)?.set_inner(
+            *self.accounts.config.address(),
+            *user,
+            bump_binding,
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+use crate::instructions::helper::{
+    AccountCheck, AccountClose, AssociatedTokenAccount, AssociatedTokenAccountCheck, MintInterface,
+    ProgramAccount, SignerAccount,
+};
+use crate::instructions::token_interface::{
+    check_mint, has_transfer_fee_config, mint_unchecked, token_account_unchecked, CloseAccount,
+    TokenProgram, TransferChecked,
+};
+use crate::{Order, ProgramState};
+use pinocchio::cpi::{Seed, Signer};
+use pinocchio::{error::ProgramError, AccountView, ProgramResult};
+
+pub struct CancelOrderAccounts<'a> {
+    // 下单的用户。必须与订单中记录的 owner 一致。
+    pub owner: &'a AccountView,
+    // 要取消的订单账户。
+    pub order: &'a AccountView,
+    // 托管输入代币的账户。
+    pub escrow: &'a AccountView,
+    // 用户接收退回代币的关联账户。
+    pub owner_ata: &'a AccountView,
+    // 输入代币的铸币账户。
+    pub mint_in: &'a AccountView,
+    // 输入代币所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_in: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for CancelOrderAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [owner, order, escrow, owner_ata, mint_in, token_program_in] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(owner)?;
+        MintInterface::check(mint_in)?;
+        TokenProgram::check(token_program_in)?;
+        Ok(Self {
+            owner,
+            order,
+            escrow,
+            owner_ata,
+            mint_in,
+            token_program_in,
+        })
+    }
+}
+
+// 取消订单 退回托管的剩余代币并关闭订单 已经全部执行完的订单也用它回收租金
+pub struct CancelOrder<'a> {
+    pub accounts: CancelOrderAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for CancelOrder<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let accounts = CancelOrderAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> CancelOrder<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let order = Order::load(self.accounts.order)?;
+        if order.owner().ne(self.accounts.owner.address()) {
+            return Err(ProgramError::IncorrectAuthority);
+        }
+        check_mint(
+            self.accounts.mint_in,
+            order.mint_in(),
+            self.accounts.token_program_in,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.escrow,
+            self.accounts.order,
+            order.mint_in(),
+            self.accounts.token_program_in,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.owner_ata,
+            self.accounts.owner,
+            order.mint_in(),
+            self.accounts.token_program_in,
+        )?;
+
+        let seed_binding = order.seed().to_le_bytes();
+        let bump = order.bump();
+        let order_seeds = [
+            Seed::from(b"order"),
+            Seed::from(order.config().as_ref()),
+            Seed::from(order.owner().as_ref()),
+            Seed::from(&seed_binding),
+            Seed::from(&bump),
+        ];
+        let order_signer = [Signer::from(&order_seeds)];
+
+        let remaining = unsafe { token_account_unchecked(self.accounts.escrow)? }.amount();
+        if remaining > 0 {
+            let mint_in = unsafe { mint_unchecked(self.accounts.mint_in)? };
+            TransferChecked {
+                from: self.accounts.escrow,
+                mint: self.accounts.mint_in,
+                to: self.accounts.owner_ata,
+                authority: self.accounts.order,
+                amount: remaining,
+                decimals: mint_in.decimals(),
+                token_program: self.accounts.token_program_in,
+            }
+            .invoke_signed(&order_signer)?;
+        }
+        // 带转账费的代币 托管账户里可能有扣留的手续费 关闭会失败 只能留下空的托管账户
+        if !has_transfer_fee_config(self.accounts.mint_in)? {
+            CloseAccount {
+                account: self.accounts.escrow,
+                destination: self.accounts.owner,
+                authority: self.accounts.order,
+                token_program: self.accounts.token_program_in,
+            }
+            .invoke_signed(&order_signer)?;
+        }
+
+        drop(order);
+        ProgramAccount::close(self.accounts.order, self.accounts.owner)
+    }
+}
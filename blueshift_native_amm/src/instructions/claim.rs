@@ -0,0 +1,128 @@
+use crate::instructions::helper::{
+    AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck, MintInterface, SignerAccount,
+};
+use crate::instructions::token_interface::{
+    check_mint, mint_unchecked, TokenProgram, TransferChecked,
+};
+use crate::{AmmError, Farm, ProgramState, StakePosition};
+use pinocchio::{
+    cpi::{Seed, Signer},
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+
+pub struct ClaimAccounts<'a> {
+    // 领取奖励的用户。
+    pub owner: &'a AccountView,
+    // 用户的奖励代币关联账户。
+    pub owner_reward_ata: &'a AccountView,
+    // 用户在农场中的质押账户。
+    pub stake: &'a AccountView,
+    // 池子的农场账户。
+    pub farm: &'a AccountView,
+    // 奖励代币的铸币账户。
+    pub reward_mint: &'a AccountView,
+    // 农场持有奖励代币的关联账户。
+    pub reward_vault: &'a AccountView,
+    // 奖励代币所属的代币程序账户 SPL Token 或 Token-2022。
+    pub reward_token_program: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for ClaimAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [owner, owner_reward_ata, stake, farm, reward_mint, reward_vault, reward_token_program] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(owner)?;
+        MintInterface::check(reward_mint)?;
+        TokenProgram::check(reward_token_program)?;
+        Ok(Self {
+            owner,
+            owner_reward_ata,
+            stake,
+            farm,
+            reward_mint,
+            reward_vault,
+            reward_token_program,
+        })
+    }
+}
+
+// 领取质押产生的全部奖励 没有奖励时什么也不做
+pub struct Claim<'a> {
+    pub accounts: ClaimAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for Claim<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let accounts = ClaimAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> Claim<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let mut farm = Farm::load_mut(self.accounts.farm)?;
+        check_mint(
+            self.accounts.reward_mint,
+            farm.reward_mint(),
+            self.accounts.reward_token_program,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.owner_reward_ata,
+            self.accounts.owner,
+            farm.reward_mint(),
+            self.accounts.reward_token_program,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.reward_vault,
+            self.accounts.farm,
+            farm.reward_mint(),
+            self.accounts.reward_token_program,
+        )?;
+        farm.update(Clock::get()?.unix_timestamp)?;
+
+        let mut stake = StakePosition::load_mut(self.accounts.stake)?;
+        if stake.owner().ne(self.accounts.owner.address())
+            || stake.farm().ne(self.accounts.farm.address())
+        {
+            return Err(AmmError::InvalidFarm.into());
+        }
+        stake.settle(farm.reward_per_share())?;
+        let rewards = stake.take_rewards();
+        drop(stake);
+        if rewards == 0 {
+            return Ok(());
+        }
+
+        // 构建farm签名
+        let config = *farm.config();
+        let bump = farm.bump();
+        drop(farm);
+        let farm_seeds = [
+            Seed::from(b"farm"),
+            Seed::from(config.as_ref()),
+            Seed::from(&bump),
+        ];
+        let farm_signer = [Signer::from(&farm_seeds)];
+
+        let reward_mint = unsafe { mint_unchecked(self.accounts.reward_mint)? };
+        TransferChecked {
+            from: self.accounts.reward_vault,
+            mint: self.accounts.reward_mint,
+            to: self.accounts.owner_reward_ata,
+            authority: self.accounts.farm,
+            amount: rewards,
+            decimals: reward_mint.decimals(),
+            token_program: self.accounts.reward_token_program,
+        }
+        .invoke_signed(&farm_signer)
+    }
+}
@@ -0,0 +1,144 @@
+use crate::instructions::helper::{
+    AccountCheck, MintInterface, ProgramAccount, SignerAccount, TokenAccount,
+};
+use crate::instructions::token_interface::{
+    check_mint, mint_unchecked, TokenProgram, TransferChecked,
+};
+use crate::Config;
+use pinocchio::cpi::{Seed, Signer};
+use pinocchio::{error::ProgramError, AccountView, ProgramResult};
+
+pub struct CollectProtocolFeesAccounts<'a> {
+    // AMM 池的权限持有者。必须与 config 中记录的 authority 一致。
+    pub authority: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+    // 存储所有存入池中的 X 代币的代币账户。
+    pub vault_x: &'a AccountView,
+    // 存储所有存入池中的 Y 代币的代币账户。
+    pub vault_y: &'a AccountView,
+    // 接收 X 代币协议费的国库代币账户。
+    pub treasury_x_ata: &'a AccountView,
+    // 接收 Y 代币协议费的国库代币账户。
+    pub treasury_y_ata: &'a AccountView,
+    // 池中代币 X 的铸币账户。Token-2022 的 TransferChecked 需要用到。
+    pub mint_x: &'a AccountView,
+    // 池中代币 Y 的铸币账户。
+    pub mint_y: &'a AccountView,
+    // 代币 X 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_x: &'a AccountView,
+    // 代币 Y 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_y: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for CollectProtocolFeesAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [authority, config, vault_x, vault_y, treasury_x_ata, treasury_y_ata, mint_x, mint_y, token_program_x, token_program_y] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        ProgramAccount::check(config)?;
+        TokenAccount::check(treasury_x_ata)?;
+        TokenAccount::check(treasury_y_ata)?;
+        MintInterface::check(mint_x)?;
+        MintInterface::check(mint_y)?;
+        TokenProgram::check(token_program_x)?;
+        TokenProgram::check(token_program_y)?;
+        Ok(Self {
+            authority,
+            config,
+            vault_x,
+            vault_y,
+            treasury_x_ata,
+            treasury_y_ata,
+            mint_x,
+            mint_y,
+            token_program_x,
+            token_program_y,
+        })
+    }
+}
+
+// 把 Swap 中累计的协议费从金库转到国库账户 只有权限持有者可以调用
+pub struct CollectProtocolFees<'a> {
+    pub accounts: CollectProtocolFeesAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for CollectProtocolFees<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let accounts = CollectProtocolFeesAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> CollectProtocolFees<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+        config.check_authority(self.accounts.authority.address())?;
+        // 闪电贷借出期间不能从金库转出协议费
+        config.check_no_flash_loan()?;
+        check_mint(self.accounts.mint_x, config.mint_x(), self.accounts.token_program_x)?;
+        check_mint(self.accounts.mint_y, config.mint_y(), self.accounts.token_program_y)?;
+
+        config.check_vaults(
+            self.accounts.vault_x.address(),
+            self.accounts.vault_y.address(),
+        )?;
+
+        let protocol_fees_x = config.protocol_fees_x();
+        let protocol_fees_y = config.protocol_fees_y();
+        let mint_x = unsafe { mint_unchecked(self.accounts.mint_x)? };
+        let mint_y = unsafe { mint_unchecked(self.accounts.mint_y)? };
+
+        // 构建config签名
+        let seed_bytes = config.seed().to_le_bytes();
+        let mut seed_array = [0u8; 8];
+        seed_array.copy_from_slice(&seed_bytes);
+        let bump = config.config_bump();
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(&seed_array),
+            Seed::from(config.mint_x().as_ref()),
+            Seed::from(config.mint_y().as_ref()),
+            Seed::from(&bump),
+        ];
+
+        let config_signer = [Signer::from(&config_seeds)];
+        if protocol_fees_x > 0 {
+            TransferChecked {
+                from: self.accounts.vault_x,
+                mint: self.accounts.mint_x,
+                to: self.accounts.treasury_x_ata,
+                authority: self.accounts.config,
+                amount: protocol_fees_x,
+                decimals: mint_x.decimals(),
+                token_program: self.accounts.token_program_x,
+            }
+            .invoke_signed(&config_signer)?;
+        }
+        if protocol_fees_y > 0 {
+            TransferChecked {
+                from: self.accounts.vault_y,
+                mint: self.accounts.mint_y,
+                to: self.accounts.treasury_y_ata,
+                authority: self.accounts.config,
+                amount: protocol_fees_y,
+                decimals: mint_y.decimals(),
+                token_program: self.accounts.token_program_y,
+            }
+            .invoke_signed(&config_signer)?;
+        }
+
+        // 协议费已经转出 清空计数
+        drop(config);
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.clear_protocol_fees()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,246 @@
+use crate::instructions::helper::{
+    AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck, MintInterface,
+    ProgramAccount, SignerAccount,
+};
+use crate::instructions::increase_liquidity::modify_liquidity;
+use crate::instructions::token_interface::{
+    check_mint, mint_unchecked, TokenProgram, TransferChecked, TransferFee,
+};
+use crate::{AmmError, Config, Position, ProgramState};
+use pinocchio::cpi::{Seed, Signer};
+use pinocchio::{
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+
+pub struct DecreaseLiquidityAccounts<'a> {
+    // 头寸的所有者 接收取出的代币和手续费。
+    pub owner: &'a AccountView,
+    // 所有者的代币 X 关联账户。
+    pub owner_x_ata: &'a AccountView,
+    // 所有者的代币 Y 关联账户。
+    pub owner_y_ata: &'a AccountView,
+    // 要减少流动性的头寸账户。
+    pub position: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+    // 池子的集中流动性状态账户。
+    pub cl_pool: &'a AccountView,
+    // 包含区间下界的 tick 数组。
+    pub tick_array_lower: &'a AccountView,
+    // 包含区间上界的 tick 数组 可以和 tick_array_lower 是同一个账户。
+    pub tick_array_upper: &'a AccountView,
+    // 持有所有存入池中的代币 X 的代币账户。
+    pub vault_x: &'a AccountView,
+    // 持有所有存入池中的代币 Y 的代币账户。
+    pub vault_y: &'a AccountView,
+    // 池中代币 X 的铸币账户。
+    pub mint_x: &'a AccountView,
+    // 池中代币 Y 的铸币账户。
+    pub mint_y: &'a AccountView,
+    // 代币 X 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_x: &'a AccountView,
+    // 代币 Y 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_y: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for DecreaseLiquidityAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [owner, owner_x_ata, owner_y_ata, position, config, cl_pool, tick_array_lower, tick_array_upper, vault_x, vault_y, mint_x, mint_y, token_program_x, token_program_y] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(owner)?;
+        ProgramAccount::check(config)?;
+        MintInterface::check(mint_x)?;
+        MintInterface::check(mint_y)?;
+        TokenProgram::check(token_program_x)?;
+        TokenProgram::check(token_program_y)?;
+        Ok(Self {
+            owner,
+            owner_x_ata,
+            owner_y_ata,
+            position,
+            config,
+            cl_pool,
+            tick_array_lower,
+            tick_array_upper,
+            vault_x,
+            vault_y,
+            mint_x,
+            mint_y,
+            token_program_x,
+            token_program_y,
+        })
+    }
+}
+
+pub struct DecreaseLiquidityInstructionData {
+    // 要取出的流动性 为 0 时只领取手续费。
+    pub liquidity: u128,
+    // 至少收到的代币 X 数量 包含手续费。
+    pub min_x: u64,
+    // 至少收到的代币 Y 数量 包含手续费。
+    pub min_y: u64,
+    // 此订单的过期时间。
+    pub expiration: i64,
+}
+
+impl TryFrom<&[u8]> for DecreaseLiquidityInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u128>() + size_of::<u64>() * 2 + size_of::<i64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let liquidity = u128::from_le_bytes(data[0..16].try_into().unwrap());
+        let min_x = u64::from_le_bytes(data[16..24].try_into().unwrap());
+        let min_y = u64::from_le_bytes(data[24..32].try_into().unwrap());
+        let expiration = i64::from_le_bytes(data[32..40].try_into().unwrap());
+
+        // Instruction Checks
+        if liquidity > i128::MAX as u128 {
+            return Err(AmmError::InsufficientLiquidity.into());
+        }
+        if Clock::get()?.unix_timestamp > expiration {
+            return Err(AmmError::Expired.into());
+        }
+        Ok(Self {
+            liquidity,
+            min_x,
+            min_y,
+            expiration,
+        })
+    }
+}
+
+// 从集中流动性头寸取出流动性 同时领取头寸累计的手续费
+pub struct DecreaseLiquidity<'a> {
+    pub accounts: DecreaseLiquidityAccounts<'a>,
+    pub instruction_data: DecreaseLiquidityInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for DecreaseLiquidity<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = DecreaseLiquidityAccounts::try_from(accounts)?;
+        let instruction_data = DecreaseLiquidityInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> DecreaseLiquidity<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+        config.check_withdrawable()?;
+        // 闪电贷借出期间不能从金库转出代币
+        config.check_no_flash_loan()?;
+        check_mint(
+            self.accounts.mint_x,
+            config.mint_x(),
+            self.accounts.token_program_x,
+        )?;
+        check_mint(
+            self.accounts.mint_y,
+            config.mint_y(),
+            self.accounts.token_program_y,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.owner_x_ata,
+            self.accounts.owner,
+            config.mint_x(),
+            self.accounts.token_program_x,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.owner_y_ata,
+            self.accounts.owner,
+            config.mint_y(),
+            self.accounts.token_program_y,
+        )?;
+
+        config.check_vaults(
+            self.accounts.vault_x.address(),
+            self.accounts.vault_y.address(),
+        )?;
+
+        // 先结算手续费再取出流动性 取出的代币和欠付的手续费一起转给所有者
+        let (x, y) = modify_liquidity(
+            self.accounts.owner,
+            self.accounts.position,
+            self.accounts.config,
+            self.accounts.cl_pool,
+            self.accounts.tick_array_lower,
+            self.accounts.tick_array_upper,
+            -(self.instruction_data.liquidity as i128),
+        )?;
+        let mut position = Position::load_mut(self.accounts.position)?;
+        let x = x
+            .checked_add(position.fees_owed_x())
+            .ok_or(AmmError::CurveOverflow)?;
+        let y = y
+            .checked_add(position.fees_owed_y())
+            .ok_or(AmmError::CurveOverflow)?;
+        position.clear_fees();
+        drop(position);
+
+        // Token-2022 转账费 用户实际收到的数量才和最小值比较
+        let epoch = Clock::get()?.epoch;
+        let received_x =
+            TransferFee::from_mint(self.accounts.mint_x, epoch)?.amount_after_fee(x)?;
+        let received_y =
+            TransferFee::from_mint(self.accounts.mint_y, epoch)?.amount_after_fee(y)?;
+        if received_x < self.instruction_data.min_x || received_y < self.instruction_data.min_y {
+            return Err(AmmError::SlippageExceeded.into());
+        }
+
+        // 构建config签名
+        let seed_bytes = config.seed().to_le_bytes();
+        let mut seed_array = [0u8; 8];
+        seed_array.copy_from_slice(&seed_bytes);
+        let bump = config.config_bump();
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(&seed_array),
+            Seed::from(config.mint_x().as_ref()),
+            Seed::from(config.mint_y().as_ref()),
+            Seed::from(&bump),
+        ];
+        let config_signer = [Signer::from(&config_seeds)];
+
+        let mint_x = unsafe { mint_unchecked(self.accounts.mint_x)? };
+        let mint_y = unsafe { mint_unchecked(self.accounts.mint_y)? };
+        if x > 0 {
+            TransferChecked {
+                from: self.accounts.vault_x,
+                mint: self.accounts.mint_x,
+                to: self.accounts.owner_x_ata,
+                authority: self.accounts.config,
+                amount: x,
+                decimals: mint_x.decimals(),
+                token_program: self.accounts.token_program_x,
+            }
+            .invoke_signed(&config_signer)?;
+        }
+        if y > 0 {
+            TransferChecked {
+                from: self.accounts.vault_y,
+                mint: self.accounts.mint_y,
+                to: self.accounts.owner_y_ata,
+                authority: self.accounts.config,
+                amount: y,
+                decimals: mint_y.decimals(),
+                token_program: self.accounts.token_program_y,
+            }
+            .invoke_signed(&config_signer)?;
+        }
+        Ok(())
+    }
+}
@@ -2,15 +2,17 @@ use crate::instructions::helper::{
     AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck, AssociatedTokenAccountInit,
     MintInterface, ProgramAccount, SignerAccount,
 };
-use crate::{AmmState, Config};
+use crate::instructions::token_interface::{
+    check_lp_mint, check_mint, mint_unchecked, token_account_unchecked, MintTo, TokenProgram,
+    TransferChecked, TransferFee,
+};
+use crate::{AmmError, AmmEvent, Config, ConstantProduct, DepositEvent, MINIMUM_LIQUIDITY};
 use pinocchio::cpi::{Seed, Signer};
 use pinocchio::{
     error::ProgramError,
     sysvars::{clock::Clock, Sysvar},
-    AccountView, Address, ProgramResult,
+    AccountView, ProgramResult,
 };
-use pinocchio_token::instructions::{MintTo, Transfer};
-use pinocchio_token::state::{Mint, TokenAccount};
 
 pub struct DepositAccounts<'a> {
     // 将代币存入 AMM 流动性的用户。
@@ -29,22 +31,52 @@ pub struct DepositAccounts<'a> {
     pub user_lp_ata: &'a AccountView,
     // AMM 池的配置账户。
     pub config: &'a AccountView,
-    // SPL 代币程序账户。
+    // LP 铸币账户所属的代币程序账户。
     pub token_program: &'a AccountView,
+    // 池中代币 X 的铸币账户。Token-2022 的 TransferChecked 需要用到。
+    pub mint_x: &'a AccountView,
+    // 池中代币 Y 的铸币账户。
+    pub mint_y: &'a AccountView,
+    // 代币 X 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_x: &'a AccountView,
+    // 代币 Y 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_y: &'a AccountView,
+    // config 持有的 LP 关联账户。首次注入流动性时锁定的 LP 铸造到这里 之后再也无法取出。
+    pub locked_lp_ata: &'a AccountView,
+    // 系统程序账户。首次注入流动性时创建 locked_lp_ata 需要用到。
+    pub system_program: &'a AccountView,
+    // 关联代币账户程序账户。
+    pub associated_token_program: &'a AccountView,
+    // 白名单池子额外传入用户的白名单账户 其他池子不需要。
+    pub remaining: &'a [AccountView],
 }
 
 impl<'a> TryFrom<&'a [AccountView]> for DepositAccounts<'a> {
     type Error = ProgramError;
 
     fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
-        let [user, mint_lp, vault_x, vault_y, user_x_ata, user_y_ata, user_lp_ata, config, token_program] =
+        let [user, mint_lp, vault_x, vault_y, user_x_ata, user_y_ata, user_lp_ata, config, token_program, mint_x, mint_y, token_program_x, token_program_y, locked_lp_ata, system_program, associated_token_program, remaining @ ..] =
             accounts
         else {
             return Err(ProgramError::NotEnoughAccountKeys);
         };
         SignerAccount::check(user)?;
+        if system_program.address().ne(&pinocchio_system::ID) {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if associated_token_program
+            .address()
+            .ne(&pinocchio_associated_token_account::ID)
+        {
+            return Err(ProgramError::IncorrectProgramId);
+        }
         ProgramAccount::check(config)?;
         MintInterface::check(mint_lp)?;
+        MintInterface::check(mint_x)?;
+        MintInterface::check(mint_y)?;
+        TokenProgram::check(token_program)?;
+        TokenProgram::check(token_program_x)?;
+        TokenProgram::check(token_program_y)?;
         Ok(Self {
             user,
             mint_lp,
@@ -55,12 +87,20 @@ impl<'a> TryFrom<&'a [AccountView]> for DepositAccounts<'a> {
             user_lp_ata,
             config,
             token_program,
+            mint_x,
+            mint_y,
+            token_program_x,
+            token_program_y,
+            locked_lp_ata,
+            system_program,
+            associated_token_program,
+            remaining,
         })
     }
 }
 
 pub struct DepositInstructionData {
-    // 用户希望接收的 LP 代币数量
+    // 用户希望接收的 LP 代币数量 首次注入流动性时是用户愿意接收的最少 LP 代币数量
     pub amount: u64,
     // 用户愿意存入的最大 Token X 数量
     pub max_x: u64,
@@ -83,18 +123,18 @@ impl<'a> TryFrom<&'a [u8]> for DepositInstructionData {
         let max_y = u64::from_le_bytes(data[16..24].try_into().unwrap());
         let expiration = i64::from_le_bytes(data[24..32].try_into().unwrap());
         // Instruction Checks
-        if amount <= 0 {
-            return Err(ProgramError::InvalidInstructionData);
+        if amount == 0 {
+            return Err(AmmError::ZeroAmount.into());
         }
-        if max_x <= 0 {
-            return Err(ProgramError::InvalidInstructionData);
+        if max_x == 0 {
+            return Err(AmmError::ZeroAmount.into());
         }
-        if max_y <= 0 {
-            return Err(ProgramError::InvalidInstructionData);
+        if max_y == 0 {
+            return Err(AmmError::ZeroAmount.into());
         }
         if Clock::get()?.unix_timestamp > expiration {
             // 超时
-            return Err(ProgramError::InvalidInstructionData);
+            return Err(AmmError::Expired.into());
         }
         Ok(Self {
             amount,
@@ -128,20 +168,27 @@ impl<'a> Deposit<'a> {
     pub fn process(&self) -> ProgramResult {
         // 校验用户的ata账户地址有没有问题
         let config = Config::load(self.accounts.config)?;
-        if config.state() != (AmmState::Initialized as u8) {
-            return Err(ProgramError::InvalidArgument);
-        }
+        config.check_tradable()?;
+        // 白名单池子只允许白名单里的用户注入流动性
+        config.check_allow_listed(
+            self.accounts.config.address(),
+            self.accounts.user.address(),
+            self.accounts.remaining,
+        )?;
+        check_mint(self.accounts.mint_x, config.mint_x(), self.accounts.token_program_x)?;
+        check_mint(self.accounts.mint_y, config.mint_y(), self.accounts.token_program_y)?;
+        check_lp_mint(self.accounts.mint_lp, self.accounts.config, self.accounts.token_program)?;
         AssociatedTokenAccount::check(
             self.accounts.user_x_ata,
             self.accounts.user,
             config.mint_x(),
-            self.accounts.token_program,
+            self.accounts.token_program_x,
         )?;
         AssociatedTokenAccount::check(
             self.accounts.user_y_ata,
             self.accounts.user,
             config.mint_y(),
-            self.accounts.token_program,
+            self.accounts.token_program_y,
         )?;
         AssociatedTokenAccount::check(
             self.accounts.user_lp_ata,
@@ -150,67 +197,82 @@ impl<'a> Deposit<'a> {
             self.accounts.token_program,
         )?;
 
-        // 校验 金库的地址有没有为题
-        // 其实 vault并不需要被初始化 因为这个地址是被计算出来的 只要计算出来 就可以标记里面有多少钱
-        // Check if the vault_x is valid
-        let (vault_x, _) = Address::find_program_address(
-            &[
-                self.accounts.config.address().as_ref(),
-                self.accounts.token_program.address().as_ref(),
-                config.mint_x().as_ref(),
-            ],
-            &pinocchio_associated_token_account::ID,
-        );
-        if vault_x.ne(self.accounts.vault_x.address()) {
-            return Err(ProgramError::InvalidAccountData);
-        }
-        let (vault_y, _) = Address::find_program_address(
-            &[
-                self.accounts.config.address().as_ref(),
-                self.accounts.token_program.address().as_ref(),
-                config.mint_y().as_ref(),
-            ],
-            &pinocchio_associated_token_account::ID,
-        );
-        if vault_y.ne(self.accounts.vault_y.address()) {
-            return Err(ProgramError::InvalidAccountData);
-        }
+        config.check_vaults(
+            self.accounts.vault_x.address(),
+            self.accounts.vault_y.address(),
+        )?;
         // 把账户从 AccountView 转化成功能账户
-        let mint_lp = unsafe { Mint::from_account_view_unchecked(self.accounts.mint_lp)? };
-        let vault_x = unsafe { TokenAccount::from_account_view_unchecked(self.accounts.vault_x)? };
-        let vault_y = unsafe { TokenAccount::from_account_view_unchecked(self.accounts.vault_y)? };
+        let mint_lp = unsafe { mint_unchecked(self.accounts.mint_lp)? };
+        let vault_x = unsafe { token_account_unchecked(self.accounts.vault_x)? };
+        let vault_y = unsafe { token_account_unchecked(self.accounts.vault_y)? };
+        let mint_x = unsafe { mint_unchecked(self.accounts.mint_x)? };
+        let mint_y = unsafe { mint_unchecked(self.accounts.mint_y)? };
+        // 金库余额扣除未领取的协议费之后才是 LP 的储备
+        let (reserve_x, reserve_y) = config.reserves(vault_x.amount(), vault_y.amount())?;
 
-        // 计算用户需要支付的 x y 代币的数量
-        let (x, y) = match mint_lp.supply() == 0 && vault_x.amount() == 0 && vault_y.amount() == 0 {
-            // 这里就是当vault_x 和 vault_y 和 lp_token供应量全部都是0 也就是首次注入流动性
-            // 在这个还没有暴露的情况下 默认可以认为这就是定下 x * y 的值 也可以认为初始定下了 x y 和 lp代币的兑换比率
-            true => (self.instruction_data.max_x, self.instruction_data.max_y),
+        let epoch = Clock::get()?.epoch;
+        let fee_x = TransferFee::from_mint(self.accounts.mint_x, epoch)?;
+        let fee_y = TransferFee::from_mint(self.accounts.mint_y, epoch)?;
+        // 金库在 Initialize 时就已经创建 任何人都可以在首次注入之前向金库转账
+        // 所以只按 LP 供应量判断是否首次注入 否则池子会永远停在有余额没有 LP 的状态
+        let is_initial = mint_lp.supply() == 0;
+        // 计算用户需要支付的 x y 代币的数量 以及铸造给用户的 LP 数量
+        let (x, y, lp) = match is_initial {
+            // 首次注入流动性 金库里已有的余额和用户存入的 max_x max_y 一起定下初始价格
+            // LP 数量按注入之后金库的余额取几何平均 已有的余额归首个 LP
+            true => {
+                let lp = ConstantProduct::l_from_initial_xy(
+                    reserve_x
+                        .checked_add(fee_x.amount_after_fee(self.instruction_data.max_x)?)
+                        .ok_or(AmmError::CurveOverflow)?,
+                    reserve_y
+                        .checked_add(fee_y.amount_after_fee(self.instruction_data.max_y)?)
+                        .ok_or(AmmError::CurveOverflow)?,
+                )?;
+                if lp < self.instruction_data.amount {
+                    return Err(AmmError::SlippageExceeded.into());
+                }
+                (self.instruction_data.max_x, self.instruction_data.max_y, lp)
+            }
             false => {
-                let amounts = Self::xy_deposit_amounts_from_l(
-                    vault_x.amount(),
-                    vault_y.amount(),
+                let (x, y) = ConstantProduct::xy_deposit_amounts_from_l(
+                    reserve_x,
+                    reserve_y,
                     mint_lp.supply(),
                     self.instruction_data.amount,
-                    6,
+                )?;
+                // 带转账费的 Token-2022 代币 用户需要多转一些 保证金库实际收到 x 和 y
+                (
+                    fee_x.amount_before_fee(x)?,
+                    fee_y.amount_before_fee(y)?,
+                    self.instruction_data.amount,
                 )
-                .map_err(|_| ProgramError::InvalidArgument)?;
-                (amounts.0, amounts.1)
             }
         };
+        // Check for slippage
+        if x > self.instruction_data.max_x || y > self.instruction_data.max_y {
+            return Err(AmmError::SlippageExceeded.into());
+        }
         // 将用户的代币账户中的金额转移到金库
-        Transfer {
+        TransferChecked {
             from: self.accounts.user_x_ata,
+            mint: self.accounts.mint_x,
             to: self.accounts.vault_x,
             authority: self.accounts.user,
             amount: x,
+            decimals: mint_x.decimals(),
+            token_program: self.accounts.token_program_x,
         }
         .invoke()?;
 
-        Transfer {
+        TransferChecked {
             from: self.accounts.user_y_ata,
+            mint: self.accounts.mint_y,
             to: self.accounts.vault_y,
             authority: self.accounts.user,
             amount: y,
+            decimals: mint_y.decimals(),
+            token_program: self.accounts.token_program_y,
         }
         .invoke()?;
 
@@ -233,52 +295,48 @@ impl<'a> Deposit<'a> {
             mint: self.accounts.mint_lp,
             account: self.accounts.user_lp_ata,
             mint_authority: self.accounts.config,
-            amount: self.instruction_data.amount,
+            amount: lp,
+            token_program: self.accounts.token_program,
         }
         .invoke_signed(&config_signer)?;
 
-        Ok(())
-    }
+        // 首次注入流动性时 把 MINIMUM_LIQUIDITY 铸造到 config 自己的 LP 账户永久锁定
+        // 没有任何指令能让 config 转出或销毁这部分 LP
+        // 锁定账户由首个 LP 支付租金创建 已经存在时跳过
+        if is_initial {
+            AssociatedTokenAccount::init_if_needed(
+                self.accounts.locked_lp_ata,
+                self.accounts.mint_lp,
+                self.accounts.user,
+                self.accounts.config,
+                self.accounts.system_program,
+                self.accounts.token_program,
+            )?;
+            MintTo {
+                mint: self.accounts.mint_lp,
+                account: self.accounts.locked_lp_ata,
+                mint_authority: self.accounts.config,
+                amount: MINIMUM_LIQUIDITY,
+                token_program: self.accounts.token_program,
+            }
+            .invoke_signed(&config_signer)?;
+        }
 
-    // 这个是根据git上直接搬过来的 错误类型改了一下
-    // x: 库存的x代币数量
-    // y: 库存的y代币数量
-    // l: 流动性代币lp的流通量
-    // a: 用户希望接收的 LP 代币数量
-    // precision: 精度
-    fn xy_deposit_amounts_from_l(
-        x: u64,
-        y: u64,
-        l: u64,
-        a: u64,
-        precision: u32,
-    ) -> Result<(u64, u64), ProgramError> {
-        // ((已经流通的lp数量 + 用户希望接收的lp数量) * 精度) / 已经流动的lp数量
-        // 这里计算得到的是 用户如果想要拿到希望数量的lp所需要支付的对应x和y代币占库存的占比
-        // 由于分母比分子大 所以这里的ratio必定大于1
-        let ratio = (l as u128)
-            .checked_add(a as u128)
-            .ok_or(ProgramError::InvalidInstructionData)?
-            .checked_mul(precision as u128)
-            .ok_or(ProgramError::InvalidInstructionData)?
-            .checked_div(l as u128)
-            .ok_or(ProgramError::InvalidInstructionData)?;
-        // ((库存代币x数量 * 比例) / 精度) - 库存x代币的数量 = 用户需要支付的x代币数量
-        let deposit_x = (x as u128)
-            .checked_mul(ratio)
-            .ok_or(ProgramError::InvalidInstructionData)?
-            .checked_div(precision as u128)
-            .ok_or(ProgramError::InvalidInstructionData)?
-            .checked_sub(x as u128)
-            .ok_or(ProgramError::InvalidInstructionData)? as u64;
-        // ((库存代币y数量 * 比例) / 精度) - 库存y代币的数量 = 用户需要支付的y代币数量
-        let deposit_y = (y as u128)
-            .checked_mul(ratio)
-            .ok_or(ProgramError::InvalidInstructionData)?
-            .checked_div(precision as u128)
-            .ok_or(ProgramError::InvalidInstructionData)?
-            .checked_sub(y as u128)
-            .ok_or(ProgramError::InvalidInstructionData)? as u64;
-        Ok((deposit_x, deposit_y))
+        // 使用存入之前的储备更新价格累加器
+        drop(config);
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.update_price_cumulative(reserve_x, reserve_y, Clock::get()?.unix_timestamp)?;
+
+        AmmEvent::Deposit(DepositEvent {
+            pool: *self.accounts.config.address(),
+            user: *self.accounts.user.address(),
+            amount_x: x,
+            amount_y: y,
+            lp_minted: lp,
+            reserve_x: reserve_x + fee_x.amount_after_fee(x)?,
+            reserve_y: reserve_y + fee_y.amount_after_fee(y)?,
+        })
+        .emit();
+        Ok(())
     }
 }
@@ -0,0 +1,269 @@
+use crate::instructions::helper::{
+    AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck, MintInterface,
+    ProgramAccount, SignerAccount,
+};
+use crate::instructions::token_interface::{
+    check_mint, mint_unchecked, token_account_unchecked, TokenProgram, TransferChecked, TransferFee,
+};
+use crate::{
+    AmmError, AmmEvent, Config, ConstantProduct, LiquidityPair, Order, ProgramState, SwapEvent,
+};
+use pinocchio::cpi::{Seed, Signer};
+use pinocchio::{
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+
+pub struct ExecuteOrderAccounts<'a> {
+    // 执行订单的 keeper 任何人都可以执行。
+    pub keeper: &'a AccountView,
+    // keeper 接收小费的输入代币账户。
+    pub keeper_ata: &'a AccountView,
+    // 下单的用户 不需要签名。
+    pub owner: &'a AccountView,
+    // 用户接收输出代币的关联账户。
+    pub owner_dst_ata: &'a AccountView,
+    // 要执行的订单账户。
+    pub order: &'a AccountView,
+    // 托管输入代币的账户。
+    pub escrow: &'a AccountView,
+    // 持有所有存入池中的代币 X 的代币账户。
+    pub vault_x: &'a AccountView,
+    // 持有所有存入池中的代币 Y 的代币账户。
+    pub vault_y: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+    // 池中代币 X 的铸币账户。
+    pub mint_x: &'a AccountView,
+    // 池中代币 Y 的铸币账户。
+    pub mint_y: &'a AccountView,
+    // 代币 X 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_x: &'a AccountView,
+    // 代币 Y 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_y: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for ExecuteOrderAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [keeper, keeper_ata, owner, owner_dst_ata, order, escrow, vault_x, vault_y, config, mint_x, mint_y, token_program_x, token_program_y] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(keeper)?;
+        ProgramAccount::check(config)?;
+        MintInterface::check(mint_x)?;
+        MintInterface::check(mint_y)?;
+        TokenProgram::check(token_program_x)?;
+        TokenProgram::check(token_program_y)?;
+        Ok(Self {
+            keeper,
+            keeper_ata,
+            owner,
+            owner_dst_ata,
+            order,
+            escrow,
+            vault_x,
+            vault_y,
+            config,
+            mint_x,
+            mint_y,
+            token_program_x,
+            token_program_y,
+        })
+    }
+}
+
+// 执行一次订单 时间条件满足并且池子的报价不低于 min_out 时才能成功
+// 交换的计算和 Swap 的 ExactIn 完全一样 keeper 从托管账户里得到小费
+pub struct ExecuteOrder<'a> {
+    pub accounts: ExecuteOrderAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for ExecuteOrder<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let accounts = ExecuteOrderAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> ExecuteOrder<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let now = Clock::get()?.unix_timestamp;
+        let order = Order::load(self.accounts.order)?;
+        if order.config().ne(self.accounts.config.address())
+            || order.owner().ne(self.accounts.owner.address())
+        {
+            return Err(AmmError::InvalidOrder.into());
+        }
+        order.check_executable(now)?;
+
+        let config = Config::load(self.accounts.config)?;
+        config.check_tradable()?;
+        check_mint(
+            self.accounts.mint_x,
+            config.mint_x(),
+            self.accounts.token_program_x,
+        )?;
+        check_mint(
+            self.accounts.mint_y,
+            config.mint_y(),
+            self.accounts.token_program_y,
+        )?;
+
+        config.check_vaults(
+            self.accounts.vault_x.address(),
+            self.accounts.vault_y.address(),
+        )?;
+
+        // 按订单方向选出支付和接收两侧的账户
+        let (p, mint_in, vault_in, token_program_in, mint_out, vault_out, token_program_out) =
+            match order.is_x() {
+                true => (
+                    LiquidityPair::X,
+                    self.accounts.mint_x,
+                    self.accounts.vault_x,
+                    self.accounts.token_program_x,
+                    self.accounts.mint_y,
+                    self.accounts.vault_y,
+                    self.accounts.token_program_y,
+                ),
+                false => (
+                    LiquidityPair::Y,
+                    self.accounts.mint_y,
+                    self.accounts.vault_y,
+                    self.accounts.token_program_y,
+                    self.accounts.mint_x,
+                    self.accounts.vault_x,
+                    self.accounts.token_program_x,
+                ),
+            };
+        AssociatedTokenAccount::check(
+            self.accounts.escrow,
+            self.accounts.order,
+            mint_in.address(),
+            token_program_in,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.owner_dst_ata,
+            self.accounts.owner,
+            mint_out.address(),
+            token_program_out,
+        )?;
+
+        let vault_x = unsafe { token_account_unchecked(self.accounts.vault_x)? };
+        let vault_y = unsafe { token_account_unchecked(self.accounts.vault_y)? };
+        let (reserve_x, reserve_y) = config.reserves(vault_x.amount(), vault_y.amount())?;
+        let mut curve = config.curve(reserve_x, reserve_y, now)?;
+
+        // Token-2022 转账费 支付的代币进金库时扣一次 收到的代币出金库时扣一次
+        let epoch = Clock::get()?.epoch;
+        let received =
+            TransferFee::from_mint(mint_in, epoch)?.amount_after_fee(order.amount_in())?;
+        let swap_result = curve.swap(p, received, 0)?;
+        let amount_out =
+            TransferFee::from_mint(mint_out, epoch)?.amount_after_fee(swap_result.2)?;
+        // 价格条件 池子的报价低于订单的限价时不能执行
+        if amount_out < order.min_out() {
+            return Err(AmmError::SlippageExceeded.into());
+        }
+        if swap_result.0 == 0 || amount_out == 0 {
+            return Err(AmmError::ZeroAmount.into());
+        }
+        let (_, protocol_fee) = ConstantProduct::split_fee(swap_result.1, config.protocol_fee())?;
+
+        // 构建订单签名 托管账户里的代币由订单转出
+        let order_seed = order.seed().to_le_bytes();
+        let order_bump = order.bump();
+        let order_seeds = [
+            Seed::from(b"order"),
+            Seed::from(order.config().as_ref()),
+            Seed::from(order.owner().as_ref()),
+            Seed::from(&order_seed),
+            Seed::from(&order_bump),
+        ];
+        let order_signer = [Signer::from(&order_seeds)];
+
+        // 构建config签名
+        let seed_bytes = config.seed().to_le_bytes();
+        let mut seed_array = [0u8; 8];
+        seed_array.copy_from_slice(&seed_bytes);
+        let bump = config.config_bump();
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(&seed_array),
+            Seed::from(config.mint_x().as_ref()),
+            Seed::from(config.mint_y().as_ref()),
+            Seed::from(&bump),
+        ];
+        let config_signer = [Signer::from(&config_seeds)];
+
+        let mint_in_state = unsafe { mint_unchecked(mint_in)? };
+        let mint_out_state = unsafe { mint_unchecked(mint_out)? };
+        TransferChecked {
+            from: self.accounts.escrow,
+            mint: mint_in,
+            to: vault_in,
+            authority: self.accounts.order,
+            amount: order.amount_in(),
+            decimals: mint_in_state.decimals(),
+            token_program: token_program_in,
+        }
+        .invoke_signed(&order_signer)?;
+        TransferChecked {
+            from: vault_out,
+            mint: mint_out,
+            to: self.accounts.owner_dst_ata,
+            authority: self.accounts.config,
+            amount: swap_result.2,
+            decimals: mint_out_state.decimals(),
+            token_program: token_program_out,
+        }
+        .invoke_signed(&config_signer)?;
+        if order.tip() > 0 {
+            TransferChecked {
+                from: self.accounts.escrow,
+                mint: mint_in,
+                to: self.accounts.keeper_ata,
+                authority: self.accounts.order,
+                amount: order.tip(),
+                decimals: mint_in_state.decimals(),
+                token_program: token_program_in,
+            }
+            .invoke_signed(&order_signer)?;
+        }
+
+        // 和 Swap 一样记录协议费、价格累加器和波动率
+        drop(config);
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.update_price_cumulative(reserve_x, reserve_y, now)?;
+        match p {
+            LiquidityPair::X => config.add_protocol_fees_x(protocol_fee)?,
+            LiquidityPair::Y => config.add_protocol_fees_y(protocol_fee)?,
+        }
+        let (reserve_x, reserve_y) = match p {
+            LiquidityPair::X => (curve.x() - protocol_fee, curve.y()),
+            LiquidityPair::Y => (curve.x(), curve.y() - protocol_fee),
+        };
+        config.update_volatility(reserve_x, reserve_y, now)?;
+        AmmEvent::Swap(SwapEvent {
+            pool: *self.accounts.config.address(),
+            user: *self.accounts.owner.address(),
+            is_x: order.is_x(),
+            amount_in: order.amount_in(),
+            amount_out,
+            fee: swap_result.1,
+            reserve_x,
+            reserve_y,
+        })
+        .emit();
+
+        drop(order);
+        Order::load_mut(self.accounts.order)?.advance(now)
+    }
+}
@@ -0,0 +1,183 @@
+use crate::instructions::helper::{
+    AccountCheck, MintInterface, ProgramAccount, SignerAccount, TokenAccount,
+};
+use crate::instructions::token_interface::{
+    check_mint, mint_unchecked, token_account_unchecked, TokenProgram, TransferChecked,
+};
+use crate::{AmmError, Config, FlashLoan, FlashRepay, Instructions, INSTRUCTIONS_SYSVAR_ID};
+use pinocchio::cpi::{Seed, Signer};
+use pinocchio::{error::ProgramError, AccountView, ProgramResult};
+
+pub struct FlashBorrowAccounts<'a> {
+    // 借款人。
+    pub borrower: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+    // 借出代币的金库。is_x 为 true 时是 X 金库 否则是 Y 金库。
+    pub vault: &'a AccountView,
+    // 接收借款的代币账户。
+    pub borrower_dst: &'a AccountView,
+    // 借出代币的铸币账户。
+    pub mint: &'a AccountView,
+    // 借出代币所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program: &'a AccountView,
+    // 指令 sysvar 账户。用来检查同一笔交易后面的归还指令。
+    pub instructions: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for FlashBorrowAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [borrower, config, vault, borrower_dst, mint, token_program, instructions] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(borrower)?;
+        ProgramAccount::check(config)?;
+        TokenAccount::check(borrower_dst)?;
+        MintInterface::check(mint)?;
+        TokenProgram::check(token_program)?;
+        if instructions.address().ne(&INSTRUCTIONS_SYSVAR_ID) {
+            return Err(ProgramError::UnsupportedSysvar);
+        }
+        Ok(Self {
+            borrower,
+            config,
+            vault,
+            borrower_dst,
+            mint,
+            token_program,
+            instructions,
+        })
+    }
+}
+
+pub struct FlashBorrowInstructionData {
+    // 借出的是代币 X 还是代币 Y。
+    pub is_x: bool,
+    // 借出的数量。
+    pub amount: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for FlashBorrowInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<bool>() + size_of::<u64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let is_x = data[0] != 0;
+        let amount = u64::from_le_bytes(data[1..9].try_into().unwrap());
+        if amount == 0 {
+            return Err(AmmError::ZeroAmount.into());
+        }
+        Ok(Self { is_x, amount })
+    }
+}
+
+// 闪电贷 从金库借出代币 必须在同一笔交易里用 FlashRepay 连本带手续费归还
+// 借出期间池子被锁定 所有按储备计算的操作都会失败 直到归还为止
+pub struct FlashBorrow<'a> {
+    pub accounts: FlashBorrowAccounts<'a>,
+    pub instruction_data: FlashBorrowInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for FlashBorrow<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = FlashBorrowAccounts::try_from(accounts)?;
+        let instruction_data = FlashBorrowInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> FlashBorrow<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+        config.check_tradable()?;
+        let (side, mint, protocol_fees) = match self.instruction_data.is_x {
+            true => (FlashLoan::X, config.mint_x(), config.protocol_fees_x()),
+            false => (FlashLoan::Y, config.mint_y(), config.protocol_fees_y()),
+        };
+        check_mint(self.accounts.mint, mint, self.accounts.token_program)?;
+        config.check_vault(self.instruction_data.is_x, self.accounts.vault.address())?;
+
+        self.check_repay()?;
+
+        // 只能借出属于 LP 的储备 协议费不外借
+        let balance = unsafe { token_account_unchecked(self.accounts.vault)?.amount() };
+        let reserve = balance
+            .checked_sub(protocol_fees)
+            .ok_or(AmmError::CurveOverflow)?;
+        if self.instruction_data.amount >= reserve {
+            return Err(AmmError::InsufficientLiquidity.into());
+        }
+
+        // 先锁定池子再转账 转账过程中 Token-2022 的 transfer hook 也无法按借出之后的余额交易
+        drop(config);
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.start_flash_loan(side, self.instruction_data.amount, balance)?;
+        drop(config);
+        let config = Config::load(self.accounts.config)?;
+
+        // 构建config签名
+        let seed_bytes = config.seed().to_le_bytes();
+        let mut seed_array = [0u8; 8];
+        seed_array.copy_from_slice(&seed_bytes);
+        let bump = config.config_bump();
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(&seed_array),
+            Seed::from(config.mint_x().as_ref()),
+            Seed::from(config.mint_y().as_ref()),
+            Seed::from(&bump),
+        ];
+
+        let config_signer = [Signer::from(&config_seeds)];
+        let mint = unsafe { mint_unchecked(self.accounts.mint)? };
+        TransferChecked {
+            from: self.accounts.vault,
+            mint: self.accounts.mint,
+            to: self.accounts.borrower_dst,
+            authority: self.accounts.config,
+            amount: self.instruction_data.amount,
+            decimals: mint.decimals(),
+            token_program: self.accounts.token_program,
+        }
+        .invoke_signed(&config_signer)
+    }
+
+    // 借款必须是顶层指令 并且交易中下一条发给本程序的顶层指令必须是同一个池子、同一侧、同样数量的 FlashRepay
+    // 归还之前不能再调用本程序的其他指令 所以不能嵌套借款
+    fn check_repay(&self) -> ProgramResult {
+        let data = self.accounts.instructions.try_borrow()?;
+        let instructions = Instructions::new(&data)?;
+        let current = instructions.current_index()?;
+        // 通过 CPI 调用时 当前的顶层指令属于别的程序
+        if instructions.get(current)?.program_id().ne(&crate::ID) {
+            return Err(AmmError::InvalidFlashLoan.into());
+        }
+        for index in current + 1..instructions.len() {
+            let instruction = instructions.get(index)?;
+            if instruction.program_id().ne(&crate::ID) {
+                continue;
+            }
+            let data = instruction.data();
+            let matches = data.len() == 1 + size_of::<bool>() + size_of::<u64>()
+                && data[0] == FlashRepay::DISCRIMINATOR
+                && (data[1] != 0) == self.instruction_data.is_x
+                && data[2..10] == self.instruction_data.amount.to_le_bytes()
+                && instruction.account(1)? == self.accounts.config.address();
+            return match matches {
+                true => Ok(()),
+                false => Err(AmmError::InvalidFlashLoan.into()),
+            };
+        }
+        Err(AmmError::InvalidFlashLoan.into())
+    }
+}
@@ -0,0 +1,172 @@
+use crate::instructions::helper::{
+    AccountCheck, MintInterface, ProgramAccount, SignerAccount, TokenAccount,
+};
+use crate::instructions::token_interface::{
+    check_mint, mint_unchecked, token_account_unchecked, TokenProgram, TransferChecked, TransferFee,
+};
+use crate::{AmmError, AmmEvent, Config, DepositEvent, FlashLoan};
+use pinocchio::{
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+
+pub struct FlashRepayAccounts<'a> {
+    // 归还借款的用户。必须是 borrower_src 的所有者。
+    pub borrower: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+    // 借出代币的金库。
+    pub vault: &'a AccountView,
+    // 支付本金和手续费的代币账户。
+    pub borrower_src: &'a AccountView,
+    // 借出代币的铸币账户。
+    pub mint: &'a AccountView,
+    // 借出代币所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program: &'a AccountView,
+    // 池子另一侧的金库 只用来在事件里报告储备。
+    pub vault_other: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for FlashRepayAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [borrower, config, vault, borrower_src, mint, token_program, vault_other] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(borrower)?;
+        ProgramAccount::check(config)?;
+        TokenAccount::check(borrower_src)?;
+        MintInterface::check(mint)?;
+        TokenProgram::check(token_program)?;
+        Ok(Self {
+            borrower,
+            config,
+            vault,
+            borrower_src,
+            mint,
+            token_program,
+            vault_other,
+        })
+    }
+}
+
+pub struct FlashRepayInstructionData {
+    // 归还的是代币 X 还是代币 Y。必须和 FlashBorrow 一致。
+    pub is_x: bool,
+    // 借出的本金。必须和 FlashBorrow 一致 手续费另外计算。
+    pub amount: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for FlashRepayInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<bool>() + size_of::<u64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let is_x = data[0] != 0;
+        let amount = u64::from_le_bytes(data[1..9].try_into().unwrap());
+        if amount == 0 {
+            return Err(AmmError::ZeroAmount.into());
+        }
+        Ok(Self { is_x, amount })
+    }
+}
+
+// 归还闪电贷 本金加上手续费转回金库 手续费留在金库里归 LP 然后解除池子的锁定
+pub struct FlashRepay<'a> {
+    pub accounts: FlashRepayAccounts<'a>,
+    pub instruction_data: FlashRepayInstructionData,
+}
+
+impl FlashRepay<'_> {
+    // FlashBorrow 用它在指令 sysvar 中查找归还指令 必须和 lib.rs 中的分发保持一致
+    pub const DISCRIMINATOR: u8 = 19;
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for FlashRepay<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = FlashRepayAccounts::try_from(accounts)?;
+        let instruction_data = FlashRepayInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> FlashRepay<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+        let is_x = self.instruction_data.is_x;
+        let (side, mint) = match is_x {
+            true => (FlashLoan::X, config.mint_x()),
+            false => (FlashLoan::Y, config.mint_y()),
+        };
+        check_mint(self.accounts.mint, mint, self.accounts.token_program)?;
+        config.check_vault(is_x, self.accounts.vault.address())?;
+        config.check_vault(!is_x, self.accounts.vault_other.address())?;
+
+        let clock = Clock::get()?;
+        let fee = config.flash_loan_fee(self.instruction_data.amount, clock.unix_timestamp)?;
+        let total = self
+            .instruction_data
+            .amount
+            .checked_add(fee)
+            .ok_or(AmmError::CurveOverflow)?;
+        // 带转账费的 Token-2022 代币 按金库实际收到 total 计算需要转出的数量
+        let gross =
+            TransferFee::from_mint(self.accounts.mint, clock.epoch)?.amount_before_fee(total)?;
+        drop(config);
+
+        let mint = unsafe { mint_unchecked(self.accounts.mint)? };
+        TransferChecked {
+            from: self.accounts.borrower_src,
+            mint: self.accounts.mint,
+            to: self.accounts.vault,
+            authority: self.accounts.borrower,
+            amount: gross,
+            decimals: mint.decimals(),
+            token_program: self.accounts.token_program,
+        }
+        .invoke()?;
+
+        // 金库余额必须回到借出之前 再加上手续费
+        let mut config = Config::load_mut(self.accounts.config)?;
+        let balance = config.finish_flash_loan(side, self.instruction_data.amount)?;
+        let vault = unsafe { token_account_unchecked(self.accounts.vault)? };
+        if vault.amount() < balance.checked_add(fee).ok_or(AmmError::CurveOverflow)? {
+            return Err(AmmError::InvalidFlashLoan.into());
+        }
+
+        // 手续费留在金库里 相当于不铸造 LP 的注入
+        // 集中流动性池也可以借款 所以不用 Config::reserves 直接从金库余额中扣除协议费
+        let other = unsafe { token_account_unchecked(self.accounts.vault_other)? };
+        let (amount_x, amount_y, balance_x, balance_y) = match is_x {
+            true => (fee, 0, vault.amount(), other.amount()),
+            false => (0, fee, other.amount(), vault.amount()),
+        };
+        let reserve_x = balance_x
+            .checked_sub(config.protocol_fees_x())
+            .ok_or(AmmError::CurveOverflow)?;
+        let reserve_y = balance_y
+            .checked_sub(config.protocol_fees_y())
+            .ok_or(AmmError::CurveOverflow)?;
+        AmmEvent::Deposit(DepositEvent {
+            pool: *self.accounts.config.address(),
+            user: *self.accounts.borrower.address(),
+            amount_x,
+            amount_y,
+            lp_minted: 0,
+            reserve_x,
+            reserve_y,
+        })
+        .emit();
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use crate::instructions::helper::{
+    AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck, MintInterface, SignerAccount,
+};
+use crate::instructions::token_interface::{
+    check_mint, mint_unchecked, TokenProgram, TransferChecked, TransferFee,
+};
+use crate::{AmmError, Farm, ProgramState};
+use pinocchio::{
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+
+pub struct FundRewardsAccounts<'a> {
+    // 注入奖励的账户 任何人都可以注入。
+    pub funder: &'a AccountView,
+    // 注入者的奖励代币账户。
+    pub funder_reward_ata: &'a AccountView,
+    // 池子的农场账户。
+    pub farm: &'a AccountView,
+    // 奖励代币的铸币账户。
+    pub reward_mint: &'a AccountView,
+    // 农场持有奖励代币的关联账户。
+    pub reward_vault: &'a AccountView,
+    // 奖励代币所属的代币程序账户 SPL Token 或 Token-2022。
+    pub reward_token_program: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for FundRewardsAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [funder, funder_reward_ata, farm, reward_mint, reward_vault, reward_token_program] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(funder)?;
+        MintInterface::check(reward_mint)?;
+        TokenProgram::check(reward_token_program)?;
+        Ok(Self {
+            funder,
+            funder_reward_ata,
+            farm,
+            reward_mint,
+            reward_vault,
+            reward_token_program,
+        })
+    }
+}
+
+pub struct FundRewardsInstructionData {
+    // 注入者转出的奖励代币数量。
+    pub amount: u64,
+}
+
+impl TryFrom<&[u8]> for FundRewardsInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let amount = u64::from_le_bytes(data.try_into().unwrap());
+        if amount == 0 {
+            return Err(AmmError::ZeroAmount.into());
+        }
+        Ok(Self { amount })
+    }
+}
+
+// 向农场注入奖励 注入的奖励从现在开始按释放速度分给质押者
+pub struct FundRewards<'a> {
+    pub accounts: FundRewardsAccounts<'a>,
+    pub instruction_data: FundRewardsInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for FundRewards<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = FundRewardsAccounts::try_from(accounts)?;
+        let instruction_data = FundRewardsInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> FundRewards<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let farm = Farm::load(self.accounts.farm)?;
+        check_mint(
+            self.accounts.reward_mint,
+            farm.reward_mint(),
+            self.accounts.reward_token_program,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.reward_vault,
+            self.accounts.farm,
+            farm.reward_mint(),
+            self.accounts.reward_token_program,
+        )?;
+        drop(farm);
+
+        let reward_mint = unsafe { mint_unchecked(self.accounts.reward_mint)? };
+        TransferChecked {
+            from: self.accounts.funder_reward_ata,
+            mint: self.accounts.reward_mint,
+            to: self.accounts.reward_vault,
+            authority: self.accounts.funder,
+            amount: self.instruction_data.amount,
+            decimals: reward_mint.decimals(),
+            token_program: self.accounts.reward_token_program,
+        }
+        .invoke()?;
+
+        // Token-2022 转账费 只把金库实际收到的数量计入待释放的奖励
+        let clock = Clock::get()?;
+        let received = TransferFee::from_mint(self.accounts.reward_mint, clock.epoch)?
+            .amount_after_fee(self.instruction_data.amount)?;
+        Farm::load_mut(self.accounts.farm)?.fund(received, clock.unix_timestamp)
+    }
+}
@@ -4,7 +4,10 @@ use pinocchio::{
     sysvars::{rent::Rent, Sysvar},
     AccountView, Address, ProgramResult,
 };
-use pinocchio_associated_token_account::instructions::Create;
+use pinocchio_associated_token_account::instructions::CreateIdempotent;
+use crate::instructions::token_interface::{
+    has_account_type, ACCOUNT_TYPE_ACCOUNT, ACCOUNT_TYPE_MINT, TOKEN_2022_PROGRAM_ID,
+};
 use pinocchio_system::instructions::CreateAccount;
 
 pub trait AccountCheck {
@@ -26,11 +29,19 @@ pub struct MintInterface;
 
 impl AccountCheck for MintInterface {
     fn check(account: &AccountView) -> Result<(), ProgramError> {
-        if account.owned_by(&pinocchio_system::ID) {
-            return Err(ProgramError::InvalidAccountData);
-        }
-        if account.data_len() != pinocchio_token::state::Mint::LEN {
-            return Err(ProgramError::InvalidAccountData);
+        if account.owned_by(&pinocchio_token::ID) {
+            if account.data_len() != pinocchio_token::state::Mint::LEN {
+                return Err(ProgramError::InvalidAccountData);
+            }
+        } else if account.owned_by(&TOKEN_2022_PROGRAM_ID) {
+            // Token-2022 的铸币账户 没有扩展时和 SPL Token 一样 有扩展时需要校验账户类型
+            if account.data_len() != pinocchio_token::state::Mint::LEN
+                && !has_account_type(account, ACCOUNT_TYPE_MINT)?
+            {
+                return Err(ProgramError::InvalidAccountData);
+            }
+        } else {
+            return Err(ProgramError::InvalidAccountOwner);
         }
         Ok(())
     }
@@ -40,11 +51,19 @@ pub struct TokenAccount;
 
 impl AccountCheck for TokenAccount {
     fn check(account: &AccountView) -> Result<(), ProgramError> {
-        if account.owned_by(&pinocchio_system::ID) {
-            return Err(ProgramError::InvalidAccountData);
-        }
-        if account.data_len() != pinocchio_token::state::TokenAccount::LEN {
-            return Err(ProgramError::InvalidAccountData);
+        if account.owned_by(&pinocchio_token::ID) {
+            if account.data_len() != pinocchio_token::state::TokenAccount::LEN {
+                return Err(ProgramError::InvalidAccountData);
+            }
+        } else if account.owned_by(&TOKEN_2022_PROGRAM_ID) {
+            // Token-2022 的代币账户 没有扩展时和 SPL Token 一样 有扩展时需要校验账户类型
+            if account.data_len() != pinocchio_token::state::TokenAccount::LEN
+                && !has_account_type(account, ACCOUNT_TYPE_ACCOUNT)?
+            {
+                return Err(ProgramError::InvalidAccountData);
+            }
+        } else {
+            return Err(ProgramError::InvalidAccountOwner);
         }
         Ok(())
     }
@@ -61,15 +80,6 @@ pub trait AssociatedTokenAccountCheck {
 pub struct AssociatedTokenAccount;
 
 pub trait AssociatedTokenAccountInit {
-    fn init(
-        account: &AccountView,
-        mint: &AccountView,
-        payer: &AccountView,
-        owner: &AccountView,
-        system_program: &AccountView,
-        token_program: &AccountView,
-    ) -> ProgramResult;
-
     fn init_if_needed(
         account: &AccountView,
         mint: &AccountView,
@@ -104,7 +114,7 @@ impl AssociatedTokenAccountCheck for AssociatedTokenAccount {
 }
 
 impl AssociatedTokenAccountInit for AssociatedTokenAccount {
-    fn init(
+    fn init_if_needed(
         account: &AccountView,
         mint: &AccountView,
         payer: &AccountView,
@@ -112,7 +122,8 @@ impl AssociatedTokenAccountInit for AssociatedTokenAccount {
         system_program: &AccountView,
         token_program: &AccountView,
     ) -> ProgramResult {
-        Create {
+        // 账户已经存在时 CreateIdempotent 只校验地址和所有者 不会报错
+        CreateIdempotent {
             funding_account: payer,
             account,
             wallet: owner,
@@ -122,20 +133,6 @@ impl AssociatedTokenAccountInit for AssociatedTokenAccount {
         }
         .invoke()
     }
-
-    fn init_if_needed(
-        account: &AccountView,
-        mint: &AccountView,
-        payer: &AccountView,
-        owner: &AccountView,
-        system_program: &AccountView,
-        token_program: &AccountView,
-    ) -> ProgramResult {
-        match Self::check(account, payer, mint.address(), token_program) {
-            Ok(_) => Ok(()),
-            Err(_) => Self::init(account, mint, payer, owner, system_program, token_program),
-        }
-    }
 }
 
 pub struct ProgramAccount;
@@ -155,11 +152,7 @@ impl AccountCheck for ProgramAccount {
             return Err(ProgramError::InvalidAccountOwner);
         }
 
-        if account.data_len().ne(&crate::state::Config::LEN) {
-            return Err(ProgramError::InvalidAccountData);
-        }
-
-        Ok(())
+        crate::state::Config::check_len(account)
     }
 }
 
@@ -0,0 +1,275 @@
+use crate::instructions::helper::{
+    AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck, MintInterface,
+    ProgramAccount, SignerAccount,
+};
+use crate::instructions::token_interface::{
+    check_mint, mint_unchecked, TokenProgram, TransferChecked, TransferFee,
+};
+use crate::{tick_array_address, AmmError, ClPool, Config, Position, ProgramState, TickArray};
+use pinocchio::{
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+
+pub struct IncreaseLiquidityAccounts<'a> {
+    // 头寸的所有者 支付加入的代币。
+    pub owner: &'a AccountView,
+    // 所有者的代币 X 关联账户。
+    pub owner_x_ata: &'a AccountView,
+    // 所有者的代币 Y 关联账户。
+    pub owner_y_ata: &'a AccountView,
+    // 要增加流动性的头寸账户。
+    pub position: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+    // 池子的集中流动性状态账户。
+    pub cl_pool: &'a AccountView,
+    // 包含区间下界的 tick 数组。
+    pub tick_array_lower: &'a AccountView,
+    // 包含区间上界的 tick 数组 可以和 tick_array_lower 是同一个账户。
+    pub tick_array_upper: &'a AccountView,
+    // 持有所有存入池中的代币 X 的代币账户。
+    pub vault_x: &'a AccountView,
+    // 持有所有存入池中的代币 Y 的代币账户。
+    pub vault_y: &'a AccountView,
+    // 池中代币 X 的铸币账户。
+    pub mint_x: &'a AccountView,
+    // 池中代币 Y 的铸币账户。
+    pub mint_y: &'a AccountView,
+    // 代币 X 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_x: &'a AccountView,
+    // 代币 Y 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_y: &'a AccountView,
+    // 白名单池子额外传入用户的白名单账户 其他池子不需要。
+    pub remaining: &'a [AccountView],
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for IncreaseLiquidityAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [owner, owner_x_ata, owner_y_ata, position, config, cl_pool, tick_array_lower, tick_array_upper, vault_x, vault_y, mint_x, mint_y, token_program_x, token_program_y, remaining @ ..] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(owner)?;
+        ProgramAccount::check(config)?;
+        MintInterface::check(mint_x)?;
+        MintInterface::check(mint_y)?;
+        TokenProgram::check(token_program_x)?;
+        TokenProgram::check(token_program_y)?;
+        Ok(Self {
+            owner,
+            owner_x_ata,
+            owner_y_ata,
+            position,
+            config,
+            cl_pool,
+            tick_array_lower,
+            tick_array_upper,
+            vault_x,
+            vault_y,
+            mint_x,
+            mint_y,
+            token_program_x,
+            token_program_y,
+            remaining,
+        })
+    }
+}
+
+pub struct IncreaseLiquidityInstructionData {
+    // 要加入的流动性。
+    pub liquidity: u128,
+    // 最多支付的代币 X 数量。
+    pub max_x: u64,
+    // 最多支付的代币 Y 数量。
+    pub max_y: u64,
+    // 此订单的过期时间。
+    pub expiration: i64,
+}
+
+impl TryFrom<&[u8]> for IncreaseLiquidityInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u128>() + size_of::<u64>() * 2 + size_of::<i64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let liquidity = u128::from_le_bytes(data[0..16].try_into().unwrap());
+        let max_x = u64::from_le_bytes(data[16..24].try_into().unwrap());
+        let max_y = u64::from_le_bytes(data[24..32].try_into().unwrap());
+        let expiration = i64::from_le_bytes(data[32..40].try_into().unwrap());
+
+        // Instruction Checks
+        if liquidity == 0 || liquidity > i128::MAX as u128 {
+            return Err(AmmError::ZeroAmount.into());
+        }
+        if Clock::get()?.unix_timestamp > expiration {
+            return Err(AmmError::Expired.into());
+        }
+        Ok(Self {
+            liquidity,
+            max_x,
+            max_y,
+            expiration,
+        })
+    }
+}
+
+// 给集中流动性头寸加入流动性 价格在区间下方只需要 X 在上方只需要 Y 在区间内两者都要
+pub struct IncreaseLiquidity<'a> {
+    pub accounts: IncreaseLiquidityAccounts<'a>,
+    pub instruction_data: IncreaseLiquidityInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for IncreaseLiquidity<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = IncreaseLiquidityAccounts::try_from(accounts)?;
+        let instruction_data = IncreaseLiquidityInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> IncreaseLiquidity<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+        config.check_tradable()?;
+        // 白名单池子只允许白名单里的用户注入流动性
+        config.check_allow_listed(
+            self.accounts.config.address(),
+            self.accounts.owner.address(),
+            self.accounts.remaining,
+        )?;
+        check_mint(
+            self.accounts.mint_x,
+            config.mint_x(),
+            self.accounts.token_program_x,
+        )?;
+        check_mint(
+            self.accounts.mint_y,
+            config.mint_y(),
+            self.accounts.token_program_y,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.owner_x_ata,
+            self.accounts.owner,
+            config.mint_x(),
+            self.accounts.token_program_x,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.owner_y_ata,
+            self.accounts.owner,
+            config.mint_y(),
+            self.accounts.token_program_y,
+        )?;
+
+        config.check_vaults(
+            self.accounts.vault_x.address(),
+            self.accounts.vault_y.address(),
+        )?;
+        drop(config);
+
+        let (x, y) = modify_liquidity(
+            self.accounts.owner,
+            self.accounts.position,
+            self.accounts.config,
+            self.accounts.cl_pool,
+            self.accounts.tick_array_lower,
+            self.accounts.tick_array_upper,
+            self.instruction_data.liquidity as i128,
+        )?;
+
+        // Token-2022 转账费 用户多付一些让金库实际收到 x 和 y
+        let epoch = Clock::get()?.epoch;
+        let amount_x = TransferFee::from_mint(self.accounts.mint_x, epoch)?.amount_before_fee(x)?;
+        let amount_y = TransferFee::from_mint(self.accounts.mint_y, epoch)?.amount_before_fee(y)?;
+        if amount_x > self.instruction_data.max_x || amount_y > self.instruction_data.max_y {
+            return Err(AmmError::SlippageExceeded.into());
+        }
+
+        let mint_x = unsafe { mint_unchecked(self.accounts.mint_x)? };
+        let mint_y = unsafe { mint_unchecked(self.accounts.mint_y)? };
+        if amount_x > 0 {
+            TransferChecked {
+                from: self.accounts.owner_x_ata,
+                mint: self.accounts.mint_x,
+                to: self.accounts.vault_x,
+                authority: self.accounts.owner,
+                amount: amount_x,
+                decimals: mint_x.decimals(),
+                token_program: self.accounts.token_program_x,
+            }
+            .invoke()?;
+        }
+        if amount_y > 0 {
+            TransferChecked {
+                from: self.accounts.owner_y_ata,
+                mint: self.accounts.mint_y,
+                to: self.accounts.vault_y,
+                authority: self.accounts.owner,
+                amount: amount_y,
+                decimals: mint_y.decimals(),
+                token_program: self.accounts.token_program_y,
+            }
+            .invoke()?;
+        }
+        Ok(())
+    }
+}
+
+// IncreaseLiquidity 和 DecreaseLiquidity 共用 校验头寸和 tick 数组之后按 delta 修改流动性
+// 返回需要存入或者可以取出的代币数量
+pub(crate) fn modify_liquidity(
+    owner: &AccountView,
+    position: &AccountView,
+    config: &AccountView,
+    cl_pool: &AccountView,
+    tick_array_lower: &AccountView,
+    tick_array_upper: &AccountView,
+    delta: i128,
+) -> Result<(u64, u64), ProgramError> {
+    let mut position = Position::load_mut(position)?;
+    if position.owner().ne(owner.address()) || position.config().ne(config.address()) {
+        return Err(AmmError::InvalidPosition.into());
+    }
+    let mut cl_pool = ClPool::load_mut(cl_pool)?;
+    if cl_pool.config().ne(config.address()) {
+        return Err(AmmError::InvalidCurve.into());
+    }
+    let tick_spacing = cl_pool.tick_spacing();
+    let (tick_lower, tick_upper) = (position.tick_lower(), position.tick_upper());
+    for (tick_array, tick) in [
+        (tick_array_lower, tick_lower),
+        (tick_array_upper, tick_upper),
+    ] {
+        let start_tick = TickArray::start_tick_of(tick, tick_spacing);
+        let (address, _) = tick_array_address(config.address(), start_tick);
+        if address.ne(tick_array.address()) {
+            return Err(AmmError::InvalidTickArray.into());
+        }
+    }
+
+    // 两个边界在同一个数组里时只能可变地借用一次
+    if tick_array_lower.address() == tick_array_upper.address() {
+        let mut tick_array = TickArray::load_mut(tick_array_lower)?;
+        let (lower, upper) = tick_array.tick_pair_mut(tick_lower, tick_upper, tick_spacing)?;
+        cl_pool.modify_position(&mut position, lower, upper, delta)
+    } else {
+        let mut lower = TickArray::load_mut(tick_array_lower)?;
+        let mut upper = TickArray::load_mut(tick_array_upper)?;
+        cl_pool.modify_position(
+            &mut position,
+            lower.tick_mut(tick_lower, tick_spacing)?,
+            upper.tick_mut(tick_upper, tick_spacing)?,
+            delta,
+        )
+    }
+}
@@ -1,6 +1,15 @@
 use crate::{
-    instructions::helper::{AccountCheck, ProgramAccount, ProgramAccountInit, SignerAccount},
-    Config,
+    instructions::helper::{
+        AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck,
+        AssociatedTokenAccountInit, MintInterface, ProgramAccount, ProgramAccountInit,
+        SignerAccount,
+    },
+    instructions::token_interface::{
+        check_metadata_symbol, check_mint, mint_unchecked, token_metadata_len,
+        InitializeMetadataPointer, InitializeMint2, InitializeTokenMetadata, TokenProgram,
+        MAX_URI_LEN, MINT_WITH_METADATA_POINTER_LEN, TOKEN_2022_PROGRAM_ID,
+    },
+    AmmError, AmmEvent, Config, Factory, InitializeEvent, Pair, ProgramState, RegistryPage,
 };
 use pinocchio::{
     cpi::{Seed, Signer},
@@ -9,9 +18,16 @@ use pinocchio::{
     AccountView, Address, ProgramResult,
 };
 use pinocchio_system::instructions::CreateAccount;
-use pinocchio_token::{instructions::InitializeMint2, state::Mint};
+use pinocchio_token::state::Mint;
 use std::mem::MaybeUninit;
 
+// LP 小数位数的上限
+pub const MAX_LP_DECIMALS: u8 = 18;
+// 交易对中每种代币 symbol 的长度上限
+pub const MAX_PAIR_SYMBOL_LEN: usize = 10;
+// fee 取这个值时使用工厂的默认交换费
+pub const DEFAULT_FEE: u16 = u16::MAX;
+
 pub struct InitializeAccounts<'a> {
     // config 账户的创建者。这不一定也必须是其权限持有者。
     pub initializer: &'a AccountView,
@@ -19,21 +35,77 @@ pub struct InitializeAccounts<'a> {
     pub mint_lp: &'a AccountView,
     // 正在初始化的配置账户。
     pub config: &'a AccountView,
+    // 系统程序 创建账户时使用。
+    pub system_program: &'a AccountView,
+    // LP 铸币账户所属的代币程序 SPL Token 或 Token-2022。
+    pub token_program: &'a AccountView,
+    // 池中代币 X 的铸币账户。
+    pub mint_x: &'a AccountView,
+    // 池中代币 Y 的铸币账户。
+    pub mint_y: &'a AccountView,
+    // 存储池中代币 X 的金库 即 config 的 X 代币关联账户 在这里创建。
+    pub vault_x: &'a AccountView,
+    // 存储池中代币 Y 的金库 即 config 的 Y 代币关联账户 在这里创建。
+    pub vault_y: &'a AccountView,
+    // 代币 X 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_x: &'a AccountView,
+    // 代币 Y 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_y: &'a AccountView,
+    // 关联代币账户程序 创建金库时使用。
+    pub associated_token_program: &'a AccountView,
+    // 池子工厂 新池子在这里登记并读取默认费率。
+    pub factory: &'a AccountView,
+    // 这对代币的交易对记录 第一个池子创建时一起创建。
+    pub pair: &'a AccountView,
+    // 新池子登记到的注册表分页 每页的第一个池子创建时一起创建。
+    pub registry_page: &'a AccountView,
 }
 
 impl<'a> TryFrom<&'a [AccountView]> for InitializeAccounts<'a> {
     type Error = ProgramError;
 
     fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
-        let [initializer, mint_lp, config, _, _] = accounts else {
+        let [initializer, mint_lp, config, system_program, token_program, mint_x, mint_y, vault_x, vault_y, token_program_x, token_program_y, associated_token_program, factory, pair, registry_page] =
+            accounts
+        else {
             return Err(ProgramError::NotEnoughAccountKeys);
         };
 
         SignerAccount::check(initializer)?;
+        if system_program.address().ne(&pinocchio_system::ID) {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if associated_token_program
+            .address()
+            .ne(&pinocchio_associated_token_account::ID)
+        {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        TokenProgram::check(token_program)?;
+        TokenProgram::check(token_program_x)?;
+        TokenProgram::check(token_program_y)?;
+        MintInterface::check(mint_x)?;
+        MintInterface::check(mint_y)?;
+        // config 和 mint_lp 都在这里创建 不能已经存在
+        if !config.is_data_empty() || !mint_lp.is_data_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
         Ok(Self {
             initializer,
             mint_lp,
             config,
+            system_program,
+            token_program,
+            mint_x,
+            mint_y,
+            vault_x,
+            vault_y,
+            token_program_x,
+            token_program_y,
+            associated_token_program,
+            factory,
+            pair,
+            registry_page,
         })
     }
 }
@@ -42,54 +114,137 @@ impl<'a> TryFrom<&'a [AccountView]> for InitializeAccounts<'a> {
 pub struct InitializeInstructionData {
     // 用于PDA（程序派生地址）种子推导的随机数。这允许创建唯一的池实例。
     pub seed: u64,
-    // 以基点表示的交换费（1基点=0.01%）。此费用在每次交易中收取，并分配给流动性提供者。
+    // 以基点表示的交换费（1基点=0.01%）。此费用在每次交易中收取，并分配给流动性提供者。DEFAULT_FEE 表示使用工厂的默认值。
     pub fee: u16,
     // 池中代币X的SPL代币铸造地址。
     pub mint_x: [u8; 32],
-    // 池中代币Y的SPL代币铸造地址。
+    // 池中代币Y的SPL代币铸造地址。mint_x 必须小于 mint_y 同一对代币只有一种顺序。
     pub mint_y: [u8; 32],
-    // 用于推导 config 账户PDA的bump种子。
+    // 旧格式的 config bump 程序现在自己推导规范的 bump 这个字节会被忽略 保留只为兼容已有的客户端。
     pub config_bump: [u8; 1],
-    // 用于推导 lp_mint 账户PDA的bump种子。
+    // 旧格式的 lp_mint bump 同样被忽略。
     pub lp_bump: [u8; 1],
     // 将拥有AMM管理权限的公钥。
     pub authority: [u8; 32],
+    // 曲线类型 0 表示恒定乘积 1 表示 StableSwap。省略时使用恒定乘积。
+    pub curve_type: u8,
+    // StableSwap 的放大系数 恒定乘积池忽略此字段。
+    pub amp: [u8; 8],
+    // LP 的小数位数相对于 X 和 Y 小数位数平均值的偏移。省略时为 0。
+    pub lp_decimals_offset: i8,
 }
 
 impl<'a> TryFrom<&'a [u8]> for InitializeInstructionData {
     type Error = ProgramError;
 
     fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
-        const INITIALIZE_DATA_LEN_WITH_AUTHORITY: usize = size_of::<InitializeInstructionData>();
+        const INITIALIZE_DATA_LEN_WITH_DECIMALS: usize = size_of::<InitializeInstructionData>();
+        const INITIALIZE_DATA_LEN_WITH_CURVE: usize =
+            INITIALIZE_DATA_LEN_WITH_DECIMALS - size_of::<i8>();
+        const INITIALIZE_DATA_LEN_WITH_AUTHORITY: usize =
+            INITIALIZE_DATA_LEN_WITH_CURVE - size_of::<u8>() - size_of::<[u8; 8]>();
         const INITIALIZE_DATA_LEN: usize =
             INITIALIZE_DATA_LEN_WITH_AUTHORITY - size_of::<[u8; 32]>();
 
-        match data.len() {
-            INITIALIZE_DATA_LEN_WITH_AUTHORITY => {
-                Ok(unsafe { (data.as_ptr() as *const Self).read_unaligned() })
+        let instruction_data = match data.len() {
+            INITIALIZE_DATA_LEN_WITH_DECIMALS => {
+                unsafe { (data.as_ptr() as *const Self).read_unaligned() }
             }
-            INITIALIZE_DATA_LEN => {
-                // If the authority is not present, we need to build the buffer and add it at the end before transmuting to the struct
-                let mut raw: MaybeUninit<[u8; INITIALIZE_DATA_LEN_WITH_AUTHORITY]> =
+            len @ (INITIALIZE_DATA_LEN
+            | INITIALIZE_DATA_LEN_WITH_AUTHORITY
+            | INITIALIZE_DATA_LEN_WITH_CURVE) => {
+                // If the authority, the curve or the decimals offset is not present, we need to build the buffer and zero the missing fields before transmuting to the struct
+                let mut raw: MaybeUninit<[u8; INITIALIZE_DATA_LEN_WITH_DECIMALS]> =
                     MaybeUninit::uninit();
                 let raw_ptr = raw.as_mut_ptr() as *mut u8;
                 unsafe {
                     // Copy the provided data
-                    core::ptr::copy_nonoverlapping(data.as_ptr(), raw_ptr, INITIALIZE_DATA_LEN);
-                    // Add the authority to the end of the buffer
-                    core::ptr::write_bytes(raw_ptr.add(INITIALIZE_DATA_LEN), 0, 32);
+                    core::ptr::copy_nonoverlapping(data.as_ptr(), raw_ptr, len);
+                    // Zero the missing fields at the end of the buffer
+                    core::ptr::write_bytes(
+                        raw_ptr.add(len),
+                        0,
+                        INITIALIZE_DATA_LEN_WITH_DECIMALS - len,
+                    );
                     // Now transmute to the struct
-                    Ok((raw.as_ptr() as *const Self).read_unaligned())
+                    (raw.as_ptr() as *const Self).read_unaligned()
                 }
             }
-            _ => Err(ProgramError::InvalidInstructionData),
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
+        // 费率不能达到 100%
+        if instruction_data.fee >= 10_000 && instruction_data.fee != DEFAULT_FEE {
+            return Err(AmmError::InvalidFee.into());
+        }
+        // 按地址字节排序的规范顺序 同时保证两种代币不同
+        if instruction_data.mint_x >= instruction_data.mint_y {
+            return Err(AmmError::InvalidMintOrder.into());
+        }
+        Ok(instruction_data)
+    }
+}
+
+// 可选的 LP 元数据 跟在 InitializeInstructionData 的完整格式后面
+// symbol_x_len u8 | symbol_x | symbol_y_len u8 | symbol_y | uri_len u8 | uri
+// LP 的名称是 "X-Y LP" symbol 是 "X-Y-LP" 只能用于 Token-2022 的 LP 铸币
+pub struct LpMetadata<'a> {
+    // 代币 X 的 symbol。
+    pub symbol_x: &'a [u8],
+    // 代币 Y 的 symbol。
+    pub symbol_y: &'a [u8],
+    // 元数据 JSON 的链接 可以为空。
+    pub uri: &'a [u8],
+}
+
+impl<'a> TryFrom<&'a [u8]> for LpMetadata<'a> {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        let mut rest = data;
+        let mut next = |max: usize| -> Result<&'a [u8], ProgramError> {
+            let (&len, tail) = rest.split_first().ok_or(AmmError::InvalidMetadata)?;
+            let len = len as usize;
+            if len > max || tail.len() < len {
+                return Err(AmmError::InvalidMetadata.into());
+            }
+            let (value, tail) = tail.split_at(len);
+            // 只允许可见的 ASCII 字符 防止用相似的 Unicode 字符伪装成别的代币
+            if !value.iter().all(u8::is_ascii_graphic) {
+                return Err(AmmError::InvalidMetadata.into());
+            }
+            rest = tail;
+            Ok(value)
+        };
+        let symbol_x = next(MAX_PAIR_SYMBOL_LEN)?;
+        let symbol_y = next(MAX_PAIR_SYMBOL_LEN)?;
+        let uri = next(MAX_URI_LEN)?;
+        if symbol_x.is_empty() || symbol_y.is_empty() || !rest.is_empty() {
+            return Err(AmmError::InvalidMetadata.into());
         }
+        Ok(Self {
+            symbol_x,
+            symbol_y,
+            uri,
+        })
+    }
+}
+
+impl LpMetadata<'_> {
+    // 把 "X-Y" 和后缀写进 buf 返回写入的长度
+    pub fn write_pair(&self, suffix: &[u8], buf: &mut [u8]) -> usize {
+        let mut len = 0;
+        for part in [self.symbol_x, b"-", self.symbol_y, suffix] {
+            buf[len..len + part.len()].copy_from_slice(part);
+            len += part.len();
+        }
+        len
     }
 }
 
 pub struct Initialize<'a> {
     pub accounts: InitializeAccounts<'a>,
     pub instruction_data: InitializeInstructionData,
+    pub metadata: Option<LpMetadata<'a>>,
 }
 
 impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for Initialize<'a> {
@@ -97,30 +252,95 @@ impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for Initialize<'a> {
 
     fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
         let accounts = InitializeAccounts::try_from(accounts)?;
+        // 超出完整格式的部分是 LP 元数据
+        let (data, metadata) = match data.len() > size_of::<InitializeInstructionData>() {
+            true => {
+                let (data, metadata) = data.split_at(size_of::<InitializeInstructionData>());
+                (data, Some(LpMetadata::try_from(metadata)?))
+            }
+            false => (data, None),
+        };
         let instruction_data = InitializeInstructionData::try_from(data)?;
 
         Ok(Self {
             accounts,
             instruction_data,
+            metadata,
         })
     }
 }
 
 impl<'a> Initialize<'a> {
     pub fn process(&self) -> ProgramResult {
+        let mint_x = Address::from(self.instruction_data.mint_x);
+        let mint_y = Address::from(self.instruction_data.mint_y);
+        check_mint(self.accounts.mint_x, &mint_x, self.accounts.token_program_x)?;
+        check_mint(self.accounts.mint_y, &mint_y, self.accounts.token_program_y)?;
+
+        // LP 的小数位数取 X 和 Y 的平均值 再加上调用者指定的偏移
+        let decimals_x = unsafe { mint_unchecked(self.accounts.mint_x)? }.decimals();
+        let decimals_y = unsafe { mint_unchecked(self.accounts.mint_y)? }.decimals();
+        let lp_decimals = ((decimals_x as i16 + decimals_y as i16) / 2)
+            .checked_add(self.instruction_data.lp_decimals_offset as i16)
+            .filter(|decimals| (0..=MAX_LP_DECIMALS as i16).contains(decimals))
+            .ok_or(AmmError::InvalidLpDecimals)? as u8;
+
+        // 元数据存在 LP 铸币自己的扩展里 只有 Token-2022 支持
+        if let Some(metadata) = &self.metadata {
+            if self
+                .accounts
+                .token_program
+                .address()
+                .ne(&TOKEN_2022_PROGRAM_ID)
+            {
+                return Err(AmmError::InvalidMetadata.into());
+            }
+            // X 和 Y 自带元数据时 名称里的 symbol 必须和它们一致
+            check_metadata_symbol(self.accounts.mint_x, metadata.symbol_x)?;
+            check_metadata_symbol(self.accounts.mint_y, metadata.symbol_y)?;
+        }
+
+        // 所有池子都要登记到工厂 工厂暂停时不能创建
+        let (factory_address, _) = Address::find_program_address(&[b"factory"], &crate::ID);
+        if factory_address.ne(self.accounts.factory.address()) {
+            return Err(AmmError::InvalidFactory.into());
+        }
+        let mut factory = Factory::load_mut(self.accounts.factory)?;
+        factory.check_not_paused()?;
+        let fee = match self.instruction_data.fee {
+            DEFAULT_FEE => factory.default_fee(),
+            fee => fee,
+        };
+
+        // 自己推导规范的 bump 不信任调用者传入的值
         let seed_binding = self.instruction_data.seed.to_le_bytes();
+        let (config_address, config_bump) = Address::find_program_address(
+            &[b"config", &seed_binding, mint_x.as_ref(), mint_y.as_ref()],
+            &crate::ID,
+        );
+        if config_address.ne(self.accounts.config.address()) {
+            return Err(AmmError::InvalidConfig.into());
+        }
+        let (mint_lp_address, lp_bump) =
+            Address::find_program_address(&[b"mint_lp", config_address.as_ref()], &crate::ID);
+        if mint_lp_address.ne(self.accounts.mint_lp.address()) {
+            return Err(AmmError::InvalidLpMint.into());
+        }
+
+        let config_bump = [config_bump];
         let config_seeds = [
             Seed::from(b"config"),
             Seed::from(&seed_binding),
-            Seed::from(&self.instruction_data.mint_x),
-            Seed::from(&self.instruction_data.mint_y),
-            Seed::from(&self.instruction_data.config_bump),
+            Seed::from(mint_x.as_ref()),
+            Seed::from(mint_y.as_ref()),
+            Seed::from(&config_bump),
         ];
 
+        let lp_bump = [lp_bump];
         let mint_lp_seeds = [
             Seed::from(b"mint_lp"),
             Seed::from(self.accounts.config.address().as_ref()),
-            Seed::from(&self.instruction_data.lp_bump),
+            Seed::from(&lp_bump),
         ];
 
         // Initialize the config
@@ -145,35 +365,196 @@ impl<'a> Initialize<'a> {
         config.set_inner(
             self.instruction_data.seed,
             Address::from(self.instruction_data.authority),
-            Address::from(self.instruction_data.mint_x),
-            Address::from(self.instruction_data.mint_y),
-            self.instruction_data.fee,
-            self.instruction_data.config_bump,
+            mint_x,
+            mint_y,
+            fee,
+            config_bump,
         )?;
+        config.set_protocol_fee(factory.default_protocol_fee())?;
+        config.set_curve(
+            self.instruction_data.curve_type,
+            u64::from_le_bytes(self.instruction_data.amp),
+        )?;
+        // 创建金库时 config 是关联账户的所有者 需要先释放可变借用
+        drop(config);
         // 创建 mint_lp
-        let lp_lamports = Rent::get()?.try_minimum_balance(Mint::LEN)?;
-
         // Create signer with seeds slice
         let lp_signer = [Signer::from(&mint_lp_seeds)];
-        // Create the account
-        CreateAccount {
-            from: self.accounts.initializer,
-            to: self.accounts.mint_lp,
-            lamports: lp_lamports,
-            space: Mint::LEN as u64,
-            // 这个地方 owner 必须是 token_program 的地址或者 token_2022_program 的地址
-            owner: &pinocchio_token::ID,
-        }
-        .invoke_signed(&lp_signer)?;
-        // 初始化铸币账户
-        InitializeMint2 {
-            mint: self.accounts.mint_lp,
-            decimals: 6,
-            mint_authority: self.accounts.config.address(),
-            freeze_authority: Some(self.accounts.config.address()),
-        }
-        .invoke()?;
+        match &self.metadata {
+            Some(metadata) => {
+                let mut name = [0u8; MAX_PAIR_SYMBOL_LEN * 2 + 4];
+                let name_len = metadata.write_pair(b" LP", &mut name);
+                let mut symbol = [0u8; MAX_PAIR_SYMBOL_LEN * 2 + 4];
+                let symbol_len = metadata.write_pair(b"-LP", &mut symbol);
+                let (name, symbol) = (&name[..name_len], &symbol[..symbol_len]);
 
+                // 账户先按 MetadataPointer 的长度创建 写入元数据时 Token-2022 会自己扩容 所以租金要按最终长度预付
+                let lp_lamports = Rent::get()?.try_minimum_balance(
+                    MINT_WITH_METADATA_POINTER_LEN + token_metadata_len(name, symbol, metadata.uri),
+                )?;
+                CreateAccount {
+                    from: self.accounts.initializer,
+                    to: self.accounts.mint_lp,
+                    lamports: lp_lamports,
+                    space: MINT_WITH_METADATA_POINTER_LEN as u64,
+                    owner: self.accounts.token_program.address(),
+                }
+                .invoke_signed(&lp_signer)?;
+                // 元数据就存在铸币账户自己身上 由 config 负责更新
+                InitializeMetadataPointer {
+                    mint: self.accounts.mint_lp,
+                    authority: self.accounts.config.address(),
+                    metadata_address: self.accounts.mint_lp.address(),
+                    token_program: self.accounts.token_program,
+                }
+                .invoke()?;
+                InitializeMint2 {
+                    mint: self.accounts.mint_lp,
+                    decimals: lp_decimals,
+                    mint_authority: self.accounts.config.address(),
+                    freeze_authority: Some(self.accounts.config.address()),
+                    token_program: self.accounts.token_program,
+                }
+                .invoke()?;
+                InitializeTokenMetadata {
+                    mint: self.accounts.mint_lp,
+                    update_authority: self.accounts.config,
+                    mint_authority: self.accounts.config,
+                    name,
+                    symbol,
+                    uri: metadata.uri,
+                    token_program: self.accounts.token_program,
+                }
+                .invoke_signed(&config_signer)?;
+            }
+            None => {
+                let lp_lamports = Rent::get()?.try_minimum_balance(Mint::LEN)?;
+                // Create the account
+                CreateAccount {
+                    from: self.accounts.initializer,
+                    to: self.accounts.mint_lp,
+                    lamports: lp_lamports,
+                    space: Mint::LEN as u64,
+                    // 这个地方 owner 必须是 token_program 的地址或者 token_2022_program 的地址
+                    owner: self.accounts.token_program.address(),
+                }
+                .invoke_signed(&lp_signer)?;
+                // 初始化铸币账户
+                InitializeMint2 {
+                    mint: self.accounts.mint_lp,
+                    decimals: lp_decimals,
+                    mint_authority: self.accounts.config.address(),
+                    freeze_authority: Some(self.accounts.config.address()),
+                    token_program: self.accounts.token_program,
+                }
+                .invoke()?;
+            }
+        }
+
+        // 创建两个金库 已经被别人提前创建时跳过
+        AssociatedTokenAccount::init_if_needed(
+            self.accounts.vault_x,
+            self.accounts.mint_x,
+            self.accounts.initializer,
+            self.accounts.config,
+            self.accounts.system_program,
+            self.accounts.token_program_x,
+        )?;
+        AssociatedTokenAccount::init_if_needed(
+            self.accounts.vault_y,
+            self.accounts.mint_y,
+            self.accounts.initializer,
+            self.accounts.config,
+            self.accounts.system_program,
+            self.accounts.token_program_y,
+        )?;
+        // 金库可能被别人提前创建 CreateIdempotent 对已经存在的账户只检查所有者和铸币 不检查地址
+        // 所以自己推导关联账户地址再比较 之后的指令只需要和这里记录的地址比较
+        AssociatedTokenAccount::check(
+            self.accounts.vault_x,
+            self.accounts.config,
+            &mint_x,
+            self.accounts.token_program_x,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.vault_y,
+            self.accounts.config,
+            &mint_y,
+            self.accounts.token_program_y,
+        )?;
+        Config::load_mut(self.accounts.config)?.set_vaults(
+            *self.accounts.vault_x.address(),
+            *self.accounts.vault_y.address(),
+        );
+
+        self.register(&mut factory, &mint_x, &mint_y)?;
+
+        AmmEvent::Initialize(InitializeEvent {
+            pool: *self.accounts.config.address(),
+            authority: Address::from(self.instruction_data.authority),
+            mint_x,
+            mint_y,
+            mint_lp: *self.accounts.mint_lp.address(),
+            seed: self.instruction_data.seed,
+            fee,
+        })
+        .emit();
         Ok(())
     }
+
+    // 把池子登记到交易对记录和注册表分页 两者都在第一次用到时创建
+    fn register(&self, factory: &mut Factory, mint_x: &Address, mint_y: &Address) -> ProgramResult {
+        let config = self.accounts.config.address();
+
+        let (pair_address, pair_bump) =
+            Address::find_program_address(&[b"pair", mint_x.as_ref(), mint_y.as_ref()], &crate::ID);
+        if pair_address.ne(self.accounts.pair.address()) {
+            return Err(AmmError::InvalidRegistry.into());
+        }
+        if self.accounts.pair.is_data_empty() {
+            let pair_bump = [pair_bump];
+            let pair_seeds = [
+                Seed::from(b"pair"),
+                Seed::from(mint_x.as_ref()),
+                Seed::from(mint_y.as_ref()),
+                Seed::from(&pair_bump),
+            ];
+            ProgramAccount::init::<Pair>(
+                self.accounts.initializer,
+                self.accounts.pair,
+                &pair_seeds,
+                Pair::LEN,
+            )?;
+            Pair::load_mut(self.accounts.pair)?.set_inner(
+                *mint_x,
+                *mint_y,
+                pair_bump,
+            );
+        }
+        Pair::load_mut(self.accounts.pair)?.add_pool(config)?;
+
+        let (page, slot) = factory.register_pool()?;
+        let page_binding = page.to_le_bytes();
+        let (page_address, page_bump) =
+            Address::find_program_address(&[b"registry", &page_binding], &crate::ID);
+        if page_address.ne(self.accounts.registry_page.address()) {
+            return Err(AmmError::InvalidRegistry.into());
+        }
+        if slot == 0 {
+            let page_bump = [page_bump];
+            let page_seeds = [
+                Seed::from(b"registry"),
+                Seed::from(&page_binding),
+                Seed::from(&page_bump),
+            ];
+            ProgramAccount::init::<RegistryPage>(
+                self.accounts.initializer,
+                self.accounts.registry_page,
+                &page_seeds,
+                RegistryPage::LEN,
+            )?;
+            RegistryPage::load_mut(self.accounts.registry_page)?.set_inner(page, page_bump);
+        }
+        RegistryPage::load_mut(self.accounts.registry_page)?.push(slot, config)
+    }
 }
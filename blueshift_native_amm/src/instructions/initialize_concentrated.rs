@@ -0,0 +1,132 @@
+use crate::instructions::helper::{
+    AccountCheck, ProgramAccount, ProgramAccountInit, SignerAccount,
+};
+use crate::{
+    tick_at_sqrt_price, AmmError, ClPool, Config, CurveType, ProgramState, MAX_SQRT_PRICE,
+    MIN_SQRT_PRICE,
+};
+use pinocchio::{cpi::Seed, error::ProgramError, AccountView, Address, ProgramResult};
+
+// tick_spacing 的上限 太大时一个 tick 数组覆盖的范围会超出 tick 的范围
+pub const MAX_TICK_SPACING: u16 = 1_024;
+
+pub struct InitializeConcentratedAccounts<'a> {
+    // AMM 池的权限持有者 支付集中流动性池账户的租金。
+    pub authority: &'a AccountView,
+    // AMM 池的配置账户。曲线类型必须是集中流动性。
+    pub config: &'a AccountView,
+    // 正在创建的集中流动性池账户。
+    pub cl_pool: &'a AccountView,
+    // 系统程序账户。
+    pub system_program: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for InitializeConcentratedAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [authority, config, cl_pool, system_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        ProgramAccount::check(config)?;
+        if !cl_pool.is_data_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        Ok(Self {
+            authority,
+            config,
+            cl_pool,
+            system_program,
+        })
+    }
+}
+
+pub struct InitializeConcentratedInstructionData {
+    // 初始价格的平方根 Q64.64。
+    pub sqrt_price: u128,
+    // 头寸的上下界必须是它的整数倍。
+    pub tick_spacing: u16,
+}
+
+impl TryFrom<&[u8]> for InitializeConcentratedInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u128>() + size_of::<u16>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let sqrt_price = u128::from_le_bytes(data[0..16].try_into().unwrap());
+        let tick_spacing = u16::from_le_bytes(data[16..18].try_into().unwrap());
+        if !(MIN_SQRT_PRICE..MAX_SQRT_PRICE).contains(&sqrt_price) {
+            return Err(AmmError::InvalidTick.into());
+        }
+        if tick_spacing == 0 || tick_spacing > MAX_TICK_SPACING {
+            return Err(AmmError::InvalidTick.into());
+        }
+        Ok(Self {
+            sqrt_price,
+            tick_spacing,
+        })
+    }
+}
+
+// 为集中流动性的池子创建价格和流动性状态 头寸通过 OpenPosition 加入
+pub struct InitializeConcentrated<'a> {
+    pub accounts: InitializeConcentratedAccounts<'a>,
+    pub instruction_data: InitializeConcentratedInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for InitializeConcentrated<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = InitializeConcentratedAccounts::try_from(accounts)?;
+        let instruction_data = InitializeConcentratedInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> InitializeConcentrated<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+        config.check_authority(self.accounts.authority.address())?;
+        if CurveType::try_from(config.curve_type())? != CurveType::Concentrated {
+            return Err(AmmError::InvalidCurve.into());
+        }
+        drop(config);
+
+        let (cl_pool, bump) = Address::find_program_address(
+            &[b"cl_pool", self.accounts.config.address().as_ref()],
+            &crate::ID,
+        );
+        if cl_pool.ne(self.accounts.cl_pool.address()) {
+            return Err(AmmError::InvalidCurve.into());
+        }
+        let bump_binding = [bump];
+        let cl_pool_seeds = [
+            Seed::from(b"cl_pool"),
+            Seed::from(self.accounts.config.address().as_ref()),
+            Seed::from(&bump_binding),
+        ];
+        ProgramAccount::init::<ClPool>(
+            self.accounts.authority,
+            self.accounts.cl_pool,
+            &cl_pool_seeds,
+            ClPool::LEN,
+        )?;
+
+        let mut cl_pool = ClPool::load_mut(self.accounts.cl_pool)?;
+        cl_pool.set_inner(
+            *self.accounts.config.address(),
+            self.instruction_data.sqrt_price,
+            tick_at_sqrt_price(self.instruction_data.sqrt_price)?,
+            self.instruction_data.tick_spacing,
+            bump_binding,
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use crate::instructions::helper::{
+    AccountCheck, ProgramAccount, ProgramAccountInit, SignerAccount,
+};
+use crate::{AmmError, Factory, ProgramState};
+use pinocchio::{cpi::Seed, error::ProgramError, AccountView, Address, ProgramResult};
+
+pub struct InitializeFactoryAccounts<'a> {
+    // 工厂的管理员 同时支付工厂账户的租金。
+    pub admin: &'a AccountView,
+    // 正在初始化的工厂账户。
+    pub factory: &'a AccountView,
+    // 系统程序账户。
+    pub system_program: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for InitializeFactoryAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [admin, factory, system_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(admin)?;
+        if !factory.is_data_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        Ok(Self {
+            admin,
+            factory,
+            system_program,
+        })
+    }
+}
+
+pub struct InitializeFactoryInstructionData {
+    // 新池子默认的交换费 以基点表示。
+    pub default_fee: u16,
+    // 新池子默认的协议费比例 以基点表示。
+    pub default_protocol_fee: u16,
+}
+
+impl<'a> TryFrom<&'a [u8]> for InitializeFactoryInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u16>() * 2 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let default_fee = u16::from_le_bytes(data[0..2].try_into().unwrap());
+        let default_protocol_fee = u16::from_le_bytes(data[2..4].try_into().unwrap());
+        Ok(Self {
+            default_fee,
+            default_protocol_fee,
+        })
+    }
+}
+
+// 创建全局唯一的工厂 只能执行一次 应该在部署程序的同一笔交易里完成
+pub struct InitializeFactory<'a> {
+    pub accounts: InitializeFactoryAccounts<'a>,
+    pub instruction_data: InitializeFactoryInstructionData,
+    pub bump: u8,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for InitializeFactory<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = InitializeFactoryAccounts::try_from(accounts)?;
+        let instruction_data = InitializeFactoryInstructionData::try_from(data)?;
+        let (factory, bump) = Address::find_program_address(&[b"factory"], &crate::ID);
+        if factory.ne(accounts.factory.address()) {
+            return Err(AmmError::InvalidFactory.into());
+        }
+        Ok(Self {
+            accounts,
+            instruction_data,
+            bump,
+        })
+    }
+}
+
+impl<'a> InitializeFactory<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let bump_binding = [self.bump];
+        let factory_seeds = [Seed::from(b"factory"), Seed::from(&bump_binding)];
+        ProgramAccount::init::<Factory>(
+            self.accounts.admin,
+            self.accounts.factory,
+            &factory_seeds,
+            Factory::LEN,
+        )?;
+
+        let mut factory = Factory::load_mut(self.accounts.factory)?;
+        factory.set_inner(
+            *self.accounts.admin.address(),
+            self.instruction_data.default_fee,
+            self.instruction_data.default_protocol_fee,
+            bump_binding,
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+use crate::instructions::helper::{
+    AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountInit, MintInterface,
+    ProgramAccount, ProgramAccountInit, SignerAccount,
+};
+use crate::instructions::token_interface::{check_mint, TokenProgram};
+use crate::{AmmError, Config, Farm, ProgramState};
+use pinocchio::{
+    cpi::Seed,
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, Address, ProgramResult,
+};
+
+pub struct InitializeFarmAccounts<'a> {
+    // AMM 池的权限持有者 支付农场和两个金库的租金。
+    pub authority: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+    // 正在创建的农场账户。
+    pub farm: &'a AccountView,
+    // 池子的 LP 铸币账户 用户质押的就是它。
+    pub mint_lp: &'a AccountView,
+    // 奖励代币的铸币账户。
+    pub reward_mint: &'a AccountView,
+    // 农场持有质押 LP 的关联账户 在这里创建。
+    pub lp_vault: &'a AccountView,
+    // 农场持有奖励代币的关联账户 在这里创建。
+    pub reward_vault: &'a AccountView,
+    // LP 铸币账户所属的代币程序账户。
+    pub lp_token_program: &'a AccountView,
+    // 奖励代币所属的代币程序账户 SPL Token 或 Token-2022。
+    pub reward_token_program: &'a AccountView,
+    // 系统程序账户。
+    pub system_program: &'a AccountView,
+    // 关联代币账户程序 创建金库时使用。
+    pub associated_token_program: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for InitializeFarmAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [authority, config, farm, mint_lp, reward_mint, lp_vault, reward_vault, lp_token_program, reward_token_program, system_program, associated_token_program] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        ProgramAccount::check(config)?;
+        MintInterface::check(mint_lp)?;
+        MintInterface::check(reward_mint)?;
+        TokenProgram::check(lp_token_program)?;
+        TokenProgram::check(reward_token_program)?;
+        if !farm.is_data_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        Ok(Self {
+            authority,
+            config,
+            farm,
+            mint_lp,
+            reward_mint,
+            lp_vault,
+            reward_vault,
+            lp_token_program,
+            reward_token_program,
+            system_program,
+            associated_token_program,
+        })
+    }
+}
+
+pub struct InitializeFarmInstructionData {
+    // 每秒释放的奖励数量。
+    pub emission_rate: u64,
+}
+
+impl TryFrom<&[u8]> for InitializeFarmInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let emission_rate = u64::from_le_bytes(data.try_into().unwrap());
+        Ok(Self { emission_rate })
+    }
+}
+
+// 为池子创建 LP 质押农场 奖励需要之后通过 FundRewards 注入
+pub struct InitializeFarm<'a> {
+    pub accounts: InitializeFarmAccounts<'a>,
+    pub instruction_data: InitializeFarmInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for InitializeFarm<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = InitializeFarmAccounts::try_from(accounts)?;
+        let instruction_data = InitializeFarmInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> InitializeFarm<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+        config.check_authority(self.accounts.authority.address())?;
+        drop(config);
+
+        // LP 铸币账户必须是这个池子的 奖励不能是 LP 本身 否则两个金库是同一个账户
+        let (mint_lp, _) = Address::find_program_address(
+            &[b"mint_lp", self.accounts.config.address().as_ref()],
+            &crate::ID,
+        );
+        check_mint(
+            self.accounts.mint_lp,
+            &mint_lp,
+            self.accounts.lp_token_program,
+        )?;
+        if self.accounts.reward_mint.address().eq(&mint_lp)
+            || !self
+                .accounts
+                .reward_mint
+                .owned_by(self.accounts.reward_token_program.address())
+        {
+            return Err(AmmError::InvalidFarm.into());
+        }
+
+        let (farm, bump) = Address::find_program_address(
+            &[b"farm", self.accounts.config.address().as_ref()],
+            &crate::ID,
+        );
+        if farm.ne(self.accounts.farm.address()) {
+            return Err(AmmError::InvalidFarm.into());
+        }
+        let bump_binding = [bump];
+        let farm_seeds = [
+            Seed::from(b"farm"),
+            Seed::from(self.accounts.config.address().as_ref()),
+            Seed::from(&bump_binding),
+        ];
+        ProgramAccount::init::<Farm>(
+            self.accounts.authority,
+            self.accounts.farm,
+            &farm_seeds,
+            Farm::LEN,
+        )?;
+        Farm::load_mut(self.accounts.farm)?.set_inner(
+            *self.accounts.config.address(),
+            mint_lp,
+            *self.accounts.reward_mint.address(),
+            self.instruction_data.emission_rate,
+            Clock::get()?.unix_timestamp,
+            bump_binding,
+        );
+
+        // 两个金库都是农场的关联代币账户 由农场签名转出
+        AssociatedTokenAccount::init_if_needed(
+            self.accounts.lp_vault,
+            self.accounts.mint_lp,
+            self.accounts.authority,
+            self.accounts.farm,
+            self.accounts.system_program,
+            self.accounts.lp_token_program,
+        )?;
+        AssociatedTokenAccount::init_if_needed(
+            self.accounts.reward_vault,
+            self.accounts.reward_mint,
+            self.accounts.authority,
+            self.accounts.farm,
+            self.accounts.system_program,
+            self.accounts.reward_token_program,
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+use crate::instructions::helper::{
+    AccountCheck, ProgramAccount, ProgramAccountInit, SignerAccount,
+};
+use crate::{AmmError, Oracle, ProgramState};
+use pinocchio::{cpi::Seed, error::ProgramError, AccountView, Address, ProgramResult};
+
+pub struct InitializeOracleAccounts<'a> {
+    // 支付预言机账户租金的账户。任何人都可以为池子创建预言机。
+    pub payer: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+    // 正在初始化的价格预言机账户。
+    pub oracle: &'a AccountView,
+    // 系统程序账户。
+    pub system_program: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for InitializeOracleAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [payer, config, oracle, system_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(payer)?;
+        ProgramAccount::check(config)?;
+        Ok(Self {
+            payer,
+            config,
+            oracle,
+            system_program,
+        })
+    }
+}
+
+pub struct InitializeOracle<'a> {
+    pub accounts: InitializeOracleAccounts<'a>,
+    pub bump: u8,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for InitializeOracle<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let accounts = InitializeOracleAccounts::try_from(accounts)?;
+        let (oracle, bump) = Address::find_program_address(
+            &[b"oracle", accounts.config.address().as_ref()],
+            &crate::ID,
+        );
+        if oracle.ne(accounts.oracle.address()) {
+            return Err(AmmError::InvalidOracle.into());
+        }
+        Ok(Self { accounts, bump })
+    }
+}
+
+impl<'a> InitializeOracle<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let bump_binding = [self.bump];
+        let oracle_seeds = [
+            Seed::from(b"oracle"),
+            Seed::from(self.accounts.config.address().as_ref()),
+            Seed::from(&bump_binding),
+        ];
+        ProgramAccount::init::<Oracle>(
+            self.accounts.payer,
+            self.accounts.oracle,
+            &oracle_seeds,
+            Oracle::LEN,
+        )?;
+
+        let mut oracle = Oracle::load_mut(self.accounts.oracle)?;
+        oracle.set_inner(*self.accounts.config.address(), bump_binding);
+        Ok(())
+    }
+}
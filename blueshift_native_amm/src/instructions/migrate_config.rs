@@ -0,0 +1,144 @@
+use crate::instructions::helper::{AccountCheck, MintInterface, SignerAccount};
+use crate::instructions::token_interface::{check_mint, TokenProgram};
+use crate::{AmmError, Config, CONFIG_LEGACY_LEN, CONFIG_V1_LEN};
+use pinocchio::{
+    error::ProgramError,
+    sysvars::{rent::Rent, Sysvar},
+    AccountView, Address, ProgramResult,
+};
+use pinocchio_system::instructions::Transfer;
+
+pub struct MigrateConfigAccounts<'a> {
+    // 补足扩容所需租金的账户 任何人都可以迁移。
+    pub payer: &'a AccountView,
+    // 要升级的 AMM 池配置账户。
+    pub config: &'a AccountView,
+    // 系统程序账户。
+    pub system_program: &'a AccountView,
+    // 池中代币 X 的铸币账户 用来推导金库地址。
+    pub mint_x: &'a AccountView,
+    // 池中代币 Y 的铸币账户 用来推导金库地址。
+    pub mint_y: &'a AccountView,
+    // 代币 X 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_x: &'a AccountView,
+    // 代币 Y 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_y: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for MigrateConfigAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [payer, config, system_program, mint_x, mint_y, token_program_x, token_program_y] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(payer)?;
+        MintInterface::check(mint_x)?;
+        MintInterface::check(mint_y)?;
+        TokenProgram::check(token_program_x)?;
+        TokenProgram::check(token_program_y)?;
+        // 旧账户的长度和当前布局不同 不能用 ProgramAccount::check
+        if !config.owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+        // 只接受确定的旧布局长度 其他长度的程序账户不能被当作 Config 扩容
+        if ![CONFIG_LEGACY_LEN, CONFIG_V1_LEN, Config::LEN].contains(&config.data_len()) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            payer,
+            config,
+            system_program,
+            mint_x,
+            mint_y,
+            token_program_x,
+            token_program_y,
+        })
+    }
+}
+
+// 把旧布局的 Config 原地升级到当前版本 旧布局总是当前布局的前缀 扩容之后补 0 即可
+// 迁移只补上可以推导出来的字段 不改变池子的任何参数 所以不需要权限持有者签名
+pub struct MigrateConfig<'a> {
+    pub accounts: MigrateConfigAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for MigrateConfig<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let accounts = MigrateConfigAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> MigrateConfig<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let len = self.accounts.config.data_len();
+        if len < Config::LEN {
+            // 扩容之前补足租金
+            let lamports = Rent::get()?.try_minimum_balance(Config::LEN)?;
+            let balance = self.accounts.config.lamports();
+            if balance < lamports {
+                Transfer {
+                    from: self.accounts.payer,
+                    to: self.accounts.config,
+                    lamports: lamports - balance,
+                }
+                .invoke()?;
+            }
+            self.accounts.config.resize(Config::LEN)?;
+            // 新增的字节是之后字段的默认值 必须是 0
+            self.accounts.config.try_borrow_mut()?[len..].fill(0);
+        }
+
+        let mut config = Config::load_mut(self.accounts.config)?;
+        // 程序的其他账户也可能是这个长度 用 config 自己记录的种子确认它是池子的 PDA
+        let seed_binding = config.seed().to_le_bytes();
+        let config_address = Address::create_program_address(
+            &[
+                b"config",
+                &seed_binding,
+                config.mint_x().as_ref(),
+                config.mint_y().as_ref(),
+                &config.config_bump(),
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| AmmError::InvalidConfig)?;
+        if config_address.ne(self.accounts.config.address()) {
+            return Err(AmmError::InvalidConfig.into());
+        }
+        check_mint(
+            self.accounts.mint_x,
+            config.mint_x(),
+            self.accounts.token_program_x,
+        )?;
+        check_mint(
+            self.accounts.mint_y,
+            config.mint_y(),
+            self.accounts.token_program_y,
+        )?;
+
+        // 版本 2 在预留字节里记录金库地址 旧池子的金库是 config 持有的关联代币账户
+        let (vault_x, _) = Address::find_program_address(
+            &[
+                self.accounts.config.address().as_ref(),
+                self.accounts.token_program_x.address().as_ref(),
+                config.mint_x().as_ref(),
+            ],
+            &pinocchio_associated_token_account::ID,
+        );
+        let (vault_y, _) = Address::find_program_address(
+            &[
+                self.accounts.config.address().as_ref(),
+                self.accounts.token_program_y.address().as_ref(),
+                config.mint_y().as_ref(),
+            ],
+            &pinocchio_associated_token_account::ID,
+        );
+        config.migrate(vault_x, vault_y)
+    }
+}
@@ -1,10 +1,84 @@
+pub mod accept_authority;
+pub mod add_to_allow_list;
+pub mod cancel_order;
+pub mod claim;
+pub mod collect_protocol_fees;
+pub mod decrease_liquidity;
 pub mod deposit;
+pub mod execute_order;
+pub mod flash_borrow;
+pub mod flash_repay;
+pub mod fund_rewards;
+pub mod increase_liquidity;
 pub mod initialize;
+pub mod initialize_concentrated;
+pub mod initialize_factory;
+pub mod initialize_farm;
+pub mod initialize_oracle;
+pub mod migrate_config;
+pub mod open_position;
+pub mod place_order;
+pub mod quote;
+pub mod ramp_amp;
+pub mod record_observation;
+pub mod remove_from_allow_list;
+pub mod route_swap;
+pub mod set_dynamic_fee;
+pub mod set_emission_rate;
+pub mod set_factory;
+pub mod set_lp_frozen;
+pub mod set_permissioned;
+pub mod set_protocol_fee;
+pub mod set_state;
+pub mod stake;
 pub mod swap;
+pub mod transfer_authority;
+pub mod unstake;
+pub mod update_fee;
 pub mod withdraw;
+pub mod zap_in;
+pub mod zap_out;
 mod helper;
+mod token_interface;
 
+pub use accept_authority::*;
+pub use add_to_allow_list::*;
+pub use cancel_order::*;
+pub use claim::*;
+pub use collect_protocol_fees::*;
+pub use decrease_liquidity::*;
 pub use deposit::*;
+pub use execute_order::*;
+pub use flash_borrow::*;
+pub use flash_repay::*;
+pub use fund_rewards::*;
+pub use increase_liquidity::*;
 pub use initialize::*;
+pub use initialize_concentrated::*;
+pub use initialize_factory::*;
+pub use initialize_farm::*;
+pub use initialize_oracle::*;
+pub use migrate_config::*;
+pub use open_position::*;
+pub use place_order::*;
+pub use quote::*;
+pub use ramp_amp::*;
+pub use record_observation::*;
+pub use remove_from_allow_list::*;
+pub use route_swap::*;
+pub use set_dynamic_fee::*;
+pub use set_emission_rate::*;
+pub use set_factory::*;
+pub use set_lp_frozen::*;
+pub use set_permissioned::*;
+pub use set_protocol_fee::*;
+pub use set_state::*;
+pub use stake::*;
 pub use swap::*;
-pub use withdraw::*;
\ No newline at end of file
+pub use transfer_authority::*;
+pub use unstake::*;
+pub use update_fee::*;
+pub use withdraw::*;
+pub use zap_in::*;
+pub use zap_out::*;
+pub use token_interface::TOKEN_2022_PROGRAM_ID;
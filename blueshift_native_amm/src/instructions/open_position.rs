@@ -0,0 +1,186 @@
+use crate::instructions::helper::{
+    AccountCheck, ProgramAccount, ProgramAccountInit, SignerAccount,
+};
+use crate::{tick_array_address, AmmError, ClPool, Position, ProgramState, TickArray};
+use pinocchio::{cpi::Seed, error::ProgramError, AccountView, Address, ProgramResult};
+
+pub struct OpenPositionAccounts<'a> {
+    // 头寸的所有者 支付头寸和 tick 数组账户的租金。
+    pub owner: &'a AccountView,
+    // 正在创建的头寸账户。
+    pub position: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+    // 池子的集中流动性状态账户。
+    pub cl_pool: &'a AccountView,
+    // 包含区间下界的 tick 数组 不存在时在这里创建。
+    pub tick_array_lower: &'a AccountView,
+    // 包含区间上界的 tick 数组 可以和 tick_array_lower 是同一个账户。
+    pub tick_array_upper: &'a AccountView,
+    // 系统程序账户。
+    pub system_program: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for OpenPositionAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [owner, position, config, cl_pool, tick_array_lower, tick_array_upper, system_program] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(owner)?;
+        ProgramAccount::check(config)?;
+        if !position.is_data_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        Ok(Self {
+            owner,
+            position,
+            config,
+            cl_pool,
+            tick_array_lower,
+            tick_array_upper,
+            system_program,
+        })
+    }
+}
+
+pub struct OpenPositionInstructionData {
+    // 区间下界 包含在区间内。
+    pub tick_lower: i32,
+    // 区间上界 不包含在区间内。
+    pub tick_upper: i32,
+}
+
+impl TryFrom<&[u8]> for OpenPositionInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<i32>() * 2 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let tick_lower = i32::from_le_bytes(data[0..4].try_into().unwrap());
+        let tick_upper = i32::from_le_bytes(data[4..8].try_into().unwrap());
+        if tick_lower >= tick_upper {
+            return Err(AmmError::InvalidTick.into());
+        }
+        Ok(Self {
+            tick_lower,
+            tick_upper,
+        })
+    }
+}
+
+// 创建一个价格区间的空头寸 流动性通过 IncreaseLiquidity 加入
+// 同一个用户在同一个区间只能有一个头寸
+pub struct OpenPosition<'a> {
+    pub accounts: OpenPositionAccounts<'a>,
+    pub instruction_data: OpenPositionInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for OpenPosition<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = OpenPositionAccounts::try_from(accounts)?;
+        let instruction_data = OpenPositionInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> OpenPosition<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let cl_pool = ClPool::load(self.accounts.cl_pool)?;
+        if cl_pool.config().ne(self.accounts.config.address()) {
+            return Err(AmmError::InvalidCurve.into());
+        }
+        cl_pool.check_tick(self.instruction_data.tick_lower)?;
+        cl_pool.check_tick(self.instruction_data.tick_upper)?;
+        let tick_spacing = cl_pool.tick_spacing();
+        drop(cl_pool);
+
+        let lower_binding = self.instruction_data.tick_lower.to_le_bytes();
+        let upper_binding = self.instruction_data.tick_upper.to_le_bytes();
+        let (position, bump) = Address::find_program_address(
+            &[
+                b"position",
+                self.accounts.config.address().as_ref(),
+                self.accounts.owner.address().as_ref(),
+                &lower_binding,
+                &upper_binding,
+            ],
+            &crate::ID,
+        );
+        if position.ne(self.accounts.position.address()) {
+            return Err(AmmError::InvalidPosition.into());
+        }
+        let bump_binding = [bump];
+        let position_seeds = [
+            Seed::from(b"position"),
+            Seed::from(self.accounts.config.address().as_ref()),
+            Seed::from(self.accounts.owner.address().as_ref()),
+            Seed::from(&lower_binding),
+            Seed::from(&upper_binding),
+            Seed::from(&bump_binding),
+        ];
+        ProgramAccount::init::<Position>(
+            self.accounts.owner,
+            self.accounts.position,
+            &position_seeds,
+            Position::LEN,
+        )?;
+        Position::load_mut(self.accounts.position)?.set_inner(
+            *self.accounts.owner.address(),
+            *self.accounts.config.address(),
+            self.instruction_data.tick_lower,
+            self.instruction_data.tick_upper,
+            bump_binding,
+        );
+
+        // 两个边界所在的 tick 数组 第一个使用这个数组的头寸负责创建
+        for (tick_array, tick) in [
+            (
+                self.accounts.tick_array_lower,
+                self.instruction_data.tick_lower,
+            ),
+            (
+                self.accounts.tick_array_upper,
+                self.instruction_data.tick_upper,
+            ),
+        ] {
+            let start_tick = TickArray::start_tick_of(tick, tick_spacing);
+            let (address, bump) = tick_array_address(self.accounts.config.address(), start_tick);
+            if address.ne(tick_array.address()) {
+                return Err(AmmError::InvalidTickArray.into());
+            }
+            if !tick_array.is_data_empty() {
+                continue;
+            }
+            let start_binding = start_tick.to_le_bytes();
+            let bump_binding = [bump];
+            let tick_array_seeds = [
+                Seed::from(b"tick_array"),
+                Seed::from(self.accounts.config.address().as_ref()),
+                Seed::from(&start_binding),
+                Seed::from(&bump_binding),
+            ];
+            ProgramAccount::init::<TickArray>(
+                self.accounts.owner,
+                tick_array,
+                &tick_array_seeds,
+                TickArray::LEN,
+            )?;
+            TickArray::load_mut(tick_array)?.set_inner(
+                *self.accounts.config.address(),
+                start_tick,
+                bump_binding,
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+use crate::instructions::helper::{
+    AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck, AssociatedTokenAccountInit,
+    MintInterface, ProgramAccount, ProgramAccountInit, SignerAccount,
+};
+use crate::instructions::token_interface::{
+    check_mint, mint_unchecked, TokenProgram, TransferChecked, TransferFee,
+};
+use crate::{AmmError, Config, Order, ProgramState};
+use pinocchio::{
+    cpi::Seed,
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, Address, ProgramResult,
+};
+
+pub struct PlaceOrderAccounts<'a> {
+    // 下单的用户 支付托管的代币和账户租金。
+    pub owner: &'a AccountView,
+    // 正在创建的订单账户。
+    pub order: &'a AccountView,
+    // 托管输入代币的账户 即订单的关联代币账户 在这里创建。
+    pub escrow: &'a AccountView,
+    // 用户支付输入代币的关联账户。
+    pub owner_src_ata: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+    // 输入代币的铸币账户。is_x 为 true 时是代币 X 否则是代币 Y。
+    pub mint_in: &'a AccountView,
+    // 输入代币所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_in: &'a AccountView,
+    // 系统程序 创建账户时使用。
+    pub system_program: &'a AccountView,
+    // 关联代币账户程序 创建托管账户时使用。
+    pub associated_token_program: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for PlaceOrderAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [owner, order, escrow, owner_src_ata, config, mint_in, token_program_in, system_program, associated_token_program] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(owner)?;
+        ProgramAccount::check(config)?;
+        MintInterface::check(mint_in)?;
+        TokenProgram::check(token_program_in)?;
+        if !order.is_data_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        Ok(Self {
+            owner,
+            order,
+            escrow,
+            owner_src_ata,
+            config,
+            mint_in,
+            token_program_in,
+            system_program,
+            associated_token_program,
+        })
+    }
+}
+
+pub struct PlaceOrderInstructionData {
+    // 订单 PDA 的种子 同一个用户在同一个池子里可以有多个订单。
+    pub seed: u64,
+    // 支付代币 X 获取代币 Y 还是反过来。
+    pub is_x: bool,
+    // 每次执行支付的代币数量。
+    pub amount_in: u64,
+    // 每次执行至少收到的代币数量。限价单用它表示目标价格。
+    pub min_out: u64,
+    // 每次执行付给 keeper 的输入代币数量。
+    pub tip: u64,
+    // 执行次数 限价单为 1。
+    pub executions: u64,
+    // 两次执行之间的最短间隔 以秒为单位 只执行一次的订单可以为 0。
+    pub interval: i64,
+    // 最早可以执行的时间。
+    pub start: i64,
+    // 订单的过期时间 过期之后只能取消。
+    pub expiration: i64,
+}
+
+impl TryFrom<&[u8]> for PlaceOrderInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() * 5 + size_of::<i64>() * 3 + size_of::<bool>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let seed = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        let is_x = data[8] != 0;
+        let amount_in = u64::from_le_bytes(data[9..17].try_into().unwrap());
+        let min_out = u64::from_le_bytes(data[17..25].try_into().unwrap());
+        let tip = u64::from_le_bytes(data[25..33].try_into().unwrap());
+        let executions = u64::from_le_bytes(data[33..41].try_into().unwrap());
+        let interval = i64::from_le_bytes(data[41..49].try_into().unwrap());
+        let start = i64::from_le_bytes(data[49..57].try_into().unwrap());
+        let expiration = i64::from_le_bytes(data[57..65].try_into().unwrap());
+
+        // Instruction Checks
+        if amount_in == 0 || min_out == 0 || executions == 0 {
+            return Err(AmmError::ZeroAmount.into());
+        }
+        // 多次执行的订单必须有间隔 否则 keeper 可以在同一时刻把它全部执行完
+        if interval < 0 || (executions > 1 && interval == 0) || expiration < start {
+            return Err(AmmError::InvalidOrder.into());
+        }
+        Order::escrow_amount(amount_in, tip, executions)?;
+        Ok(Self {
+            seed,
+            is_x,
+            amount_in,
+            min_out,
+            tip,
+            executions,
+            interval,
+            start,
+            expiration,
+        })
+    }
+}
+
+// 下单 把所有执行需要的输入代币和 keeper 小费一次性托管到订单账户
+pub struct PlaceOrder<'a> {
+    pub accounts: PlaceOrderAccounts<'a>,
+    pub instruction_data: PlaceOrderInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for PlaceOrder<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = PlaceOrderAccounts::try_from(accounts)?;
+        let instruction_data = PlaceOrderInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> PlaceOrder<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+        let mint_in = match self.instruction_data.is_x {
+            true => config.mint_x(),
+            false => config.mint_y(),
+        };
+        check_mint(
+            self.accounts.mint_in,
+            mint_in,
+            self.accounts.token_program_in,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.owner_src_ata,
+            self.accounts.owner,
+            mint_in,
+            self.accounts.token_program_in,
+        )?;
+        if Clock::get()?.unix_timestamp > self.instruction_data.expiration {
+            return Err(AmmError::Expired.into());
+        }
+
+        let seed_binding = self.instruction_data.seed.to_le_bytes();
+        let (order, bump) = Address::find_program_address(
+            &[
+                b"order",
+                self.accounts.config.address().as_ref(),
+                self.accounts.owner.address().as_ref(),
+                &seed_binding,
+            ],
+            &crate::ID,
+        );
+        if order.ne(self.accounts.order.address()) {
+            return Err(AmmError::InvalidOrder.into());
+        }
+        let bump_binding = [bump];
+        let order_seeds = [
+            Seed::from(b"order"),
+            Seed::from(self.accounts.config.address().as_ref()),
+            Seed::from(self.accounts.owner.address().as_ref()),
+            Seed::from(&seed_binding),
+            Seed::from(&bump_binding),
+        ];
+        ProgramAccount::init::<Order>(
+            self.accounts.owner,
+            self.accounts.order,
+            &order_seeds,
+            Order::LEN,
+        )?;
+        let mut order = Order::load_mut(self.accounts.order)?;
+        order.set_inner(
+            *self.accounts.owner.address(),
+            *self.accounts.config.address(),
+            *mint_in,
+            self.instruction_data.seed,
+            self.instruction_data.is_x,
+            self.instruction_data.amount_in,
+            self.instruction_data.min_out,
+            self.instruction_data.tip,
+            self.instruction_data.executions,
+            self.instruction_data.interval,
+            self.instruction_data.start,
+            self.instruction_data.expiration,
+            bump_binding,
+        );
+        drop(order);
+
+        // 托管账户的所有者是订单 执行时由订单签名转出 已经被别人提前创建时跳过
+        AssociatedTokenAccount::init_if_needed(
+            self.accounts.escrow,
+            self.accounts.mint_in,
+            self.accounts.owner,
+            self.accounts.order,
+            self.accounts.system_program,
+            self.accounts.token_program_in,
+        )?;
+
+        // Token-2022 转账费 托管账户实际收到的数量要够所有执行使用
+        let escrow_amount = Order::escrow_amount(
+            self.instruction_data.amount_in,
+            self.instruction_data.tip,
+            self.instruction_data.executions,
+        )?;
+        let amount = TransferFee::from_mint(self.accounts.mint_in, Clock::get()?.epoch)?
+            .amount_before_fee(escrow_amount)?;
+        let mint_in_state = unsafe { mint_unchecked(self.accounts.mint_in)? };
+        TransferChecked {
+            from: self.accounts.owner_src_ata,
+            mint: self.accounts.mint_in,
+            to: self.accounts.escrow,
+            authority: self.accounts.owner,
+            amount,
+            decimals: mint_in_state.decimals(),
+            token_program: self.accounts.token_program_in,
+        }
+        .invoke()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,225 @@
+use crate::instructions::helper::{AccountCheck, MintInterface, ProgramAccount};
+use crate::instructions::token_interface::{
+    lp_mint_address, mint_unchecked, token_account_unchecked, TokenProgram,
+};
+use crate::{AmmError, Config, ConstantProduct, LiquidityPair, SwapMode};
+use pinocchio::cpi::set_return_data;
+use pinocchio::{
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+
+pub struct QuoteAccounts<'a> {
+    // 代表池流动性的铸币账户。只有 Deposit 和 Withdraw 报价需要用到。
+    pub mint_lp: &'a AccountView,
+    // 持有所有存入池中的代币 X 的代币账户。
+    pub vault_x: &'a AccountView,
+    // 持有所有存入池中的代币 Y 的代币账户。
+    pub vault_y: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+    // 代币 X 所属的代币程序账户。用于校验金库地址。
+    pub token_program_x: &'a AccountView,
+    // 代币 Y 所属的代币程序账户。用于校验金库地址。
+    pub token_program_y: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for QuoteAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [mint_lp, vault_x, vault_y, config, token_program_x, token_program_y] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        ProgramAccount::check(config)?;
+        MintInterface::check(mint_lp)?;
+        TokenProgram::check(token_program_x)?;
+        TokenProgram::check(token_program_y)?;
+        Ok(Self {
+            mint_lp,
+            vault_x,
+            vault_y,
+            config,
+            token_program_x,
+            token_program_y,
+        })
+    }
+}
+
+pub enum QuoteInstructionData {
+    // 对 Swap 报价 参数和 SwapInstructionData 一致 但不需要滑点和过期时间
+    Swap {
+        is_x: bool,
+        amount: u64,
+        mode: SwapMode,
+    },
+    // 对 Deposit 报价 amount 是希望得到的 LP 代币数量
+    Deposit {
+        amount: u64,
+    },
+    // 对 Withdraw 报价 amount 是希望销毁的 LP 代币数量
+    Withdraw {
+        amount: u64,
+    },
+}
+
+impl TryFrom<&[u8]> for QuoteInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let (kind, data) = data
+            .split_first()
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        let quote = match (*kind, data.len()) {
+            (0, 10) => {
+                let mode = match data[9] {
+                    0 => SwapMode::ExactIn,
+                    1 => SwapMode::ExactOut,
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                };
+                QuoteInstructionData::Swap {
+                    is_x: data[0] != 0,
+                    amount: u64::from_le_bytes(data[1..9].try_into().unwrap()),
+                    mode,
+                }
+            }
+            (1, 8) => QuoteInstructionData::Deposit {
+                amount: u64::from_le_bytes(data[0..8].try_into().unwrap()),
+            },
+            (2, 8) => QuoteInstructionData::Withdraw {
+                amount: u64::from_le_bytes(data[0..8].try_into().unwrap()),
+            },
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
+        let amount = match quote {
+            QuoteInstructionData::Swap { amount, .. }
+            | QuoteInstructionData::Deposit { amount }
+            | QuoteInstructionData::Withdraw { amount } => amount,
+        };
+        if amount == 0 {
+            return Err(AmmError::ZeroAmount.into());
+        }
+        Ok(quote)
+    }
+}
+
+// 只读的报价指令 使用和链上交易完全相同的计算 结果通过 set_return_data 返回
+// 不需要签名 也不转移任何代币 客户端可以直接模拟交易拿到结果
+// Swap:     [amount_in, amount_out, fee, price_impact_bps] 各 8 字节小端
+// Deposit:  [x, y, lp] 各 8 字节小端
+// Withdraw: [x, y, lp] 各 8 字节小端
+// 报价只包含曲线本身的计算 不包含 Token-2022 铸币的转账费
+pub struct Quote<'a> {
+    pub accounts: QuoteAccounts<'a>,
+    pub instruction_data: QuoteInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for Quote<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = QuoteAccounts::try_from(accounts)?;
+        let instruction_data = QuoteInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> Quote<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+        // 报价和真实交易一样遵守池子的状态
+        match self.instruction_data {
+            QuoteInstructionData::Withdraw { .. } => config.check_withdrawable()?,
+            _ => config.check_tradable()?,
+        }
+
+        config.check_vaults(
+            self.accounts.vault_x.address(),
+            self.accounts.vault_y.address(),
+        )?;
+
+        let vault_x = unsafe { token_account_unchecked(self.accounts.vault_x)? };
+        let vault_y = unsafe { token_account_unchecked(self.accounts.vault_y)? };
+        let (reserve_x, reserve_y) = config.reserves(vault_x.amount(), vault_y.amount())?;
+
+        match self.instruction_data {
+            QuoteInstructionData::Swap { is_x, amount, mode } => {
+                let curve = config.curve(reserve_x, reserve_y, Clock::get()?.unix_timestamp)?;
+                let p = match is_x {
+                    true => LiquidityPair::X,
+                    false => LiquidityPair::Y,
+                };
+                // 在副本上交换 价格影响按交换之前的储备计算
+                let mut swapped = curve;
+                let (amount_in, fee, amount_out) = match mode {
+                    SwapMode::ExactIn => swapped.swap(p, amount, 0)?,
+                    SwapMode::ExactOut => swapped.swap_exact_out(p, amount, u64::MAX)?,
+                };
+                let price_impact = curve.price_impact(p, amount_in - fee, amount_out)?;
+
+                let mut result = [0u8; 32];
+                result[0..8].copy_from_slice(&amount_in.to_le_bytes());
+                result[8..16].copy_from_slice(&amount_out.to_le_bytes());
+                result[16..24].copy_from_slice(&fee.to_le_bytes());
+                result[24..32].copy_from_slice(&price_impact.to_le_bytes());
+                set_return_data(&result);
+            }
+            QuoteInstructionData::Deposit { amount } => {
+                self.check_lp_mint()?;
+                let mint_lp = unsafe { mint_unchecked(self.accounts.mint_lp)? };
+                // 首次注入流动性时比例由用户自己决定 无法报价
+                if mint_lp.supply() == 0 {
+                    return Err(AmmError::InsufficientLiquidity.into());
+                }
+                let (x, y) = ConstantProduct::xy_deposit_amounts_from_l(
+                    reserve_x,
+                    reserve_y,
+                    mint_lp.supply(),
+                    amount,
+                )?;
+                set_return_data(&Self::lp_result(x, y, amount));
+            }
+            QuoteInstructionData::Withdraw { amount } => {
+                self.check_lp_mint()?;
+                let mint_lp = unsafe { mint_unchecked(self.accounts.mint_lp)? };
+                let (x, y) = match mint_lp.supply() == amount {
+                    true => (reserve_x, reserve_y),
+                    false => ConstantProduct::xy_withdraw_amounts_from_l(
+                        reserve_x,
+                        reserve_y,
+                        mint_lp.supply(),
+                        amount,
+                    )?,
+                };
+                set_return_data(&Self::lp_result(x, y, amount));
+            }
+        }
+        Ok(())
+    }
+
+    // LP 报价按供应量计算 铸币账户必须是这个池子的 否则报价可以被任意操纵
+    // Quote 不传 LP 的代币程序 MintInterface 已经校验它属于某个代币程序
+    fn check_lp_mint(&self) -> ProgramResult {
+        if self
+            .accounts
+            .mint_lp
+            .address()
+            .ne(&lp_mint_address(self.accounts.config))
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(())
+    }
+
+    fn lp_result(x: u64, y: u64, lp: u64) -> [u8; 24] {
+        let mut result = [0u8; 24];
+        result[0..8].copy_from_slice(&x.to_le_bytes());
+        result[8..16].copy_from_slice(&y.to_le_bytes());
+        result[16..24].copy_from_slice(&lp.to_le_bytes());
+        result
+    }
+}
@@ -0,0 +1,82 @@
+use crate::instructions::helper::{AccountCheck, ProgramAccount, SignerAccount};
+use crate::{AmmError, Config};
+use pinocchio::{
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+
+pub struct RampAmpAccounts<'a> {
+    // AMM 池的权限持有者。必须与 config 中记录的 authority 一致。
+    pub authority: &'a AccountView,
+    // AMM 池的配置账户。必须是 StableSwap 池。
+    pub config: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for RampAmpAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [authority, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        ProgramAccount::check(config)?;
+        Ok(Self { authority, config })
+    }
+}
+
+pub struct RampAmpInstructionData {
+    // 调整结束时的放大系数。
+    pub target_amp: u64,
+    // 调整结束的时间 从当前时间到结束时间之间线性变化。
+    pub stop: i64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for RampAmpInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() + size_of::<i64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let target_amp = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        let stop = i64::from_le_bytes(data[8..16].try_into().unwrap());
+        if target_amp == 0 {
+            return Err(AmmError::InvalidAmp.into());
+        }
+        Ok(Self { target_amp, stop })
+    }
+}
+
+// 逐步调整 StableSwap 池的放大系数 避免瞬间改变价格曲线被套利
+pub struct RampAmp<'a> {
+    pub accounts: RampAmpAccounts<'a>,
+    pub instruction_data: RampAmpInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for RampAmp<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = RampAmpAccounts::try_from(accounts)?;
+        let instruction_data = RampAmpInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> RampAmp<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.check_authority(self.accounts.authority.address())?;
+        config.ramp_amp(
+            self.instruction_data.target_amp,
+            Clock::get()?.unix_timestamp,
+            self.instruction_data.stop,
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use crate::instructions::helper::{AccountCheck, ProgramAccount};
+use crate::instructions::token_interface::{token_account_unchecked, TokenProgram};
+use crate::{AmmError, Config, Observation, Oracle, ProgramState};
+use pinocchio::{
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+
+pub struct RecordObservationAccounts<'a> {
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+    // 池子的价格预言机账户。
+    pub oracle: &'a AccountView,
+    // 持有所有存入池中的代币 X 的代币账户。
+    pub vault_x: &'a AccountView,
+    // 持有所有存入池中的代币 Y 的代币账户。
+    pub vault_y: &'a AccountView,
+    // 代币 X 所属的代币程序账户。用于校验金库地址。
+    pub token_program_x: &'a AccountView,
+    // 代币 Y 所属的代币程序账户。用于校验金库地址。
+    pub token_program_y: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for RecordObservationAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [config, oracle, vault_x, vault_y, token_program_x, token_program_y] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        ProgramAccount::check(config)?;
+        TokenProgram::check(token_program_x)?;
+        TokenProgram::check(token_program_y)?;
+        Ok(Self {
+            config,
+            oracle,
+            vault_x,
+            vault_y,
+            token_program_x,
+            token_program_y,
+        })
+    }
+}
+
+// 把当前的价格累加器写入预言机的环形缓冲区 任何人都可以调用
+// 写入之前会先用当前储备把累加器推进到当前时间 这样即使池子长时间没有交易 观测也是准确的
+pub struct RecordObservation<'a> {
+    pub accounts: RecordObservationAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for RecordObservation<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let accounts = RecordObservationAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> RecordObservation<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+        let mut oracle = Oracle::load_mut(self.accounts.oracle)?;
+        if oracle.config().ne(self.accounts.config.address()) {
+            return Err(AmmError::InvalidOracle.into());
+        }
+
+        config.check_vaults(
+            self.accounts.vault_x.address(),
+            self.accounts.vault_y.address(),
+        )?;
+
+        let vault_x = unsafe { token_account_unchecked(self.accounts.vault_x)? };
+        let vault_y = unsafe { token_account_unchecked(self.accounts.vault_y)? };
+        let (reserve_x, reserve_y) = config.reserves(vault_x.amount(), vault_y.amount())?;
+
+        let now = Clock::get()?.unix_timestamp;
+        config.update_price_cumulative(reserve_x, reserve_y, now)?;
+        if !oracle.write(Observation::new(
+            now,
+            config.price_x_cumulative(),
+            config.price_y_cumulative(),
+        )) {
+            return Err(AmmError::StaleObservation.into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use crate::instructions::helper::{AccountCheck, AccountClose, ProgramAccount, SignerAccount};
+use crate::{AllowListEntry, AmmError, Config, ProgramState};
+use pinocchio::{error::ProgramError, AccountView, ProgramResult};
+
+pub struct RemoveFromAllowListAccounts<'a> {
+    // AMM 池的权限持有者 接收白名单账户退回的租金。
+    pub authority: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+    // 要关闭的白名单账户。
+    pub entry: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for RemoveFromAllowListAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [authority, config, entry] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        ProgramAccount::check(config)?;
+        Ok(Self {
+            authority,
+            config,
+            entry,
+        })
+    }
+}
+
+// 把用户移出池子的白名单 已经持有的 LP 不受影响 需要的话再用 SetLpFrozen 冻结
+pub struct RemoveFromAllowList<'a> {
+    pub accounts: RemoveFromAllowListAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for RemoveFromAllowList<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let accounts = RemoveFromAllowListAccounts::try_from(accounts)?;
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> RemoveFromAllowList<'a> {
+    pub fn process(&self) -> ProgramResult {
+        Config::load(self.accounts.config)?.check_authority(self.accounts.authority.address())?;
+        {
+            let entry = AllowListEntry::load(self.accounts.entry)?;
+            if entry.config().ne(self.accounts.config.address()) {
+                return Err(AmmError::NotAllowListed.into());
+            }
+        }
+        ProgramAccount::close(self.accounts.entry, self.accounts.authority)
+    }
+}
@@ -0,0 +1,322 @@
+use crate::instructions::helper::{
+    AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck, MintInterface,
+    ProgramAccount, SignerAccount,
+};
+use crate::instructions::token_interface::{
+    check_mint, mint_unchecked, token_account_unchecked, TokenProgram, TransferChecked, TransferFee,
+};
+use crate::{AmmError, AmmEvent, Config, ConstantProduct, LiquidityPair, SwapEvent};
+use pinocchio::cpi::{Seed, Signer};
+use pinocchio::{
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+
+// 一条路由最多经过的池子数量 受限于交易的账户数量和计算单元
+pub const MAX_ROUTE_HOPS: usize = 4;
+// 每一跳需要的账户数量
+pub const ROUTE_HOP_ACCOUNTS: usize = 7;
+
+// 路由中的一跳 支付 mint_in 从池子换出 mint_out
+pub struct RouteHop<'a> {
+    // 这一跳所在 AMM 池的配置账户。
+    pub config: &'a AccountView,
+    // 这一跳接收支付代币的金库。
+    pub vault_in: &'a AccountView,
+    // 这一跳转出代币的金库。
+    pub vault_out: &'a AccountView,
+    // 支付代币的铸币账户。
+    pub mint_in: &'a AccountView,
+    // 换出代币的铸币账户。
+    pub mint_out: &'a AccountView,
+    // 支付代币所属的代币程序账户。
+    pub token_program_in: &'a AccountView,
+    // 换出代币所属的代币程序账户。
+    pub token_program_out: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for RouteHop<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [config, vault_in, vault_out, mint_in, mint_out, token_program_in, token_program_out] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        ProgramAccount::check(config)?;
+        MintInterface::check(mint_in)?;
+        MintInterface::check(mint_out)?;
+        TokenProgram::check(token_program_in)?;
+        TokenProgram::check(token_program_out)?;
+        Ok(Self {
+            config,
+            vault_in,
+            vault_out,
+            mint_in,
+            mint_out,
+            token_program_in,
+            token_program_out,
+        })
+    }
+}
+
+impl<'a> RouteHop<'a> {
+    // 校验这一跳的铸币和金库属于 config 记录的池子 返回交换方向
+    fn check(&self, config: &Config) -> Result<LiquidityPair, ProgramError> {
+        let is_x = self.mint_in.address().eq(config.mint_x());
+        let (p, mint_in, mint_out) = match is_x {
+            true => (LiquidityPair::X, config.mint_x(), config.mint_y()),
+            false => (LiquidityPair::Y, config.mint_y(), config.mint_x()),
+        };
+        check_mint(self.mint_in, mint_in, self.token_program_in)?;
+        check_mint(self.mint_out, mint_out, self.token_program_out)?;
+        config.check_vault(is_x, self.vault_in.address())?;
+        config.check_vault(!is_x, self.vault_out.address())?;
+        Ok(p)
+    }
+
+    // 由 config 签名 把 vault_out 中的代币转给下一跳的金库或用户
+    fn transfer_out(&self, to: &AccountView, amount: u64) -> ProgramResult {
+        let config = Config::load(self.config)?;
+        let mint_out = unsafe { mint_unchecked(self.mint_out)? };
+        // 构建config签名
+        let seed_bytes = config.seed().to_le_bytes();
+        let mut seed_array = [0u8; 8];
+        seed_array.copy_from_slice(&seed_bytes);
+        let bump = config.config_bump();
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(&seed_array),
+            Seed::from(config.mint_x().as_ref()),
+            Seed::from(config.mint_y().as_ref()),
+            Seed::from(&bump),
+        ];
+
+        let config_signer = [Signer::from(&config_seeds)];
+        TransferChecked {
+            from: self.vault_out,
+            mint: self.mint_out,
+            to,
+            authority: self.config,
+            amount,
+            decimals: mint_out.decimals(),
+            token_program: self.token_program_out,
+        }
+        .invoke_signed(&config_signer)
+    }
+}
+
+pub struct RouteSwapAccounts<'a> {
+    // 发起路由交换的用户。
+    pub user: &'a AccountView,
+    // 用户支付第一跳代币的关联账户。
+    pub user_src_ata: &'a AccountView,
+    // 用户接收最后一跳代币的关联账户。
+    pub user_dst_ata: &'a AccountView,
+    // 每一跳的账户 按 ROUTE_HOP_ACCOUNTS 个一组依次排列。
+    pub hops: &'a [AccountView],
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for RouteSwapAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [user, user_src_ata, user_dst_ata, hops @ ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(user)?;
+        if hops.is_empty()
+            || hops.len() % ROUTE_HOP_ACCOUNTS != 0
+            || hops.len() / ROUTE_HOP_ACCOUNTS > MAX_ROUTE_HOPS
+        {
+            return Err(AmmError::InvalidRoute.into());
+        }
+        Ok(Self {
+            user,
+            user_src_ata,
+            user_dst_ata,
+            hops,
+        })
+    }
+}
+
+pub struct RouteSwapInstructionData {
+    // 用户在第一跳支付的代币数量。
+    pub amount_in: u64,
+    // 用户在最后一跳至少要收到的代币数量。整条路由只检查这一次滑点。
+    pub min_out: u64,
+    // 此订单的过期时间。确保交易必须在一定时间内完成非常重要。
+    pub expiration: i64,
+}
+
+impl TryFrom<&[u8]> for RouteSwapInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() * 3 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let amount_in = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        let min_out = u64::from_le_bytes(data[8..16].try_into().unwrap());
+        let expiration = i64::from_le_bytes(data[16..24].try_into().unwrap());
+
+        // Instruction Checks
+        if amount_in == 0 {
+            return Err(AmmError::ZeroAmount.into());
+        }
+        if min_out == 0 {
+            return Err(AmmError::ZeroAmount.into());
+        }
+        if Clock::get()?.unix_timestamp > expiration {
+            // 超时
+            return Err(AmmError::Expired.into());
+        }
+        Ok(Self {
+            amount_in,
+            min_out,
+            expiration,
+        })
+    }
+}
+
+// 一条指令内依次经过多个池子交换 例如 X -> Y -> Z
+// 上一跳换出的代币直接从上一个池子的金库转入下一个池子的金库 不经过用户的账户
+pub struct RouteSwap<'a> {
+    pub accounts: RouteSwapAccounts<'a>,
+    pub instruction_data: RouteSwapInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for RouteSwap<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = RouteSwapAccounts::try_from(accounts)?;
+        let instruction_data = RouteSwapInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> RouteSwap<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let epoch = Clock::get()?.epoch;
+        let now = Clock::get()?.unix_timestamp;
+        // 上一跳 以及上一跳从金库转出的数量
+        let mut prev: Option<(RouteHop, u64)> = None;
+
+        for accounts in self.accounts.hops.chunks_exact(ROUTE_HOP_ACCOUNTS) {
+            let hop = RouteHop::try_from(accounts)?;
+            let config = Config::load(hop.config)?;
+            config.check_tradable()?;
+            let p = hop.check(&config)?;
+
+            // 转入这一跳金库的数量 第一跳由用户支付 之后由上一跳的金库支付
+            let amount_in = match &prev {
+                None => {
+                    AssociatedTokenAccount::check(
+                        self.accounts.user_src_ata,
+                        self.accounts.user,
+                        hop.mint_in.address(),
+                        hop.token_program_in,
+                    )?;
+                    self.instruction_data.amount_in
+                }
+                Some((prev_hop, amount_out)) => {
+                    // 同一个池子连续两跳没有意义 而且上一跳的输出还在金库里会让储备算错
+                    if prev_hop.config.address().eq(hop.config.address())
+                        || prev_hop.mint_out.address().ne(hop.mint_in.address())
+                    {
+                        return Err(AmmError::InvalidRoute.into());
+                    }
+                    *amount_out
+                }
+            };
+
+            // 在代币转入之前读取储备
+            let vault_in = unsafe { token_account_unchecked(hop.vault_in)? };
+            let vault_out = unsafe { token_account_unchecked(hop.vault_out)? };
+            let (reserve_x, reserve_y) = match p {
+                LiquidityPair::X => config.reserves(vault_in.amount(), vault_out.amount())?,
+                LiquidityPair::Y => config.reserves(vault_out.amount(), vault_in.amount())?,
+            };
+            // Token-2022 转账费 金库实际收到的数量才参与曲线计算
+            let received =
+                TransferFee::from_mint(hop.mint_in, epoch)?.amount_after_fee(amount_in)?;
+            let mut curve = config.curve(reserve_x, reserve_y, now)?;
+            // 0- deposit 1-fee 2-withdraw 中间每一跳不检查滑点 只在最后检查一次
+            let swap_result = curve.swap(p, received, 0)?;
+            if swap_result.2 == 0 {
+                return Err(AmmError::ZeroAmount.into());
+            }
+            let (_, protocol_fee) =
+                ConstantProduct::split_fee(swap_result.1, config.protocol_fee())?;
+            drop(config);
+
+            // 把支付的代币转入这一跳的金库
+            match &prev {
+                None => {
+                    let mint_in = unsafe { mint_unchecked(hop.mint_in)? };
+                    TransferChecked {
+                        from: self.accounts.user_src_ata,
+                        mint: hop.mint_in,
+                        to: hop.vault_in,
+                        authority: self.accounts.user,
+                        amount: amount_in,
+                        decimals: mint_in.decimals(),
+                        token_program: hop.token_program_in,
+                    }
+                    .invoke()?;
+                }
+                Some((prev_hop, amount_out)) => prev_hop.transfer_out(hop.vault_in, *amount_out)?,
+            }
+
+            // 记录协议费和价格累加器
+            let mut config = Config::load_mut(hop.config)?;
+            config.update_price_cumulative(reserve_x, reserve_y, now)?;
+            match p {
+                LiquidityPair::X => config.add_protocol_fees_x(protocol_fee)?,
+                LiquidityPair::Y => config.add_protocol_fees_y(protocol_fee)?,
+            }
+            let (reserve_x, reserve_y) = match p {
+                LiquidityPair::X => (curve.x() - protocol_fee, curve.y()),
+                LiquidityPair::Y => (curve.x(), curve.y() - protocol_fee),
+            };
+            config.update_volatility(reserve_x, reserve_y, now)?;
+            drop(config);
+
+            // 每一跳都是一次独立的交换 金额是转入和转出这一跳金库的数量
+            AmmEvent::Swap(SwapEvent {
+                pool: *hop.config.address(),
+                user: *self.accounts.user.address(),
+                is_x: p == LiquidityPair::X,
+                amount_in,
+                amount_out: swap_result.2,
+                fee: swap_result.1,
+                reserve_x,
+                reserve_y,
+            })
+            .emit();
+
+            prev = Some((hop, swap_result.2));
+        }
+
+        // 最后一跳换出的代币转给用户 整条路由只检查一次滑点
+        let (last_hop, amount_out) = prev.ok_or(AmmError::InvalidRoute)?;
+        AssociatedTokenAccount::check(
+            self.accounts.user_dst_ata,
+            self.accounts.user,
+            last_hop.mint_out.address(),
+            last_hop.token_program_out,
+        )?;
+        let received =
+            TransferFee::from_mint(last_hop.mint_out, epoch)?.amount_after_fee(amount_out)?;
+        if received < self.instruction_data.min_out {
+            return Err(AmmError::SlippageExceeded.into());
+        }
+        last_hop.transfer_out(self.accounts.user_dst_ata, amount_out)
+    }
+}
@@ -0,0 +1,86 @@
+use crate::instructions::helper::{AccountCheck, ProgramAccount, SignerAccount};
+use crate::{AmmError, Config, FeeMode};
+use pinocchio::{error::ProgramError, AccountView, ProgramResult};
+
+pub struct SetDynamicFeeAccounts<'a> {
+    // AMM 池的权限持有者。必须与 config 中记录的 authority 一致。
+    pub authority: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for SetDynamicFeeAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [authority, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        ProgramAccount::check(config)?;
+        Ok(Self { authority, config })
+    }
+}
+
+pub struct SetDynamicFeeInstructionData {
+    // 费率模式 0 表示固定费率 1 表示动态费率。
+    pub fee_mode: u8,
+    // 动态费率的下限 以基点表示。没有波动时收取这个费率。
+    pub min_fee: u16,
+    // 动态费率的上限 以基点表示。
+    pub max_fee: u16,
+}
+
+impl<'a> TryFrom<&'a [u8]> for SetDynamicFeeInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u8>() + size_of::<u16>() * 2 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let fee_mode = FeeMode::try_from(data[0])? as u8;
+        let min_fee = u16::from_le_bytes(data[1..3].try_into().unwrap());
+        let max_fee = u16::from_le_bytes(data[3..5].try_into().unwrap());
+        // 费率不能达到 100% 下限不能超过上限
+        if min_fee > max_fee || max_fee >= 10_000 {
+            return Err(AmmError::InvalidFee.into());
+        }
+        Ok(Self {
+            fee_mode,
+            min_fee,
+            max_fee,
+        })
+    }
+}
+
+// 开启或关闭动态费率 以及设置动态费率的上下限
+pub struct SetDynamicFee<'a> {
+    pub accounts: SetDynamicFeeAccounts<'a>,
+    pub instruction_data: SetDynamicFeeInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for SetDynamicFee<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = SetDynamicFeeAccounts::try_from(accounts)?;
+        let instruction_data = SetDynamicFeeInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> SetDynamicFee<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.check_authority(self.accounts.authority.address())?;
+        config.set_dynamic_fee(
+            self.instruction_data.fee_mode,
+            self.instruction_data.min_fee,
+            self.instruction_data.max_fee,
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use crate::instructions::helper::{AccountCheck, ProgramAccount, SignerAccount};
+use crate::{AmmError, Config, Farm, ProgramState};
+use pinocchio::{
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+
+pub struct SetEmissionRateAccounts<'a> {
+    // AMM 池的权限持有者。必须与 config 中记录的 authority 一致。
+    pub authority: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+    // 池子的农场账户。
+    pub farm: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for SetEmissionRateAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [authority, config, farm] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        ProgramAccount::check(config)?;
+        Ok(Self {
+            authority,
+            config,
+            farm,
+        })
+    }
+}
+
+pub struct SetEmissionRateInstructionData {
+    // 新的每秒释放数量 为 0 时暂停释放。
+    pub emission_rate: u64,
+}
+
+impl TryFrom<&[u8]> for SetEmissionRateInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let emission_rate = u64::from_le_bytes(data.try_into().unwrap());
+        Ok(Self { emission_rate })
+    }
+}
+
+// 调整农场的奖励释放速度 之前已经释放的奖励按旧的速度结算
+pub struct SetEmissionRate<'a> {
+    pub accounts: SetEmissionRateAccounts<'a>,
+    pub instruction_data: SetEmissionRateInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for SetEmissionRate<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = SetEmissionRateAccounts::try_from(accounts)?;
+        let instruction_data = SetEmissionRateInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> SetEmissionRate<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+        config.check_authority(self.accounts.authority.address())?;
+        let mut farm = Farm::load_mut(self.accounts.farm)?;
+        if farm.config().ne(self.accounts.config.address()) {
+            return Err(AmmError::InvalidFarm.into());
+        }
+        farm.set_emission_rate(
+            self.instruction_data.emission_rate,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+}
@@ -0,0 +1,98 @@
+use crate::instructions::helper::{AccountCheck, SignerAccount};
+use crate::{Factory, ProgramState};
+use pinocchio::{error::ProgramError, AccountView, Address, ProgramResult};
+
+pub struct SetFactoryAccounts<'a> {
+    // 工厂的管理员。必须与工厂中记录的 admin 一致。
+    pub admin: &'a AccountView,
+    // 工厂账户。
+    pub factory: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for SetFactoryAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [admin, factory] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(admin)?;
+        Ok(Self { admin, factory })
+    }
+}
+
+pub struct SetFactoryInstructionData {
+    // 新池子默认的交换费 以基点表示。
+    pub default_fee: u16,
+    // 新池子默认的协议费比例 以基点表示。
+    pub default_protocol_fee: u16,
+    // 是否暂停创建新池子。
+    pub paused: bool,
+    // 新的管理员 省略时保持不变。
+    pub admin: Option<Address>,
+}
+
+impl<'a> TryFrom<&'a [u8]> for SetFactoryInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        const SET_FACTORY_DATA_LEN: usize = size_of::<u16>() * 2 + size_of::<bool>();
+        const SET_FACTORY_DATA_LEN_WITH_ADMIN: usize = SET_FACTORY_DATA_LEN + size_of::<Address>();
+
+        let admin = match data.len() {
+            SET_FACTORY_DATA_LEN => None,
+            SET_FACTORY_DATA_LEN_WITH_ADMIN => Some(Address::new_from_array(
+                data[SET_FACTORY_DATA_LEN..].try_into().unwrap(),
+            )),
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
+        let default_fee = u16::from_le_bytes(data[0..2].try_into().unwrap());
+        let default_protocol_fee = u16::from_le_bytes(data[2..4].try_into().unwrap());
+        let paused = match data[4] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
+        Ok(Self {
+            default_fee,
+            default_protocol_fee,
+            paused,
+            admin,
+        })
+    }
+}
+
+// 修改工厂的默认费率和暂停开关 也可以把管理权交给新的地址
+pub struct SetFactory<'a> {
+    pub accounts: SetFactoryAccounts<'a>,
+    pub instruction_data: SetFactoryInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for SetFactory<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = SetFactoryAccounts::try_from(accounts)?;
+        let instruction_data = SetFactoryInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> SetFactory<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let mut factory = Factory::load_mut(self.accounts.factory)?;
+        factory.check_admin(self.accounts.admin.address())?;
+        factory.set_defaults(
+            self.instruction_data.default_fee,
+            self.instruction_data.default_protocol_fee,
+        )?;
+        factory.set_paused(self.instruction_data.paused);
+        if let Some(admin) = self.instruction_data.admin {
+            factory.set_admin(admin);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+use crate::instructions::helper::{
+    AccountCheck, MintInterface, ProgramAccount, SignerAccount, TokenAccount,
+};
+use crate::instructions::token_interface::{check_mint, FreezeAccount, ThawAccount, TokenProgram};
+use crate::Config;
+use pinocchio::cpi::{Seed, Signer};
+use pinocchio::{error::ProgramError, AccountView, Address, ProgramResult};
+
+pub struct SetLpFrozenAccounts<'a> {
+    // AMM 池的权限持有者。必须与 config 中记录的 authority 一致。
+    pub authority: &'a AccountView,
+    // AMM 池的配置账户 同时是 LP 铸币账户的 freeze_authority。
+    pub config: &'a AccountView,
+    // 池子的 LP 铸币账户。
+    pub mint_lp: &'a AccountView,
+    // 要冻结或解冻的 LP 代币账户。
+    pub lp_account: &'a AccountView,
+    // LP 铸币账户所属的代币程序账户。
+    pub token_program: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for SetLpFrozenAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [authority, config, mint_lp, lp_account, token_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        ProgramAccount::check(config)?;
+        MintInterface::check(mint_lp)?;
+        TokenAccount::check(lp_account)?;
+        TokenProgram::check(token_program)?;
+        Ok(Self {
+            authority,
+            config,
+            mint_lp,
+            lp_account,
+            token_program,
+        })
+    }
+}
+
+pub struct SetLpFrozenInstructionData {
+    // true 冻结 false 解冻。
+    pub frozen: bool,
+}
+
+impl TryFrom<&[u8]> for SetLpFrozenInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let frozen = match data {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
+        Ok(Self { frozen })
+    }
+}
+
+// 冻结或解冻某个持有者的 LP 代币账户 例如白名单池子里私钥泄露的钱包
+// 冻结的账户不能转账 也不能销毁 LP 取回流动性
+pub struct SetLpFrozen<'a> {
+    pub accounts: SetLpFrozenAccounts<'a>,
+    pub instruction_data: SetLpFrozenInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for SetLpFrozen<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = SetLpFrozenAccounts::try_from(accounts)?;
+        let instruction_data = SetLpFrozenInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> SetLpFrozen<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+        config.check_authority(self.accounts.authority.address())?;
+        let (mint_lp, _) = Address::find_program_address(
+            &[b"mint_lp", self.accounts.config.address().as_ref()],
+            &crate::ID,
+        );
+        check_mint(self.accounts.mint_lp, &mint_lp, self.accounts.token_program)?;
+
+        // Initialize 把 config 设置为 LP 铸币账户的 freeze_authority
+        let seed_binding = config.seed().to_le_bytes();
+        let bump = config.config_bump();
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(&seed_binding),
+            Seed::from(config.mint_x().as_ref()),
+            Seed::from(config.mint_y().as_ref()),
+            Seed::from(&bump),
+        ];
+        let config_signer = [Signer::from(&config_seeds)];
+        match self.instruction_data.frozen {
+            true => FreezeAccount {
+                account: self.accounts.lp_account,
+                mint: self.accounts.mint_lp,
+                freeze_authority: self.accounts.config,
+                token_program: self.accounts.token_program,
+            }
+            .invoke_signed(&config_signer),
+            false => ThawAccount {
+                account: self.accounts.lp_account,
+                mint: self.accounts.mint_lp,
+                freeze_authority: self.accounts.config,
+                token_program: self.accounts.token_program,
+            }
+            .invoke_signed(&config_signer),
+        }
+    }
+}
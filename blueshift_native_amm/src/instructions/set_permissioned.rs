@@ -0,0 +1,69 @@
+use crate::instructions::helper::{AccountCheck, ProgramAccount, SignerAccount};
+use crate::Config;
+use pinocchio::{error::ProgramError, AccountView, ProgramResult};
+
+pub struct SetPermissionedAccounts<'a> {
+    // AMM 池的权限持有者。必须与 config 中记录的 authority 一致。
+    pub authority: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for SetPermissionedAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [authority, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        ProgramAccount::check(config)?;
+        Ok(Self { authority, config })
+    }
+}
+
+pub struct SetPermissionedInstructionData {
+    // true 只有白名单里的用户可以注入流动性 false 任何人都可以。
+    pub permissioned: bool,
+}
+
+impl TryFrom<&[u8]> for SetPermissionedInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let permissioned = match data {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
+        Ok(Self { permissioned })
+    }
+}
+
+// 开启或关闭白名单模式 只限制注入流动性 已经持有 LP 的用户仍然可以取回流动性和交换
+pub struct SetPermissioned<'a> {
+    pub accounts: SetPermissionedAccounts<'a>,
+    pub instruction_data: SetPermissionedInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for SetPermissioned<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = SetPermissionedAccounts::try_from(accounts)?;
+        let instruction_data = SetPermissionedInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> SetPermissioned<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.check_authority(self.accounts.authority.address())?;
+        config.set_permissioned(self.instruction_data.permissioned);
+        Ok(())
+    }
+}
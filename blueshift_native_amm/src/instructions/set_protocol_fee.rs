@@ -0,0 +1,70 @@
+use crate::instructions::helper::{AccountCheck, ProgramAccount, SignerAccount};
+use crate::{AmmError, Config};
+use pinocchio::{error::ProgramError, AccountView, ProgramResult};
+
+pub struct SetProtocolFeeAccounts<'a> {
+    // AMM 池的权限持有者。必须与 config 中记录的 authority 一致。
+    pub authority: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for SetProtocolFeeAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [authority, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        ProgramAccount::check(config)?;
+        Ok(Self { authority, config })
+    }
+}
+
+pub struct SetProtocolFeeInstructionData {
+    // 协议从交换费中抽取的比例 以基点表示（10_000 表示全部交换费归协议）。
+    pub protocol_fee: u16,
+}
+
+impl<'a> TryFrom<&'a [u8]> for SetProtocolFeeInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u16>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let protocol_fee = u16::from_le_bytes(data[0..2].try_into().unwrap());
+        if protocol_fee > 10_000 {
+            return Err(AmmError::InvalidFee.into());
+        }
+        Ok(Self { protocol_fee })
+    }
+}
+
+pub struct SetProtocolFee<'a> {
+    pub accounts: SetProtocolFeeAccounts<'a>,
+    pub instruction_data: SetProtocolFeeInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for SetProtocolFee<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = SetProtocolFeeAccounts::try_from(accounts)?;
+        let instruction_data = SetProtocolFeeInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> SetProtocolFee<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.check_authority(self.accounts.authority.address())?;
+        config.set_protocol_fee(self.instruction_data.protocol_fee)?;
+        Ok(())
+    }
+}
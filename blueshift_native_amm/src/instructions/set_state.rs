@@ -0,0 +1,70 @@
+use crate::instructions::helper::{AccountCheck, ProgramAccount, SignerAccount};
+use crate::{AmmError, AmmState, Config};
+use pinocchio::{error::ProgramError, AccountView, ProgramResult};
+
+pub struct SetStateAccounts<'a> {
+    // AMM 池的权限持有者。必须与 config 中记录的 authority 一致。
+    pub authority: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for SetStateAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [authority, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        ProgramAccount::check(config)?;
+        Ok(Self { authority, config })
+    }
+}
+
+pub struct SetStateInstructionData {
+    // 池子的新状态 对应 AmmState 的取值。
+    pub state: u8,
+}
+
+impl<'a> TryFrom<&'a [u8]> for SetStateInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        let [state] = data else {
+            return Err(ProgramError::InvalidInstructionData);
+        };
+        // 已经初始化的池子不能再退回未初始化状态
+        if *state == AmmState::Uninitialized as u8 {
+            return Err(AmmError::InvalidPoolState.into());
+        }
+        Ok(Self { state: *state })
+    }
+}
+
+pub struct SetState<'a> {
+    pub accounts: SetStateAccounts<'a>,
+    pub instruction_data: SetStateInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for SetState<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = SetStateAccounts::try_from(accounts)?;
+        let instruction_data = SetStateInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> SetState<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.check_authority(self.accounts.authority.address())?;
+        config.set_state(self.instruction_data.state)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+use crate::instructions::helper::{
+    AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck, MintInterface,
+    ProgramAccount, ProgramAccountInit, SignerAccount,
+};
+use crate::instructions::token_interface::{
+    check_mint, mint_unchecked, TokenProgram, TransferChecked,
+};
+use crate::{AmmError, Farm, ProgramState, StakePosition};
+use pinocchio::{
+    cpi::Seed,
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, Address, ProgramResult,
+};
+
+pub struct StakeAccounts<'a> {
+    // 质押 LP 的用户 第一次质押时支付质押账户的租金。
+    pub owner: &'a AccountView,
+    // 用户的 LP 关联账户。
+    pub owner_lp_ata: &'a AccountView,
+    // 用户在农场中的质押账户 不存在时在这里创建。
+    pub stake: &'a AccountView,
+    // 池子的农场账户。
+    pub farm: &'a AccountView,
+    // 池子的 LP 铸币账户。
+    pub mint_lp: &'a AccountView,
+    // 农场持有质押 LP 的关联账户。
+    pub lp_vault: &'a AccountView,
+    // LP 铸币账户所属的代币程序账户。
+    pub lp_token_program: &'a AccountView,
+    // 系统程序账户。
+    pub system_program: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for StakeAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [owner, owner_lp_ata, stake, farm, mint_lp, lp_vault, lp_token_program, system_program] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(owner)?;
+        MintInterface::check(mint_lp)?;
+        TokenProgram::check(lp_token_program)?;
+        Ok(Self {
+            owner,
+            owner_lp_ata,
+            stake,
+            farm,
+            mint_lp,
+            lp_vault,
+            lp_token_program,
+            system_program,
+        })
+    }
+}
+
+pub struct StakeInstructionData {
+    // 要质押的 LP 数量。
+    pub amount: u64,
+}
+
+impl TryFrom<&[u8]> for StakeInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let amount = u64::from_le_bytes(data.try_into().unwrap());
+        if amount == 0 {
+            return Err(AmmError::ZeroAmount.into());
+        }
+        Ok(Self { amount })
+    }
+}
+
+// 把 LP 质押到农场 质押之前已经产生的奖励先结算到 rewards_owed
+pub struct Stake<'a> {
+    pub accounts: StakeAccounts<'a>,
+    pub instruction_data: StakeInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for Stake<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = StakeAccounts::try_from(accounts)?;
+        let instruction_data = StakeInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> Stake<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let mut farm = Farm::load_mut(self.accounts.farm)?;
+        check_mint(
+            self.accounts.mint_lp,
+            farm.lp_mint(),
+            self.accounts.lp_token_program,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.owner_lp_ata,
+            self.accounts.owner,
+            farm.lp_mint(),
+            self.accounts.lp_token_program,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.lp_vault,
+            self.accounts.farm,
+            farm.lp_mint(),
+            self.accounts.lp_token_program,
+        )?;
+        farm.update(Clock::get()?.unix_timestamp)?;
+
+        // 第一次质押时创建质押账户 从当前的 reward_per_share 开始计算奖励
+        if self.accounts.stake.is_data_empty() {
+            let (stake, bump) = Address::find_program_address(
+                &[
+                    b"stake",
+                    self.accounts.farm.address().as_ref(),
+                    self.accounts.owner.address().as_ref(),
+                ],
+                &crate::ID,
+            );
+            if stake.ne(self.accounts.stake.address()) {
+                return Err(AmmError::InvalidFarm.into());
+            }
+            let bump_binding = [bump];
+            let stake_seeds = [
+                Seed::from(b"stake"),
+                Seed::from(self.accounts.farm.address().as_ref()),
+                Seed::from(self.accounts.owner.address().as_ref()),
+                Seed::from(&bump_binding),
+            ];
+            ProgramAccount::init::<StakePosition>(
+                self.accounts.owner,
+                self.accounts.stake,
+                &stake_seeds,
+                StakePosition::LEN,
+            )?;
+            StakePosition::load_mut(self.accounts.stake)?.set_inner(
+                *self.accounts.owner.address(),
+                *self.accounts.farm.address(),
+                farm.reward_per_share(),
+                bump_binding,
+            );
+        }
+
+        let mut stake = StakePosition::load_mut(self.accounts.stake)?;
+        if stake.owner().ne(self.accounts.owner.address())
+            || stake.farm().ne(self.accounts.farm.address())
+        {
+            return Err(AmmError::InvalidFarm.into());
+        }
+        stake.settle(farm.reward_per_share())?;
+        stake.deposit(self.instruction_data.amount)?;
+        farm.stake(self.instruction_data.amount)?;
+        drop(stake);
+        drop(farm);
+
+        // LP 铸币账户由程序创建 没有转账费
+        let mint_lp = unsafe { mint_unchecked(self.accounts.mint_lp)? };
+        TransferChecked {
+            from: self.accounts.owner_lp_ata,
+            mint: self.accounts.mint_lp,
+            to: self.accounts.lp_vault,
+            authority: self.accounts.owner,
+            amount: self.instruction_data.amount,
+            decimals: mint_lp.decimals(),
+            token_program: self.accounts.lp_token_program,
+        }
+        .invoke()
+    }
+}
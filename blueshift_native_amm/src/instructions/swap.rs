@@ -1,15 +1,23 @@
 use crate::instructions::helper::{
-    AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck, SignerAccount,
+    AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck, MintInterface,
+    SignerAccount,
 };
-use crate::{AmmState, Config};
+use crate::instructions::token_interface::{
+    check_mint, mint_unchecked, token_account_unchecked, TokenProgram, TransferChecked,
+    TransferFee,
+};
+use crate::{
+    first_tick_array_start, tick_array_address, tick_array_start, AmmError, AmmEvent, ClPool,
+    Config, ConstantProduct, CurveType, LiquidityPair, ProgramState, SwapEvent, TickArray,
+    MAX_TICK_ARRAYS,
+};
+use pinocchio::account::RefMut;
 use pinocchio::{
     error::ProgramError,
     sysvars::{clock::Clock, Sysvar},
-    AccountView, Address, ProgramResult,
+    AccountView, ProgramResult,
 };
 use pinocchio::cpi::{Seed, Signer};
-use pinocchio_token::{instructions::Transfer, state::TokenAccount};
-use solana_program_log::log;
 
 pub struct SwapAccounts<'a> {
     // 将代币交换到 AMM 流动性中的用户。
@@ -24,19 +32,32 @@ pub struct SwapAccounts<'a> {
     pub vault_y: &'a AccountView,
     // AMM 池的配置账户。存储所有相关的池参数和状态。
     pub config: &'a AccountView,
-    // SPL 代币程序账户。执行代币操作（如转账和铸造）所需。
-    pub token_program: &'a AccountView,
+    // 池中代币 X 的铸币账户。Token-2022 的 TransferChecked 需要用到。
+    pub mint_x: &'a AccountView,
+    // 池中代币 Y 的铸币账户。
+    pub mint_y: &'a AccountView,
+    // 代币 X 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_x: &'a AccountView,
+    // 代币 Y 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_y: &'a AccountView,
+    // 集中流动性池额外传入 ClPool 账户和沿交换方向排列的 tick 数组 其他池子不需要。
+    pub remaining: &'a [AccountView],
 }
 
 impl<'a> TryFrom<&'a [AccountView]> for SwapAccounts<'a> {
     type Error = ProgramError;
 
     fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
-        let [user, user_x_ata, user_y_ata, vault_x, vault_y, config, token_program] = accounts
+        let [user, user_x_ata, user_y_ata, vault_x, vault_y, config, mint_x, mint_y, token_program_x, token_program_y, remaining @ ..] =
+            accounts
         else {
             return Err(ProgramError::NotEnoughAccountKeys);
         };
         SignerAccount::check(user)?;
+        MintInterface::check(mint_x)?;
+        MintInterface::check(mint_y)?;
+        TokenProgram::check(token_program_x)?;
+        TokenProgram::check(token_program_y)?;
         Ok(Self {
             user,
             user_x_ata,
@@ -44,52 +65,74 @@ impl<'a> TryFrom<&'a [AccountView]> for SwapAccounts<'a> {
             vault_x,
             vault_y,
             config,
-            token_program,
+            mint_x,
+            mint_y,
+            token_program_x,
+            token_program_y,
+            remaining,
         })
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SwapMode {
+    // 固定支付数量 amount 是支付的数量 limit 是最少收到的数量
+    ExactIn = 0u8,
+    // 固定收到数量 amount 是希望收到的数量 limit 是最多支付的数量
+    ExactOut = 1u8,
+}
+
 pub struct SwapInstructionData {
     // 此交换是从代币 X 到代币 Y 或反之进行的；需要正确对齐账户。
     pub is_x: bool,
-    // 用户愿意用来交换另一种代币的代币数量。
+    // ExactIn: 用户愿意用来交换另一种代币的代币数量。ExactOut: 用户希望收到的代币数量。
     pub amount: u64,
-    // 用户愿意在交换 amount 时接收的最小代币数量。
-    pub min: u64,
+    // ExactIn: 用户愿意在交换 amount 时接收的最小代币数量。ExactOut: 用户愿意支付的最大代币数量。
+    pub limit: u64,
     // 此订单的过期时间。确保交易必须在一定时间内完成非常重要。
     pub expiration: i64,
+    // 交换模式 省略时默认为 ExactIn 兼容旧的指令数据。
+    pub mode: SwapMode,
 }
 
 impl TryFrom<&[u8]> for SwapInstructionData {
     type Error = ProgramError;
 
     fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
-        log!("初始化数据");
-        if data.len() != size_of::<u64>() * 3 + size_of::<bool>() {
-            return Err(ProgramError::InvalidArgument);
-        }
-        let is_x = data.get(0) != Some(&0u8);
+        const SWAP_DATA_LEN: usize = size_of::<u64>() * 3 + size_of::<bool>();
+        let mode = match data.len() {
+            SWAP_DATA_LEN => SwapMode::ExactIn,
+            len if len == SWAP_DATA_LEN + size_of::<u8>() => match data[SWAP_DATA_LEN] {
+                0 => SwapMode::ExactIn,
+                1 => SwapMode::ExactOut,
+                _ => return Err(ProgramError::InvalidInstructionData),
+            },
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
+        let is_x = data[0] != 0;
         let amount = u64::from_le_bytes(data[1..9].try_into().unwrap());
-        let min = u64::from_le_bytes(data[9..17].try_into().unwrap());
+        let limit = u64::from_le_bytes(data[9..17].try_into().unwrap());
         let expiration = i64::from_le_bytes(data[17..25].try_into().unwrap());
 
         // Instruction Checks
-        if amount <= 0 {
-            return Err(ProgramError::InvalidInstructionData);
+        if amount == 0 {
+            return Err(AmmError::ZeroAmount.into());
         }
-        if min <= 0 {
-            return Err(ProgramError::InvalidInstructionData);
+        if limit == 0 {
+            return Err(AmmError::ZeroAmount.into());
         }
         if Clock::get()?.unix_timestamp > expiration {
             // 超时
-            return Err(ProgramError::InvalidInstructionData);
+            return Err(AmmError::Expired.into());
         }
 
         Ok(Self {
             is_x,
             amount,
-            min,
+            limit,
             expiration,
+            mode,
         })
     }
 }
@@ -117,74 +160,86 @@ impl<'a> Swap<'a> {
         // 根据题目要求 所有的ata都已经在指令外初始化了
         // 否则的话 对于用户接收代币的ata需要使用init_if_needed的处理防止账户不存在
         // 校验用户的ata账户地址有没有问题
-        log!("开始校验");
         let config = Config::load(self.accounts.config)?;
-        if config.state() != (AmmState::Initialized as u8) {
-            return Err(ProgramError::InvalidArgument);
-        }
+        config.check_tradable()?;
+        check_mint(self.accounts.mint_x, config.mint_x(), self.accounts.token_program_x)?;
+        check_mint(self.accounts.mint_y, config.mint_y(), self.accounts.token_program_y)?;
         AssociatedTokenAccount::check(
             self.accounts.user_x_ata,
             self.accounts.user,
             config.mint_x(),
-            self.accounts.token_program,
+            self.accounts.token_program_x,
         )?;
         AssociatedTokenAccount::check(
             self.accounts.user_y_ata,
             self.accounts.user,
             config.mint_y(),
-            self.accounts.token_program,
+            self.accounts.token_program_y,
         )?;
 
-        // 校验 金库的地址有没有为题
-        // 其实 vault并不需要被初始化 因为这个地址是被计算出来的 只要计算出来 就可以标记里面有多少钱
-        // Check if the vault_x is valid
-        let (vault_x, _) = Address::find_program_address(
-            &[
-                self.accounts.config.address().as_ref(),
-                self.accounts.token_program.address().as_ref(),
-                config.mint_x().as_ref(),
-            ],
-            &pinocchio_associated_token_account::ID,
-        );
-        if vault_x.ne(self.accounts.vault_x.address()) {
-            return Err(ProgramError::InvalidAccountData);
-        }
-        let (vault_y, _) = Address::find_program_address(
-            &[
-                self.accounts.config.address().as_ref(),
-                self.accounts.token_program.address().as_ref(),
-                config.mint_y().as_ref(),
-            ],
-            &pinocchio_associated_token_account::ID,
-        );
-        if vault_y.ne(self.accounts.vault_y.address()) {
-            return Err(ProgramError::InvalidAccountData);
+        config.check_vaults(
+            self.accounts.vault_x.address(),
+            self.accounts.vault_y.address(),
+        )?;
+        // 集中流动性池的价格和流动性在 ClPool 里 逐个 tick 交换
+        if config.curve_type() == CurveType::Concentrated as u8 {
+            drop(config);
+            return self.process_concentrated();
         }
         // Deserialize the token accounts
-        let vault_x = unsafe { TokenAccount::from_account_view_unchecked(self.accounts.vault_x)? };
-        let vault_y = unsafe { TokenAccount::from_account_view_unchecked(self.accounts.vault_y)? };
+        let vault_x = unsafe { token_account_unchecked(self.accounts.vault_x)? };
+        let vault_y = unsafe { token_account_unchecked(self.accounts.vault_y)? };
+        let mint_x = unsafe { mint_unchecked(self.accounts.mint_x)? };
+        let mint_y = unsafe { mint_unchecked(self.accounts.mint_y)? };
+        // 金库余额扣除未领取的协议费之后才是 LP 的储备
+        let (reserve_x, reserve_y) = config.reserves(vault_x.amount(), vault_y.amount())?;
         // Swap Calculations
-        let mut curve = ConstantProduct::init(
-            vault_x.amount(),
-            vault_y.amount(),
-            vault_x.amount(),
-            config.fee(),
-            None,
-        )
-        .map_err(|_| ProgramError::Custom(1))?;
+        let mut curve = config.curve(reserve_x, reserve_y, Clock::get()?.unix_timestamp)?;
         let p = match self.instruction_data.is_x {
             true => LiquidityPair::X,
             false => LiquidityPair::Y,
         };
-        // 0- deposit 1-fee 2-withdraw
-        let swap_result = curve
-            .swap(p, self.instruction_data.amount, self.instruction_data.min)
-            .map_err(|_| ProgramError::Custom(1))?;
+        // Token-2022 转账费 支付的代币进金库时扣一次 收到的代币出金库时扣一次
+        let epoch = Clock::get()?.epoch;
+        let (fee_in, fee_out) = match self.instruction_data.is_x {
+            true => (
+                TransferFee::from_mint(self.accounts.mint_x, epoch)?,
+                TransferFee::from_mint(self.accounts.mint_y, epoch)?,
+            ),
+            false => (
+                TransferFee::from_mint(self.accounts.mint_y, epoch)?,
+                TransferFee::from_mint(self.accounts.mint_x, epoch)?,
+            ),
+        };
+        // 0- deposit 1-fee 2-withdraw 都是金库实际收到和转出的数量
+        // amount_in 是用户转出的数量 amount_out 是用户实际收到的数量
+        let (swap_result, amount_in, amount_out) = match self.instruction_data.mode {
+            SwapMode::ExactIn => {
+                let received = fee_in.amount_after_fee(self.instruction_data.amount)?;
+                let swap_result = curve.swap(p, received, 0)?;
+                let amount_out = fee_out.amount_after_fee(swap_result.2)?;
+                if amount_out < self.instruction_data.limit {
+                    return Err(AmmError::SlippageExceeded.into());
+                }
+                (swap_result, self.instruction_data.amount, amount_out)
+            }
+            SwapMode::ExactOut => {
+                let gross_out = fee_out.amount_before_fee(self.instruction_data.amount)?;
+                let swap_result = curve.swap_exact_out(p, gross_out, u64::MAX)?;
+                let amount_in = fee_in.amount_before_fee(swap_result.0)?;
+                if amount_in > self.instruction_data.limit {
+                    return Err(AmmError::SlippageExceeded.into());
+                }
+                (swap_result, amount_in, self.instruction_data.amount)
+            }
+        };
         // Check for correct values
         // 不允许支付金额或者提现金额为0
-        if swap_result.0 == 0 || swap_result.2 == 0 {
-            return Err(ProgramError::InvalidArgument);
+        if swap_result.0 == 0 || amount_out == 0 {
+            return Err(AmmError::ZeroAmount.into());
         }
+        // 交换费中属于协议的部分 剩下的留在金库里归 LP
+        let (_, protocol_fee) = ConstantProduct::split_fee(swap_result.1, config.protocol_fee())?;
         // 进行交易
         // 构建config签名
         let seed_bytes = config.seed().to_le_bytes();
@@ -204,202 +259,231 @@ impl<'a> Swap<'a> {
         if self.instruction_data.is_x {
             // 如果是 支付x 获取 y
             // 从用户的 x ata里面转移 amount 个代币到 金库
-            Transfer {
+            TransferChecked {
                 from: self.accounts.user_x_ata,
+                mint: self.accounts.mint_x,
                 to: self.accounts.vault_x,
                 authority: self.accounts.user,
-                amount: self.instruction_data.amount,
+                amount: amount_in,
+                decimals: mint_x.decimals(),
+                token_program: self.accounts.token_program_x,
             }
             .invoke()?;
-            Transfer {
+            TransferChecked {
                 from: self.accounts.vault_y,
+                mint: self.accounts.mint_y,
                 to: self.accounts.user_y_ata,
                 authority: self.accounts.config,
                 amount: swap_result.2,
+                decimals: mint_y.decimals(),
+                token_program: self.accounts.token_program_y,
             }
             .invoke_signed(&config_signer)?;
         } else {
             // 如果是 支付y 获取 x
             // 从用户的 y ata里面转移 amount 个代币到 金库
-            Transfer {
+            TransferChecked {
                 from: self.accounts.user_y_ata,
+                mint: self.accounts.mint_y,
                 to: self.accounts.vault_y,
                 authority: self.accounts.user,
-                amount: self.instruction_data.amount,
+                amount: amount_in,
+                decimals: mint_y.decimals(),
+                token_program: self.accounts.token_program_y,
             }
             .invoke()?;
-            Transfer {
+            TransferChecked {
                 from: self.accounts.vault_x,
+                mint: self.accounts.mint_x,
                 to: self.accounts.user_x_ata,
                 authority: self.accounts.config,
                 amount: swap_result.2,
+                decimals: mint_x.decimals(),
+                token_program: self.accounts.token_program_x,
             }
             .invoke_signed(&config_signer)?;
         }
-        Ok(())
-    }
-}
 
-/// 下面的代码都是从根据task的要求引入的git上复制过来的
-/// 根据自己的理解写了一些注释
-// 这里应该是校验数组中的全部数据是否包含 0
-macro_rules! assert_non_zero {
-    ($array:expr) => {
-        if $array.contains(&0u64) {
-            return Err(ProgramError::InvalidArgument);
-        }
-    };
-}
-
-// 这个宏的意思是比较两个2个数的大小 如果 第一个数比第二个数的就报错
-macro_rules! swap_slippage {
-    ($x:expr, $x_min:expr) => {
-        if $x < $x_min {
-            return Err(ProgramError::InvalidArgument);
+        // 记录协议费和价格累加器 需要先释放只读借用才能可变地加载 config
+        drop(config);
+        let now = Clock::get()?.unix_timestamp;
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.update_price_cumulative(reserve_x, reserve_y, now)?;
+        if self.instruction_data.is_x {
+            config.add_protocol_fees_x(protocol_fee)?;
+        } else {
+            config.add_protocol_fees_y(protocol_fee)?;
         }
-    };
-}
-
-#[derive(Debug)]
-pub enum LiquidityPair {
-    X,
-    Y,
-}
-
-// x-代币x的金额 y-代币y的金额 l-代币x的金额 fee-费率 precision-精度
-// 这里 l 为代币x的金额其实是因为在初始化里面会有个逻辑 当代币x的数量为0的时候会取x和y的最大值
-#[derive(Debug)]
-pub struct ConstantProduct {
-    x: u64,   // Balance of Token X
-    y: u64,   // Balance of Token Y
-    l: u64,   // LP Token Balance
-    fee: u16, // Fee in basis points, ie: 100 = 1%
-    precision: u32,
-}
-impl ConstantProduct {
-    // Create a new Constant Product Curve
-    pub fn init(
-        x: u64,
-        y: u64,
-        l: u64,
-        fee: u16,
-        precision: Option<u8>,
-    ) -> Result<ConstantProduct, ProgramError> {
-        // Assert non-zero values of X and Y
-        assert_non_zero!([x, y]);
-        // 给一个默认的精度
-        let precision = match precision {
-            Some(p) => 10u32
-                .checked_pow(p as u32)
-                .ok_or(ProgramError::InvalidArgument)?,
-            None => 1_000_000,
-        };
 
-        // If L is zero, make it the higher value of either X or Y, as this will have less rounding errors
-        let l = match l > 0 {
-            true => l,
-            false => x.max(y),
+        // 交换之后 LP 的储备 协议费不属于 LP
+        let (reserve_x, reserve_y) = match self.instruction_data.is_x {
+            true => (curve.x() - protocol_fee, curve.y()),
+            false => (curve.x(), curve.y() - protocol_fee),
         };
-
-        Ok(ConstantProduct {
-            x,
-            y,
-            l,
-            fee,
-            precision,
+        // 按交换之后的价格更新波动率 影响之后交换的动态费率
+        config.update_volatility(reserve_x, reserve_y, now)?;
+        AmmEvent::Swap(SwapEvent {
+            pool: *self.accounts.config.address(),
+            user: *self.accounts.user.address(),
+            is_x: self.instruction_data.is_x,
+            amount_in,
+            amount_out,
+            fee: swap_result.1,
+            reserve_x,
+            reserve_y,
         })
+        .emit();
+        Ok(())
     }
+    // 集中流动性池的交换 只支持 ExactIn 使用池子的固定费率
+    // 价格由 ClPool 决定 不更新价格累加器和波动率
+    fn process_concentrated(&self) -> ProgramResult {
+        let [cl_pool, tick_arrays @ ..] = self.accounts.remaining else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        if tick_arrays.is_empty() || tick_arrays.len() > MAX_TICK_ARRAYS {
+            return Err(AmmError::InvalidTickArray.into());
+        }
+        if self.instruction_data.mode != SwapMode::ExactIn {
+            return Err(AmmError::InvalidCurve.into());
+        }
+        let config = Config::load(self.accounts.config)?;
+        let mut pool = ClPool::load_mut(cl_pool)?;
+        if pool.config().ne(self.accounts.config.address()) {
+            return Err(AmmError::InvalidCurve.into());
+        }
+        let p = match self.instruction_data.is_x {
+            true => LiquidityPair::X,
+            false => LiquidityPair::Y,
+        };
 
-    // p: x代表 支付x 获取y；y代表 支付y 获取x
-    // a: 愿意支付的代币数量
-    // min: 愿意接受的最小代币数量
-    // Swap asset X for asset Y or vice versa with slippage protection
-    pub fn swap(
-        &mut self,
-        p: LiquidityPair,
-        a: u64,
-        min: u64,
-    ) -> Result<(u64, u64, u64), ProgramError> {
-        // 支付金额 * (10_000 - 费率) / 10_000
-        // 这里是计算出扣掉费率之后 实际能用来买新代币的代币数量
-        // 这里的 10_000 就是 x * y 的值 也就是 k
-        let a2 = (a as u128)
-            .checked_mul((10_000 - self.fee) as u128)
-            .ok_or(ProgramError::InvalidArgument)?
-            .checked_div(10_000)
-            .ok_or(ProgramError::InvalidArgument)? as u64;
-
-        // 返回的是新的x值 新的y值和 支付给用户的代币数量
-        let (new_x, new_y, withdraw) = match p {
-            // 支付x 获取y
-            LiquidityPair::X => {
-                // 金库的代币x数量 + 买代币的数量 = 如果交易成功最新的x代币数量
-                let x2 = self
-                    .x
-                    .checked_add(a2)
-                    .ok_or(ProgramError::InvalidArgument)?;
-                // 获取y2的数量
-                let y2 = Self::y2_from_x_swap_amount(self.x, self.y, a2)?;
-                let delta_y = Self::delta_y_from_x_swap_amount(self.x, self.y, a2)?;
-                (x2, y2, delta_y)
+        // tick 数组必须是从当前价格开始沿交换方向相邻的 PDA 还没有创建的数组没有流动性
+        let first = first_tick_array_start(pool.tick(), pool.tick_spacing(), p);
+        let mut loaded: [Option<RefMut<TickArray>>; MAX_TICK_ARRAYS] = [None, None, None];
+        for (i, tick_array) in tick_arrays.iter().enumerate() {
+            let start_tick = tick_array_start(first, i, pool.tick_spacing(), p);
+            let (address, _) = tick_array_address(self.accounts.config.address(), start_tick);
+            if address.ne(tick_array.address()) {
+                return Err(AmmError::InvalidTickArray.into());
             }
-            // 支付y 获取x
-            LiquidityPair::Y => {
-                // 计算出新的x代币的数量
-                let x2 = Self::x2_from_y_swap_amount(self.x, self.y, a)?;
-                // 金库的代币x数量 + 买代币的数量 = 如果交易成功最新的x代币数量
-                let y2 = self.y.checked_add(a).ok_or(ProgramError::InvalidArgument)?;
-                let delta_x = Self::delta_x_from_y_swap_amount(self.x, self.y, a)?;
-                (x2, y2, delta_x)
+            if !tick_array.is_data_empty() {
+                loaded[i] = Some(TickArray::load_mut(tick_array)?);
             }
-        };
-        // 如果提现的数据小于用户愿意获取的最小值 报错返回
-        swap_slippage!(withdraw, min);
-        // 费率也就是支付给AMM的钱 就是 用户支付的代币金额 - 用户实际用于支付的代币金额
-        let fee = a.checked_sub(a2).ok_or(ProgramError::InvalidArgument)?;
-        self.x = new_x;
-        self.y = new_y;
-
-        Ok((a, fee, withdraw))
-    }
-
-    // x-之前的代币x的数量 y-之前的代币y的数量 a-新代币x的数量
-    fn y2_from_x_swap_amount(x: u64, y: u64, a: u64) -> Result<u64, ProgramError> {
-        Self::x2_from_y_swap_amount(y, x, a)
-    }
+        }
+        let mut arrays = loaded
+            .each_mut()
+            .map(|tick_array| tick_array.as_deref_mut());
 
-    // 这里是一个公用方法 x转y 和 y转x 都走这个方法 但是这里的x和y传的值不一样
-    // 这里等于是计算出原有的k值 然后把新的 y 值计算出来 然后再用 k 除以最新的y值 得到新的 x值
-    // Calculate new value of X after depositing Y
-    // When we swap amount A of Y for X, we must calculate the new balance of X from invariant K
-    // Y₂ = Y₁ + Amount
-    // X₂ = K / Y₂
-    pub fn x2_from_y_swap_amount(x: u64, y: u64, a: u64) -> Result<u64, ProgramError> {
-        let k = Self::k_from_xy(x, y)?;
-        let x_new = (y as u128)
-            .checked_add(a as u128)
-            .ok_or(ProgramError::InvalidArgument)?;
-        Ok(k.checked_div(x_new).ok_or(ProgramError::InvalidArgument)? as u64)
-    }
+        // Token-2022 转账费 支付的代币进金库时扣一次 收到的代币出金库时扣一次
+        let epoch = Clock::get()?.epoch;
+        let (
+            mint_in,
+            vault_in,
+            user_in,
+            token_program_in,
+            mint_out,
+            vault_out,
+            user_out,
+            token_program_out,
+        ) = match self.instruction_data.is_x {
+            true => (
+                self.accounts.mint_x,
+                self.accounts.vault_x,
+                self.accounts.user_x_ata,
+                self.accounts.token_program_x,
+                self.accounts.mint_y,
+                self.accounts.vault_y,
+                self.accounts.user_y_ata,
+                self.accounts.token_program_y,
+            ),
+            false => (
+                self.accounts.mint_y,
+                self.accounts.vault_y,
+                self.accounts.user_y_ata,
+                self.accounts.token_program_y,
+                self.accounts.mint_x,
+                self.accounts.vault_x,
+                self.accounts.user_x_ata,
+                self.accounts.token_program_x,
+            ),
+        };
+        let received = TransferFee::from_mint(mint_in, epoch)?
+            .amount_after_fee(self.instruction_data.amount)?;
+        let result = pool.swap(
+            &mut arrays[..tick_arrays.len()],
+            p,
+            received,
+            config.fee(),
+            config.protocol_fee(),
+        )?;
+        drop(pool);
+        let amount_out =
+            TransferFee::from_mint(mint_out, epoch)?.amount_after_fee(result.amount_out)?;
+        if amount_out < self.instruction_data.limit {
+            return Err(AmmError::SlippageExceeded.into());
+        }
+        if received == 0 || amount_out == 0 {
+            return Err(AmmError::ZeroAmount.into());
+        }
 
-    // 就是旧的代币数量减去新的代币数量 也就是用户买到的代币数量
-    // Calculate the withdraw amount of X from swapping in Y
-    // ΔX = X₁ - X₂
-    pub fn delta_x_from_y_swap_amount(x: u64, y: u64, a: u64) -> Result<u64, ProgramError> {
-        Ok(x.checked_sub(Self::x2_from_y_swap_amount(x, y, a)?)
-            .ok_or(ProgramError::InvalidArgument)?)
-    }
+        // 构建config签名
+        let seed_bytes = config.seed().to_le_bytes();
+        let mut seed_array = [0u8; 8];
+        seed_array.copy_from_slice(&seed_bytes);
+        let bump = config.config_bump();
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(&seed_array),
+            Seed::from(config.mint_x().as_ref()),
+            Seed::from(config.mint_y().as_ref()),
+            Seed::from(&bump),
+        ];
+        let config_signer = [Signer::from(&config_seeds)];
 
-    // Calculate difference in Y from swapping in X
-    // ΔY = Y₁ - Y₂
-    pub fn delta_y_from_x_swap_amount(x: u64, y: u64, a: u64) -> Result<u64, ProgramError> {
-        Self::delta_x_from_y_swap_amount(y, x, a)
-    }
+        let mint_in_state = unsafe { mint_unchecked(mint_in)? };
+        let mint_out_state = unsafe { mint_unchecked(mint_out)? };
+        TransferChecked {
+            from: user_in,
+            mint: mint_in,
+            to: vault_in,
+            authority: self.accounts.user,
+            amount: self.instruction_data.amount,
+            decimals: mint_in_state.decimals(),
+            token_program: token_program_in,
+        }
+        .invoke()?;
+        TransferChecked {
+            from: vault_out,
+            mint: mint_out,
+            to: user_out,
+            authority: self.accounts.config,
+            amount: result.amount_out,
+            decimals: mint_out_state.decimals(),
+            token_program: token_program_out,
+        }
+        .invoke_signed(&config_signer)?;
 
-    // 这里就是校验 x 和 y都 不为0 之后 获取 x*y 也就是k的值 然后返回
-    // Static Invariant calculation
-    pub fn k_from_xy(x: u64, y: u64) -> Result<u128, ProgramError> {
-        assert_non_zero!([x, y]);
-        Ok((x as u128).checked_mul(y as u128).unwrap())
+        drop(config);
+        let mut config = Config::load_mut(self.accounts.config)?;
+        if self.instruction_data.is_x {
+            config.add_protocol_fees_x(result.protocol_fee)?;
+        } else {
+            config.add_protocol_fees_y(result.protocol_fee)?;
+        }
+        // 集中流动性池没有统一的储备 事件里记录交换之后金库中属于头寸的余额
+        let vault_x = unsafe { token_account_unchecked(self.accounts.vault_x)? };
+        let vault_y = unsafe { token_account_unchecked(self.accounts.vault_y)? };
+        AmmEvent::Swap(SwapEvent {
+            pool: *self.accounts.config.address(),
+            user: *self.accounts.user.address(),
+            is_x: self.instruction_data.is_x,
+            amount_in: self.instruction_data.amount,
+            amount_out,
+            fee: result.fee,
+            reserve_x: vault_x.amount().saturating_sub(config.protocol_fees_x()),
+            reserve_y: vault_y.amount().saturating_sub(config.protocol_fees_y()),
+        })
+        .emit();
+        Ok(())
     }
 }
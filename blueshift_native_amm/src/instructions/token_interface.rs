@@ -0,0 +1,571 @@
+use crate::instructions::helper::AccountCheck;
+use crate::AmmError;
+use pinocchio::{
+    cpi::{invoke, invoke_signed, Signer},
+    error::ProgramError,
+    instruction::{InstructionAccount, InstructionView},
+    AccountView, Address, ProgramResult,
+};
+use pinocchio_token::state::{Mint, TokenAccount};
+
+// 同时兼容 SPL Token 和 Token-2022 的代币操作
+// pinocchio_token 里的指令固定发给 SPL Token 程序 这里的指令会发给传入的 token_program
+// 两个程序的指令格式和账户基础布局完全一致 Token-2022 只是在基础布局后面追加了扩展数据
+
+// TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb
+pub const TOKEN_2022_PROGRAM_ID: Address = Address::new_from_array([
+    0x06, 0xdd, 0xf6, 0xe1, 0xee, 0x75, 0x8f, 0xde, 0x18, 0x42, 0x5d, 0xbc, 0xe4, 0x6c, 0xcd, 0xda,
+    0xb6, 0x1a, 0xfc, 0x4d, 0x83, 0xb9, 0x0d, 0x27, 0xfe, 0xbd, 0xf9, 0x28, 0xd8, 0xa1, 0x8b, 0xfc,
+]);
+
+// Token-2022 的扩展数据从 165 字节之后开始：1 字节账户类型 然后是 TLV 格式的扩展列表
+const ACCOUNT_TYPE_OFFSET: usize = TokenAccount::LEN;
+const EXTENSIONS_OFFSET: usize = ACCOUNT_TYPE_OFFSET + 1;
+pub const ACCOUNT_TYPE_MINT: u8 = 1;
+pub const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+
+// TransferFeeConfig 扩展的类型和字段偏移
+const TRANSFER_FEE_CONFIG_EXTENSION: u16 = 1;
+const TRANSFER_FEE_CONFIG_LEN: usize = 108;
+const OLDER_TRANSFER_FEE_OFFSET: usize = 72;
+const NEWER_TRANSFER_FEE_OFFSET: usize = 90;
+
+// TokenMetadata 扩展的类型
+const TOKEN_METADATA_EXTENSION: u16 = 19;
+// 带 MetadataPointer 扩展的铸币账户长度：扩展头 4 字节 + authority 32 字节 + metadata_address 32 字节
+pub const MINT_WITH_METADATA_POINTER_LEN: usize = EXTENSIONS_OFFSET + 4 + 64;
+// sha256("spl_token_metadata_interface:initialize_account") 的前 8 字节
+const INITIALIZE_TOKEN_METADATA_DISCRIMINATOR: [u8; 8] = [210, 225, 30, 162, 88, 184, 77, 141];
+// 代币元数据各字段的长度上限
+pub const MAX_NAME_LEN: usize = 32;
+pub const MAX_SYMBOL_LEN: usize = 32;
+pub const MAX_URI_LEN: usize = 200;
+
+pub struct TokenProgram;
+
+impl AccountCheck for TokenProgram {
+    fn check(account: &AccountView) -> Result<(), ProgramError> {
+        if account.address().ne(&pinocchio_token::ID)
+            && account.address().ne(&TOKEN_2022_PROGRAM_ID)
+        {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Ok(())
+    }
+}
+
+// 校验传入的铸币账户就是池子记录的铸币 并且属于传入的代币程序
+#[inline(always)]
+pub fn check_mint(
+    mint: &AccountView,
+    expected: &Address,
+    token_program: &AccountView,
+) -> Result<(), ProgramError> {
+    if mint.address().ne(expected) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if !mint.owned_by(token_program.address()) {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+// 池子的 LP 铸币账户 PDA: [b"mint_lp", config]
+#[inline(always)]
+pub fn lp_mint_address(config: &AccountView) -> Address {
+    Address::find_program_address(&[b"mint_lp", config.address().as_ref()], &crate::ID).0
+}
+
+// 校验传入的 LP 铸币账户就是池子的 PDA
+// LP 的份额按它的供应量计算 任何人都能创建一个只有自己持有的铸币账户 不校验地址就能取走全部储备
+#[inline(always)]
+pub fn check_lp_mint(
+    mint_lp: &AccountView,
+    config: &AccountView,
+    token_program: &AccountView,
+) -> Result<(), ProgramError> {
+    check_mint(mint_lp, &lp_mint_address(config), token_program)
+}
+
+// Token-2022 带扩展的账户：基础布局 + 填充到 165 字节 + 账户类型 + 扩展
+#[inline(always)]
+pub fn has_account_type(account: &AccountView, account_type: u8) -> Result<bool, ProgramError> {
+    if account.data_len() <= ACCOUNT_TYPE_OFFSET {
+        return Ok(false);
+    }
+    let data = account.try_borrow()?;
+    Ok(data[ACCOUNT_TYPE_OFFSET] == account_type)
+}
+
+// 读取代币账户的基础字段 兼容 Token-2022 带扩展的账户
+// 调用者需要保证账户已经通过 TokenAccount 校验
+#[inline(always)]
+pub unsafe fn token_account_unchecked(
+    account: &AccountView,
+) -> Result<&TokenAccount, ProgramError> {
+    if account.data_len() < TokenAccount::LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(unsafe { TokenAccount::from_bytes_unchecked(account.borrow_unchecked()) })
+}
+
+// 读取铸币账户的基础字段 兼容 Token-2022 带扩展的铸币账户
+// 调用者需要保证账户已经通过 MintInterface 校验
+#[inline(always)]
+pub unsafe fn mint_unchecked(account: &AccountView) -> Result<&Mint, ProgramError> {
+    if account.data_len() < Mint::LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(unsafe { Mint::from_bytes_unchecked(account.borrow_unchecked()) })
+}
+
+// 转账费配置 (基点, 单笔最大手续费)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransferFee {
+    pub basis_points: u16,
+    pub maximum_fee: u64,
+}
+
+impl TransferFee {
+    // 读取铸币账户当前 epoch 生效的转账费 SPL Token 和没有 TransferFeeConfig 扩展的铸币没有转账费
+    pub fn from_mint(mint: &AccountView, epoch: u64) -> Result<Self, ProgramError> {
+        if !mint.owned_by(&TOKEN_2022_PROGRAM_ID) || mint.data_len() <= EXTENSIONS_OFFSET {
+            return Ok(Self::default());
+        }
+        let data = mint.try_borrow()?;
+        let mut offset = EXTENSIONS_OFFSET;
+        while offset + 4 <= data.len() {
+            let extension = u16::from_le_bytes([data[offset], data[offset + 1]]);
+            let len = u16::from_le_bytes([data[offset + 2], data[offset + 3]]) as usize;
+            let value = offset + 4;
+            if extension == TRANSFER_FEE_CONFIG_EXTENSION {
+                if len != TRANSFER_FEE_CONFIG_LEN || value + len > data.len() {
+                    return Err(ProgramError::InvalidAccountData);
+                }
+                let config = &data[value..value + len];
+                let newer = Self::parse(&config[NEWER_TRANSFER_FEE_OFFSET..])?;
+                let newer_epoch = u64::from_le_bytes(
+                    config[NEWER_TRANSFER_FEE_OFFSET..NEWER_TRANSFER_FEE_OFFSET + 8]
+                        .try_into()
+                        .unwrap(),
+                );
+                // 新的费率从 newer_epoch 开始生效 在此之前仍然使用旧的费率
+                return match epoch >= newer_epoch {
+                    true => Ok(newer),
+                    false => Self::parse(&config[OLDER_TRANSFER_FEE_OFFSET..]),
+                };
+            }
+            // 类型为 0 表示后面没有扩展了
+            if extension == 0 {
+                break;
+            }
+            offset = value + len;
+        }
+        Ok(Self::default())
+    }
+
+    // TransferFee 布局：epoch u64 | maximum_fee u64 | transfer_fee_basis_points u16
+    fn parse(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < 18 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            maximum_fee: u64::from_le_bytes(data[8..16].try_into().unwrap()),
+            basis_points: u16::from_le_bytes(data[16..18].try_into().unwrap()),
+        })
+    }
+
+    // 转出 amount 时被扣掉的手续费 和 Token-2022 一样向上取整并且不超过 maximum_fee
+    pub fn fee(&self, amount: u64) -> Result<u64, ProgramError> {
+        if self.basis_points == 0 || amount == 0 {
+            return Ok(0);
+        }
+        let fee = (amount as u128 * self.basis_points as u128).div_ceil(10_000);
+        Ok((fee as u64).min(self.maximum_fee))
+    }
+
+    // 收款方实际收到的数量
+    pub fn amount_after_fee(&self, amount: u64) -> Result<u64, ProgramError> {
+        Ok(amount - self.fee(amount)?)
+    }
+
+    // 为了让收款方恰好收到 amount 发送方需要转出的数量
+    pub fn amount_before_fee(&self, amount: u64) -> Result<u64, ProgramError> {
+        if self.basis_points == 0 || amount == 0 {
+            return Ok(amount);
+        }
+        if self.basis_points >= 10_000 {
+            return amount
+                .checked_add(self.maximum_fee)
+                .ok_or(AmmError::CurveOverflow.into());
+        }
+        let gross = (amount as u128 * 10_000).div_ceil(10_000 - self.basis_points as u128);
+        let gross = u64::try_from(gross).map_err(|_| AmmError::CurveOverflow)?;
+        let mut gross = gross.min(
+            amount
+                .checked_add(self.maximum_fee)
+                .ok_or(AmmError::CurveOverflow)?,
+        );
+        // 手续费向上取整 上面的反推可能少 1
+        while self.amount_after_fee(gross)? < amount {
+            gross = gross.checked_add(1).ok_or(AmmError::CurveOverflow)?;
+        }
+        Ok(gross)
+    }
+}
+
+// 在 Token-2022 账户的扩展列表中查找指定类型的扩展 返回扩展的数据
+fn find_extension(data: &[u8], extension_type: u16) -> Result<Option<&[u8]>, ProgramError> {
+    let mut offset = EXTENSIONS_OFFSET;
+    while offset + 4 <= data.len() {
+        let extension = u16::from_le_bytes([data[offset], data[offset + 1]]);
+        let len = u16::from_le_bytes([data[offset + 2], data[offset + 3]]) as usize;
+        let value = offset + 4;
+        // 类型为 0 表示后面没有扩展了
+        if extension == 0 {
+            break;
+        }
+        if value + len > data.len() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if extension == extension_type {
+            return Ok(Some(&data[value..value + len]));
+        }
+        offset = value + len;
+    }
+    Ok(None)
+}
+
+// 铸币账户是否带有 TransferFeeConfig 扩展 这种代币的账户里可能有扣留的转账费 不能直接关闭
+pub fn has_transfer_fee_config(mint: &AccountView) -> Result<bool, ProgramError> {
+    if !mint.owned_by(&TOKEN_2022_PROGRAM_ID) || mint.data_len() <= EXTENSIONS_OFFSET {
+        return Ok(false);
+    }
+    let data = mint.try_borrow()?;
+    Ok(find_extension(&data, TRANSFER_FEE_CONFIG_EXTENSION)?.is_some())
+}
+
+// 如果铸币账户自带 Token-2022 的 TokenMetadata 扩展 检查其中的 symbol 和传入的一致
+// SPL Token 和没有这个扩展的铸币无法在链上读取 symbol 直接通过
+pub fn check_metadata_symbol(mint: &AccountView, symbol: &[u8]) -> ProgramResult {
+    if !mint.owned_by(&TOKEN_2022_PROGRAM_ID) || mint.data_len() <= EXTENSIONS_OFFSET {
+        return Ok(());
+    }
+    let data = mint.try_borrow()?;
+    let Some(metadata) = find_extension(&data, TOKEN_METADATA_EXTENSION)? else {
+        return Ok(());
+    };
+    // update_authority 32 | mint 32 | name (u32 长度 + 字节) | symbol (u32 长度 + 字节) | ...
+    let read_string = |offset: usize| -> Result<(&[u8], usize), ProgramError> {
+        let len = metadata
+            .get(offset..offset + 4)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+        let value = metadata
+            .get(offset + 4..offset + 4 + len)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        Ok((value, offset + 4 + len))
+    };
+    let (_, symbol_offset) = read_string(64)?;
+    let (expected, _) = read_string(symbol_offset)?;
+    if expected != symbol {
+        return Err(AmmError::InvalidMetadata.into());
+    }
+    Ok(())
+}
+
+// TokenMetadata 扩展占用的长度 4 字节扩展头 + 数据
+#[inline(always)]
+pub fn token_metadata_len(name: &[u8], symbol: &[u8], uri: &[u8]) -> usize {
+    // update_authority | mint | name | symbol | uri | additional_metadata (空的 Vec)
+    4 + 32 + 32 + (4 + name.len()) + (4 + symbol.len()) + (4 + uri.len()) + 4
+}
+
+pub struct TransferChecked<'a> {
+    pub from: &'a AccountView,
+    pub mint: &'a AccountView,
+    pub to: &'a AccountView,
+    pub authority: &'a AccountView,
+    pub amount: u64,
+    pub decimals: u8,
+    pub token_program: &'a AccountView,
+}
+
+impl TransferChecked<'_> {
+    #[inline(always)]
+    pub fn invoke(&self) -> ProgramResult {
+        self.invoke_signed(&[])
+    }
+
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let accounts = [
+            InstructionAccount::writable(self.from.address()),
+            InstructionAccount::readonly(self.mint.address()),
+            InstructionAccount::writable(self.to.address()),
+            InstructionAccount::readonly_signer(self.authority.address()),
+        ];
+        let mut data = [0u8; 10];
+        data[0] = 12;
+        data[1..9].copy_from_slice(&self.amount.to_le_bytes());
+        data[9] = self.decimals;
+        let instruction = InstructionView {
+            program_id: self.token_program.address(),
+            accounts: &accounts,
+            data: &data,
+        };
+        invoke_signed(
+            &instruction,
+            &[self.from, self.mint, self.to, self.authority],
+            signers,
+        )
+    }
+}
+
+pub struct MintTo<'a> {
+    pub mint: &'a AccountView,
+    pub account: &'a AccountView,
+    pub mint_authority: &'a AccountView,
+    pub amount: u64,
+    pub token_program: &'a AccountView,
+}
+
+impl MintTo<'_> {
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let accounts = [
+            InstructionAccount::writable(self.mint.address()),
+            InstructionAccount::writable(self.account.address()),
+            InstructionAccount::readonly_signer(self.mint_authority.address()),
+        ];
+        let mut data = [0u8; 9];
+        data[0] = 7;
+        data[1..9].copy_from_slice(&self.amount.to_le_bytes());
+        let instruction = InstructionView {
+            program_id: self.token_program.address(),
+            accounts: &accounts,
+            data: &data,
+        };
+        invoke_signed(
+            &instruction,
+            &[self.mint, self.account, self.mint_authority],
+            signers,
+        )
+    }
+}
+
+pub struct Burn<'a> {
+    pub account: &'a AccountView,
+    pub mint: &'a AccountView,
+    pub authority: &'a AccountView,
+    pub amount: u64,
+    pub token_program: &'a AccountView,
+}
+
+impl Burn<'_> {
+    pub fn invoke(&self) -> ProgramResult {
+        let accounts = [
+            InstructionAccount::writable(self.account.address()),
+            InstructionAccount::writable(self.mint.address()),
+            InstructionAccount::readonly_signer(self.authority.address()),
+        ];
+        let mut data = [0u8; 9];
+        data[0] = 8;
+        data[1..9].copy_from_slice(&self.amount.to_le_bytes());
+        let instruction = InstructionView {
+            program_id: self.token_program.address(),
+            accounts: &accounts,
+            data: &data,
+        };
+        invoke(&instruction, &[self.account, self.mint, self.authority])
+    }
+}
+
+// 关闭余额为 0 的代币账户 租金退给 destination
+pub struct CloseAccount<'a> {
+    pub account: &'a AccountView,
+    pub destination: &'a AccountView,
+    pub authority: &'a AccountView,
+    pub token_program: &'a AccountView,
+}
+
+impl CloseAccount<'_> {
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let accounts = [
+            InstructionAccount::writable(self.account.address()),
+            InstructionAccount::writable(self.destination.address()),
+            InstructionAccount::readonly_signer(self.authority.address()),
+        ];
+        let instruction = InstructionView {
+            program_id: self.token_program.address(),
+            accounts: &accounts,
+            data: &[9],
+        };
+        invoke_signed(
+            &instruction,
+            &[self.account, self.destination, self.authority],
+            signers,
+        )
+    }
+}
+
+// 冻结代币账户 只有铸币账户的 freeze_authority 可以调用
+pub struct FreezeAccount<'a> {
+    pub account: &'a AccountView,
+    pub mint: &'a AccountView,
+    pub freeze_authority: &'a AccountView,
+    pub token_program: &'a AccountView,
+}
+
+impl FreezeAccount<'_> {
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let accounts = [
+            InstructionAccount::writable(self.account.address()),
+            InstructionAccount::readonly(self.mint.address()),
+            InstructionAccount::readonly_signer(self.freeze_authority.address()),
+        ];
+        let instruction = InstructionView {
+            program_id: self.token_program.address(),
+            accounts: &accounts,
+            data: &[10],
+        };
+        invoke_signed(
+            &instruction,
+            &[self.account, self.mint, self.freeze_authority],
+            signers,
+        )
+    }
+}
+
+// 解冻代币账户 账户布局和 FreezeAccount 相同
+pub struct ThawAccount<'a> {
+    pub account: &'a AccountView,
+    pub mint: &'a AccountView,
+    pub freeze_authority: &'a AccountView,
+    pub token_program: &'a AccountView,
+}
+
+impl ThawAccount<'_> {
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let accounts = [
+            InstructionAccount::writable(self.account.address()),
+            InstructionAccount::readonly(self.mint.address()),
+            InstructionAccount::readonly_signer(self.freeze_authority.address()),
+        ];
+        let instruction = InstructionView {
+            program_id: self.token_program.address(),
+            accounts: &accounts,
+            data: &[11],
+        };
+        invoke_signed(
+            &instruction,
+            &[self.account, self.mint, self.freeze_authority],
+            signers,
+        )
+    }
+}
+
+pub struct InitializeMint2<'a> {
+    pub mint: &'a AccountView,
+    pub decimals: u8,
+    pub mint_authority: &'a Address,
+    pub freeze_authority: Option<&'a Address>,
+    pub token_program: &'a AccountView,
+}
+
+impl InitializeMint2<'_> {
+    pub fn invoke(&self) -> ProgramResult {
+        let accounts = [InstructionAccount::writable(self.mint.address())];
+        // 20 | decimals | mint_authority | COption<freeze_authority>
+        let mut data = [0u8; 67];
+        data[0] = 20;
+        data[1] = self.decimals;
+        data[2..34].copy_from_slice(self.mint_authority.as_ref());
+        if let Some(freeze_authority) = self.freeze_authority {
+            data[34] = 1;
+            data[35..67].copy_from_slice(freeze_authority.as_ref());
+        }
+        let instruction = InstructionView {
+            program_id: self.token_program.address(),
+            accounts: &accounts,
+            data: &data,
+        };
+        invoke(&instruction, &[self.mint])
+    }
+}
+
+// Token-2022 的 MetadataPointer 扩展 必须在 InitializeMint2 之前初始化
+pub struct InitializeMetadataPointer<'a> {
+    pub mint: &'a AccountView,
+    pub authority: &'a Address,
+    pub metadata_address: &'a Address,
+    pub token_program: &'a AccountView,
+}
+
+impl InitializeMetadataPointer<'_> {
+    pub fn invoke(&self) -> ProgramResult {
+        let accounts = [InstructionAccount::writable(self.mint.address())];
+        // 39 | 0 (Initialize) | authority | metadata_address
+        let mut data = [0u8; 66];
+        data[0] = 39;
+        data[2..34].copy_from_slice(self.authority.as_ref());
+        data[34..66].copy_from_slice(self.metadata_address.as_ref());
+        let instruction = InstructionView {
+            program_id: self.token_program.address(),
+            accounts: &accounts,
+            data: &data,
+        };
+        invoke(&instruction, &[self.mint])
+    }
+}
+
+// 把元数据写进铸币账户自己的 TokenMetadata 扩展 需要铸币权限签名
+// 调用之前铸币账户里要有足够支付扩展之后租金的 lamports
+pub struct InitializeTokenMetadata<'a> {
+    pub mint: &'a AccountView,
+    pub update_authority: &'a AccountView,
+    pub mint_authority: &'a AccountView,
+    pub name: &'a [u8],
+    pub symbol: &'a [u8],
+    pub uri: &'a [u8],
+    pub token_program: &'a AccountView,
+}
+
+impl InitializeTokenMetadata<'_> {
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        if self.name.len() > MAX_NAME_LEN
+            || self.symbol.len() > MAX_SYMBOL_LEN
+            || self.uri.len() > MAX_URI_LEN
+        {
+            return Err(AmmError::InvalidMetadata.into());
+        }
+        let accounts = [
+            InstructionAccount::writable(self.mint.address()),
+            InstructionAccount::readonly(self.update_authority.address()),
+            InstructionAccount::readonly(self.mint.address()),
+            InstructionAccount::readonly_signer(self.mint_authority.address()),
+        ];
+        // discriminator | name | symbol | uri 字符串都是 u32 长度 + 字节
+        let mut data = [0u8; 8 + 4 * 3 + MAX_NAME_LEN + MAX_SYMBOL_LEN + MAX_URI_LEN];
+        data[..8].copy_from_slice(&INITIALIZE_TOKEN_METADATA_DISCRIMINATOR);
+        let mut len = 8;
+        for value in [self.name, self.symbol, self.uri] {
+            data[len..len + 4].copy_from_slice(&(value.len() as u32).to_le_bytes());
+            data[len + 4..len + 4 + value.len()].copy_from_slice(value);
+            len += 4 + value.len();
+        }
+        let instruction = InstructionView {
+            program_id: self.token_program.address(),
+            accounts: &accounts,
+            data: &data[..len],
+        };
+        invoke_signed(
+            &instruction,
+            &[
+                self.mint,
+                self.update_authority,
+                self.mint,
+                self.mint_authority,
+            ],
+            signers,
+        )
+    }
+}
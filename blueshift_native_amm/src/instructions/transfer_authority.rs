@@ -0,0 +1,70 @@
+use crate::instructions::helper::{AccountCheck, ProgramAccount, SignerAccount};
+use crate::Config;
+use pinocchio::{error::ProgramError, AccountView, Address, ProgramResult};
+
+pub struct TransferAuthorityAccounts<'a> {
+    // AMM 池当前的权限持有者。
+    pub authority: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for TransferAuthorityAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [authority, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        ProgramAccount::check(config)?;
+        Ok(Self { authority, config })
+    }
+}
+
+pub struct TransferAuthorityInstructionData {
+    // 被提名的新权限地址。全0表示撤销之前的提名。
+    pub new_authority: [u8; 32],
+}
+
+impl<'a> TryFrom<&'a [u8]> for TransferAuthorityInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<[u8; 32]>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        Ok(Self {
+            new_authority: data.try_into().unwrap(),
+        })
+    }
+}
+
+// 两步转移权限的第一步：当前权限持有者只是提名新地址
+// 只有新地址自己签名调用 AcceptAuthority 之后权限才会真正转移 这样填错地址也不会让池子失去管理者
+pub struct TransferAuthority<'a> {
+    pub accounts: TransferAuthorityAccounts<'a>,
+    pub instruction_data: TransferAuthorityInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for TransferAuthority<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = TransferAuthorityAccounts::try_from(accounts)?;
+        let instruction_data = TransferAuthorityInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> TransferAuthority<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.check_authority(self.accounts.authority.address())?;
+        config.set_pending_authority(Address::from(self.instruction_data.new_authority))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use crate::instructions::helper::{
+    AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck, MintInterface, SignerAccount,
+};
+use crate::instructions::token_interface::{
+    check_mint, mint_unchecked, TokenProgram, TransferChecked,
+};
+use crate::{AmmError, Farm, ProgramState, StakePosition};
+use pinocchio::{
+    cpi::{Seed, Signer},
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+
+pub struct UnstakeAccounts<'a> {
+    // 取回 LP 的用户。
+    pub owner: &'a AccountView,
+    // 用户的 LP 关联账户。
+    pub owner_lp_ata: &'a AccountView,
+    // 用户在农场中的质押账户。
+    pub stake: &'a AccountView,
+    // 池子的农场账户。
+    pub farm: &'a AccountView,
+    // 池子的 LP 铸币账户。
+    pub mint_lp: &'a AccountView,
+    // 农场持有质押 LP 的关联账户。
+    pub lp_vault: &'a AccountView,
+    // LP 铸币账户所属的代币程序账户。
+    pub lp_token_program: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for UnstakeAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [owner, owner_lp_ata, stake, farm, mint_lp, lp_vault, lp_token_program] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(owner)?;
+        MintInterface::check(mint_lp)?;
+        TokenProgram::check(lp_token_program)?;
+        Ok(Self {
+            owner,
+            owner_lp_ata,
+            stake,
+            farm,
+            mint_lp,
+            lp_vault,
+            lp_token_program,
+        })
+    }
+}
+
+pub struct UnstakeInstructionData {
+    // 要取回的 LP 数量。
+    pub amount: u64,
+}
+
+impl TryFrom<&[u8]> for UnstakeInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let amount = u64::from_le_bytes(data.try_into().unwrap());
+        if amount == 0 {
+            return Err(AmmError::ZeroAmount.into());
+        }
+        Ok(Self { amount })
+    }
+}
+
+// 从农场取回 LP 已经产生的奖励保留在 rewards_owed 里 之后通过 Claim 领取
+pub struct Unstake<'a> {
+    pub accounts: UnstakeAccounts<'a>,
+    pub instruction_data: UnstakeInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for Unstake<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = UnstakeAccounts::try_from(accounts)?;
+        let instruction_data = UnstakeInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> Unstake<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let mut farm = Farm::load_mut(self.accounts.farm)?;
+        check_mint(
+            self.accounts.mint_lp,
+            farm.lp_mint(),
+            self.accounts.lp_token_program,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.owner_lp_ata,
+            self.accounts.owner,
+            farm.lp_mint(),
+            self.accounts.lp_token_program,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.lp_vault,
+            self.accounts.farm,
+            farm.lp_mint(),
+            self.accounts.lp_token_program,
+        )?;
+        farm.update(Clock::get()?.unix_timestamp)?;
+
+        let mut stake = StakePosition::load_mut(self.accounts.stake)?;
+        if stake.owner().ne(self.accounts.owner.address())
+            || stake.farm().ne(self.accounts.farm.address())
+        {
+            return Err(AmmError::InvalidFarm.into());
+        }
+        stake.settle(farm.reward_per_share())?;
+        stake.withdraw(self.instruction_data.amount)?;
+        farm.unstake(self.instruction_data.amount)?;
+        drop(stake);
+
+        // 构建farm签名
+        let config = *farm.config();
+        let bump = farm.bump();
+        drop(farm);
+        let farm_seeds = [
+            Seed::from(b"farm"),
+            Seed::from(config.as_ref()),
+            Seed::from(&bump),
+        ];
+        let farm_signer = [Signer::from(&farm_seeds)];
+
+        let mint_lp = unsafe { mint_unchecked(self.accounts.mint_lp)? };
+        TransferChecked {
+            from: self.accounts.lp_vault,
+            mint: self.accounts.mint_lp,
+            to: self.accounts.owner_lp_ata,
+            authority: self.accounts.farm,
+            amount: self.instruction_data.amount,
+            decimals: mint_lp.decimals(),
+            token_program: self.accounts.lp_token_program,
+        }
+        .invoke_signed(&farm_signer)
+    }
+}
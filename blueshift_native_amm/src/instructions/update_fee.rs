@@ -0,0 +1,71 @@
+use crate::instructions::helper::{AccountCheck, ProgramAccount, SignerAccount};
+use crate::{AmmError, Config};
+use pinocchio::{error::ProgramError, AccountView, ProgramResult};
+
+pub struct UpdateFeeAccounts<'a> {
+    // AMM 池的权限持有者。必须与 config 中记录的 authority 一致。
+    pub authority: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for UpdateFeeAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [authority, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        ProgramAccount::check(config)?;
+        Ok(Self { authority, config })
+    }
+}
+
+pub struct UpdateFeeInstructionData {
+    // 新的交换费 以基点表示（1基点=0.01%）。
+    pub fee: u16,
+}
+
+impl<'a> TryFrom<&'a [u8]> for UpdateFeeInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u16>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let fee = u16::from_le_bytes(data[0..2].try_into().unwrap());
+        // 费率不能达到 100%
+        if fee >= 10_000 {
+            return Err(AmmError::InvalidFee.into());
+        }
+        Ok(Self { fee })
+    }
+}
+
+pub struct UpdateFee<'a> {
+    pub accounts: UpdateFeeAccounts<'a>,
+    pub instruction_data: UpdateFeeInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for UpdateFee<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = UpdateFeeAccounts::try_from(accounts)?;
+        let instruction_data = UpdateFeeInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> UpdateFee<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.check_authority(self.accounts.authority.address())?;
+        config.set_fee(self.instruction_data.fee)?;
+        Ok(())
+    }
+}
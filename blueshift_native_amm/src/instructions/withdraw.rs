@@ -2,14 +2,15 @@ use crate::instructions::helper::{
     AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck, MintInterface,
     ProgramAccount, SignerAccount,
 };
-use crate::{AmmState, Config};
+use crate::instructions::token_interface::{
+    check_lp_mint, check_mint, mint_unchecked, token_account_unchecked, Burn, TokenProgram,
+    TransferChecked, TransferFee,
+};
+use crate::{AmmError, AmmEvent, Config, ConstantProduct, WithdrawEvent};
 use pinocchio::cpi::{Seed, Signer};
 use pinocchio::sysvars::clock::Clock;
 use pinocchio::sysvars::Sysvar;
 use pinocchio::{error::ProgramError, AccountView, ProgramResult};
-use pinocchio_token::instructions::{Burn, MintTo, Transfer};
-use pinocchio_token::state::{Mint, TokenAccount};
-use solana_address::Address;
 
 pub struct WithdrawAccounts<'a> {
     // 将代币提取到 AMM 流动性中的用户。
@@ -28,15 +29,23 @@ pub struct WithdrawAccounts<'a> {
     pub user_lp_ata: &'a AccountView,
     // AMM 池的配置账户。
     pub config: &'a AccountView,
-    // SPL 代币程序账户。这是执行代币操作（如转账和铸造）所需的。
+    // LP 铸币账户所属的代币程序账户。
     pub token_program: &'a AccountView,
+    // 池中代币 X 的铸币账户。Token-2022 的 TransferChecked 需要用到。
+    pub mint_x: &'a AccountView,
+    // 池中代币 Y 的铸币账户。
+    pub mint_y: &'a AccountView,
+    // 代币 X 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_x: &'a AccountView,
+    // 代币 Y 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_y: &'a AccountView,
 }
 
 impl<'a> TryFrom<&'a [AccountView]> for WithdrawAccounts<'a> {
     type Error = ProgramError;
 
     fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
-        let [user, mint_lp, vault_x, vault_y, user_x_ata, user_y_ata, user_lp_ata, config, token_program] =
+        let [user, mint_lp, vault_x, vault_y, user_x_ata, user_y_ata, user_lp_ata, config, token_program, mint_x, mint_y, token_program_x, token_program_y] =
             accounts
         else {
             return Err(ProgramError::NotEnoughAccountKeys);
@@ -44,6 +53,11 @@ impl<'a> TryFrom<&'a [AccountView]> for WithdrawAccounts<'a> {
         SignerAccount::check(user)?;
         MintInterface::check(mint_lp)?;
         ProgramAccount::check(config)?;
+        MintInterface::check(mint_x)?;
+        MintInterface::check(mint_y)?;
+        TokenProgram::check(token_program)?;
+        TokenProgram::check(token_program_x)?;
+        TokenProgram::check(token_program_y)?;
 
         Ok(Self {
             user,
@@ -55,6 +69,10 @@ impl<'a> TryFrom<&'a [AccountView]> for WithdrawAccounts<'a> {
             user_lp_ata,
             config,
             token_program,
+            mint_x,
+            mint_y,
+            token_program_x,
+            token_program_y,
         })
     }
 }
@@ -75,7 +93,7 @@ impl TryFrom<&[u8]> for WithdrawInstructionData {
 
     fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
         if data.len() != size_of::<u64>() * 4 {
-            return Err(ProgramError::InvalidArgument);
+            return Err(ProgramError::InvalidInstructionData);
         };
         let amount = u64::from_le_bytes(data[0..8].try_into().unwrap());
         let min_x = u64::from_le_bytes(data[8..16].try_into().unwrap());
@@ -83,18 +101,18 @@ impl TryFrom<&[u8]> for WithdrawInstructionData {
         let expiration = i64::from_le_bytes(data[24..32].try_into().unwrap());
 
         // Instruction Checks
-        if amount <= 0 {
-            return Err(ProgramError::InvalidInstructionData);
+        if amount == 0 {
+            return Err(AmmError::ZeroAmount.into());
         }
-        if min_x <= 0 {
-            return Err(ProgramError::InvalidInstructionData);
+        if min_x == 0 {
+            return Err(AmmError::ZeroAmount.into());
         }
-        if min_y <= 0 {
-            return Err(ProgramError::InvalidInstructionData);
+        if min_y == 0 {
+            return Err(AmmError::ZeroAmount.into());
         }
         if Clock::get()?.unix_timestamp > expiration {
             // 超时
-            return Err(ProgramError::InvalidInstructionData);
+            return Err(AmmError::Expired.into());
         }
         Ok(Self {
             amount,
@@ -127,20 +145,21 @@ impl<'a> Withdraw<'a> {
     pub fn process(&self) -> ProgramResult {
         // 校验用户的ata账户地址有没有问题
         let config = Config::load(self.accounts.config)?;
-        if config.state() != (AmmState::Initialized as u8) {
-            return Err(ProgramError::InvalidArgument);
-        }
+        config.check_withdrawable()?;
+        check_mint(self.accounts.mint_x, config.mint_x(), self.accounts.token_program_x)?;
+        check_mint(self.accounts.mint_y, config.mint_y(), self.accounts.token_program_y)?;
+        check_lp_mint(self.accounts.mint_lp, self.accounts.config, self.accounts.token_program)?;
         AssociatedTokenAccount::check(
             self.accounts.user_x_ata,
             self.accounts.user,
             config.mint_x(),
-            self.accounts.token_program,
+            self.accounts.token_program_x,
         )?;
         AssociatedTokenAccount::check(
             self.accounts.user_y_ata,
             self.accounts.user,
             config.mint_y(),
-            self.accounts.token_program,
+            self.accounts.token_program_y,
         )?;
         AssociatedTokenAccount::check(
             self.accounts.user_lp_ata,
@@ -149,55 +168,39 @@ impl<'a> Withdraw<'a> {
             self.accounts.token_program,
         )?;
 
-        // 校验 金库的地址有没有为题
-        // 其实 vault并不需要被初始化 因为这个地址是被计算出来的 只要计算出来 就可以标记里面有多少钱
-        // Check if the vault_x is valid
-        let (vault_x, _) = Address::find_program_address(
-            &[
-                self.accounts.config.address().as_ref(),
-                self.accounts.token_program.address().as_ref(),
-                config.mint_x().as_ref(),
-            ],
-            &pinocchio_associated_token_account::ID,
-        );
-        if vault_x.ne(self.accounts.vault_x.address()) {
-            return Err(ProgramError::InvalidAccountData);
-        }
-        let (vault_y, _) = Address::find_program_address(
-            &[
-                self.accounts.config.address().as_ref(),
-                self.accounts.token_program.address().as_ref(),
-                config.mint_y().as_ref(),
-            ],
-            &pinocchio_associated_token_account::ID,
-        );
-        if vault_y.ne(self.accounts.vault_y.address()) {
-            return Err(ProgramError::InvalidAccountData);
-        }
+        config.check_vaults(
+            self.accounts.vault_x.address(),
+            self.accounts.vault_y.address(),
+        )?;
 
         // 把账户从 AccountView 转化成功能账户
-        let mint_lp = unsafe { Mint::from_account_view_unchecked(self.accounts.mint_lp)? };
-        let vault_x = unsafe { TokenAccount::from_account_view_unchecked(self.accounts.vault_x)? };
-        let vault_y = unsafe { TokenAccount::from_account_view_unchecked(self.accounts.vault_y)? };
+        let mint_lp = unsafe { mint_unchecked(self.accounts.mint_lp)? };
+        let vault_x = unsafe { token_account_unchecked(self.accounts.vault_x)? };
+        let vault_y = unsafe { token_account_unchecked(self.accounts.vault_y)? };
+        let mint_x = unsafe { mint_unchecked(self.accounts.mint_x)? };
+        let mint_y = unsafe { mint_unchecked(self.accounts.mint_y)? };
+        // 金库余额扣除未领取的协议费之后才是 LP 的储备
+        let (reserve_x, reserve_y) = config.reserves(vault_x.amount(), vault_y.amount())?;
         // 计算需要从vault转给用户的代币数量
         let (x, y) = match mint_lp.supply() == self.instruction_data.amount {
-            true => (vault_x.amount(), vault_y.amount()),
+            true => (reserve_x, reserve_y),
             false => {
-                let amounts = Self::xy_withdraw_amounts_from_l(
-                    vault_x.amount(),
-                    vault_y.amount(),
+                ConstantProduct::xy_withdraw_amounts_from_l(
+                    reserve_x,
+                    reserve_y,
                     mint_lp.supply(),
                     self.instruction_data.amount,
-                    6,
-                )
-                .map_err(|_| ProgramError::InvalidArgument)?;
-                (amounts.0, amounts.1)
+                )?
             }
         };
 
         // Check for slippage
-        if !(x >= self.instruction_data.min_x && y >= self.instruction_data.min_y) {
-            return Err(ProgramError::InvalidArgument);
+        // 带转账费的 Token-2022 代币 按用户实际收到的数量检查滑点
+        let epoch = Clock::get()?.epoch;
+        let received_x = TransferFee::from_mint(self.accounts.mint_x, epoch)?.amount_after_fee(x)?;
+        let received_y = TransferFee::from_mint(self.accounts.mint_y, epoch)?.amount_after_fee(y)?;
+        if !(received_x >= self.instruction_data.min_x && received_y >= self.instruction_data.min_y) {
+            return Err(AmmError::SlippageExceeded.into());
         }
         // 1.把对应x,y代币转移到用户的ata账户
         // 构建config签名
@@ -214,19 +217,25 @@ impl<'a> Withdraw<'a> {
         ];
 
         let config_signer = [Signer::from(&config_seeds)];
-        Transfer {
+        TransferChecked {
             from: self.accounts.vault_x,
+            mint: self.accounts.mint_x,
             to: self.accounts.user_x_ata,
             authority: self.accounts.config,
             amount: x,
+            decimals: mint_x.decimals(),
+            token_program: self.accounts.token_program_x,
         }
         .invoke_signed(&config_signer)?;
 
-        Transfer {
+        TransferChecked {
             from: self.accounts.vault_y,
+            mint: self.accounts.mint_y,
             to: self.accounts.user_y_ata,
             authority: self.accounts.config,
             amount: y,
+            decimals: mint_y.decimals(),
+            token_program: self.accounts.token_program_y,
         }
         .invoke_signed(&config_signer)?;
 
@@ -237,54 +246,25 @@ impl<'a> Withdraw<'a> {
             account: self.accounts.user_lp_ata,
             amount: self.instruction_data.amount,
             authority: self.accounts.user,
+            token_program: self.accounts.token_program,
         }
         .invoke()?;
-        Ok(())
-    }
 
-    // Get amount of X and Y to withdraw from liquidity token amount
-    // 这个是根据git上直接搬过来的 错误类型改了一下
-    // x: 库存的x代币数量
-    // y: 库存的y代币数量
-    // l: 流动性代币lp的流通量
-    // a: 用户希望销毁的 LP 代币数量
-    // precision: 精度
-    fn xy_withdraw_amounts_from_l(
-        x: u64,
-        y: u64,
-        l: u64,
-        a: u64,
-        precision: u32,
-    ) -> Result<(u64, u64), ProgramError> {
-        // ((lp代币总量 - 用户希望销毁的lp代币数量) * 精度) / 流通代币总量
-        // 上面的约等于是 用户希望销毁的代币数量后 总的流通代币占流通总量的占比 * 精度
-        // 简单来说就是总流动性的剩余比例
-        let ratio = ((l - a) as u128)
-            .checked_mul(precision as u128)
-            .ok_or(ProgramError::InvalidArgument)?
-            .checked_div(l as u128)
-            .ok_or(ProgramError::InvalidArgument)?;
-        // 库存代币x的总量 - (库存x代币总量 * 总流动性的剩余比例 / 精度)
-        let withdraw_x = (x as u128)
-            .checked_sub(
-                (x as u128)
-                    .checked_mul(ratio)
-                    .ok_or(ProgramError::InvalidArgument)?
-                    .checked_div(precision as u128)
-                    .ok_or(ProgramError::InvalidArgument)?,
-            )
-            .ok_or(ProgramError::InvalidArgument)? as u64;
-        // 库存代币y的总量 - (库存y代币总量 * 总流动性的剩余比例 / 精度)
-        let withdraw_y = (y as u128)
-            .checked_sub(
-                (y as u128)
-                    .checked_mul(ratio)
-                    .ok_or(ProgramError::InvalidArgument)?
-                    .checked_div(precision as u128)
-                    .ok_or(ProgramError::InvalidArgument)?,
-            )
-            .ok_or(ProgramError::InvalidArgument)? as u64;
-        // 总结下来就是根据用户希望销毁的代币占总流通量的比例 直接从库存的x,y代币中直接按比例提取。
-        Ok((withdraw_x, withdraw_y))
+        // 使用提取之前的储备更新价格累加器
+        drop(config);
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.update_price_cumulative(reserve_x, reserve_y, Clock::get()?.unix_timestamp)?;
+
+        AmmEvent::Withdraw(WithdrawEvent {
+            pool: *self.accounts.config.address(),
+            user: *self.accounts.user.address(),
+            amount_x: x,
+            amount_y: y,
+            lp_burned: self.instruction_data.amount,
+            reserve_x: reserve_x - x,
+            reserve_y: reserve_y - y,
+        })
+        .emit();
+        Ok(())
     }
 }
@@ -0,0 +1,325 @@
+use crate::instructions::helper::{
+    AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck, MintInterface,
+    ProgramAccount, SignerAccount,
+};
+use crate::instructions::token_interface::{
+    check_lp_mint, check_mint, mint_unchecked, token_account_unchecked, MintTo, TokenProgram,
+    TransferChecked, TransferFee,
+};
+use crate::{AmmError, AmmEvent, Config, ConstantProduct, DepositEvent, LiquidityPair, SwapEvent};
+use pinocchio::cpi::{Seed, Signer};
+use pinocchio::{
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+
+pub struct ZapInAccounts<'a> {
+    // 只用一种代币注入流动性的用户。
+    pub user: &'a AccountView,
+    // 代表池流动性的铸币账户。
+    pub mint_lp: &'a AccountView,
+    // 存储所有存入池中的 X 代币的代币账户。
+    pub vault_x: &'a AccountView,
+    // 存储所有存入池中的 Y 代币的代币账户。
+    pub vault_y: &'a AccountView,
+    // 用户支付代币的关联账户。is_x 为 true 时是 X 代币账户 否则是 Y 代币账户。
+    pub user_src_ata: &'a AccountView,
+    // 用户的 LP 代币关联账户。这是铸造 LP 代币的目标账户。
+    pub user_lp_ata: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+    // LP 铸币账户所属的代币程序账户。
+    pub token_program: &'a AccountView,
+    // 池中代币 X 的铸币账户。
+    pub mint_x: &'a AccountView,
+    // 池中代币 Y 的铸币账户。
+    pub mint_y: &'a AccountView,
+    // 代币 X 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_x: &'a AccountView,
+    // 代币 Y 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_y: &'a AccountView,
+    // 白名单池子额外传入用户的白名单账户 其他池子不需要。
+    pub remaining: &'a [AccountView],
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for ZapInAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [user, mint_lp, vault_x, vault_y, user_src_ata, user_lp_ata, config, token_program, mint_x, mint_y, token_program_x, token_program_y, remaining @ ..] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(user)?;
+        ProgramAccount::check(config)?;
+        MintInterface::check(mint_lp)?;
+        MintInterface::check(mint_x)?;
+        MintInterface::check(mint_y)?;
+        TokenProgram::check(token_program)?;
+        TokenProgram::check(token_program_x)?;
+        TokenProgram::check(token_program_y)?;
+        Ok(Self {
+            user,
+            mint_lp,
+            vault_x,
+            vault_y,
+            user_src_ata,
+            user_lp_ata,
+            config,
+            token_program,
+            mint_x,
+            mint_y,
+            token_program_x,
+            token_program_y,
+            remaining,
+        })
+    }
+}
+
+pub struct ZapInInstructionData {
+    // 用户支付的是代币 X 还是代币 Y。
+    pub is_x: bool,
+    // 用户支付的代币数量。
+    pub amount: u64,
+    // 用户愿意接收的最少 LP 代币数量。
+    pub min_lp: u64,
+    // 此订单的过期时间。确保交易必须在一定时间内完成非常重要。
+    pub expiration: i64,
+}
+
+impl TryFrom<&[u8]> for ZapInInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<bool>() + size_of::<u64>() * 3 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let is_x = data[0] != 0;
+        let amount = u64::from_le_bytes(data[1..9].try_into().unwrap());
+        let min_lp = u64::from_le_bytes(data[9..17].try_into().unwrap());
+        let expiration = i64::from_le_bytes(data[17..25].try_into().unwrap());
+
+        // Instruction Checks
+        if amount == 0 {
+            return Err(AmmError::ZeroAmount.into());
+        }
+        if min_lp == 0 {
+            return Err(AmmError::ZeroAmount.into());
+        }
+        if Clock::get()?.unix_timestamp > expiration {
+            // 超时
+            return Err(AmmError::Expired.into());
+        }
+        Ok(Self {
+            is_x,
+            amount,
+            min_lp,
+            expiration,
+        })
+    }
+}
+
+// 单边注入流动性 用户只支付一种代币
+// 先在池子里把一部分换成另一种代币 再把两种代币按比例注入 换出的代币本来就在金库里 所以只需要一次转账
+pub struct ZapIn<'a> {
+    pub accounts: ZapInAccounts<'a>,
+    pub instruction_data: ZapInInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for ZapIn<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = ZapInAccounts::try_from(accounts)?;
+        let instruction_data = ZapInInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> ZapIn<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+        config.check_tradable()?;
+        // 白名单池子只允许白名单里的用户注入流动性
+        config.check_allow_listed(
+            self.accounts.config.address(),
+            self.accounts.user.address(),
+            self.accounts.remaining,
+        )?;
+        check_mint(
+            self.accounts.mint_x,
+            config.mint_x(),
+            self.accounts.token_program_x,
+        )?;
+        check_mint(
+            self.accounts.mint_y,
+            config.mint_y(),
+            self.accounts.token_program_y,
+        )?;
+        check_lp_mint(
+            self.accounts.mint_lp,
+            self.accounts.config,
+            self.accounts.token_program,
+        )?;
+        // 按支付方向选出支付一侧的账户
+        let (p, mint_in, vault_in, token_program_in) = match self.instruction_data.is_x {
+            true => (
+                LiquidityPair::X,
+                self.accounts.mint_x,
+                self.accounts.vault_x,
+                self.accounts.token_program_x,
+            ),
+            false => (
+                LiquidityPair::Y,
+                self.accounts.mint_y,
+                self.accounts.vault_y,
+                self.accounts.token_program_y,
+            ),
+        };
+        AssociatedTokenAccount::check(
+            self.accounts.user_src_ata,
+            self.accounts.user,
+            mint_in.address(),
+            token_program_in,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.user_lp_ata,
+            self.accounts.user,
+            self.accounts.mint_lp.address(),
+            self.accounts.token_program,
+        )?;
+
+        config.check_vaults(
+            self.accounts.vault_x.address(),
+            self.accounts.vault_y.address(),
+        )?;
+
+        let mint_lp = unsafe { mint_unchecked(self.accounts.mint_lp)? };
+        let vault_x = unsafe { token_account_unchecked(self.accounts.vault_x)? };
+        let vault_y = unsafe { token_account_unchecked(self.accounts.vault_y)? };
+        let (reserve_x, reserve_y) = config.reserves(vault_x.amount(), vault_y.amount())?;
+
+        // Token-2022 转账费 金库实际收到的数量才参与计算
+        let epoch = Clock::get()?.epoch;
+        let received = TransferFee::from_mint(mint_in, epoch)?
+            .amount_after_fee(self.instruction_data.amount)?;
+
+        // 先把其中 s 个换成另一侧的代币 这部分交换和 Swap 一样收取手续费
+        let mut curve = config.curve(reserve_x, reserve_y, Clock::get()?.unix_timestamp)?;
+        let s = curve.zap_swap_amount(p, received)?;
+        let (_, fee, out) = curve.swap(p, s, 0)?;
+        let (_, protocol_fee) = ConstantProduct::split_fee(fee, config.protocol_fee())?;
+
+        // 再把剩下的 received - s 和换到的 out 按交换之后的储备注入
+        // 协议费不属于 LP 计算份额时要从储备中扣掉
+        let lp = match p {
+            LiquidityPair::X => ConstantProduct::l_from_xy(
+                curve.x() - protocol_fee,
+                curve.y(),
+                mint_lp.supply(),
+                received - s,
+                out,
+            )?,
+            LiquidityPair::Y => ConstantProduct::l_from_xy(
+                curve.x(),
+                curve.y() - protocol_fee,
+                mint_lp.supply(),
+                out,
+                received - s,
+            )?,
+        };
+        if lp == 0 {
+            return Err(AmmError::ZeroAmount.into());
+        }
+        // Check for slippage
+        if lp < self.instruction_data.min_lp {
+            return Err(AmmError::SlippageExceeded.into());
+        }
+
+        // 用户支付的代币全部转入金库
+        let mint_in_state = unsafe { mint_unchecked(mint_in)? };
+        TransferChecked {
+            from: self.accounts.user_src_ata,
+            mint: mint_in,
+            to: vault_in,
+            authority: self.accounts.user,
+            amount: self.instruction_data.amount,
+            decimals: mint_in_state.decimals(),
+            token_program: token_program_in,
+        }
+        .invoke()?;
+
+        // 构建config签名
+        let seed_bytes = config.seed().to_le_bytes();
+        let mut seed_array = [0u8; 8];
+        seed_array.copy_from_slice(&seed_bytes);
+        let bump = config.config_bump();
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(&seed_array),
+            Seed::from(config.mint_x().as_ref()),
+            Seed::from(config.mint_y().as_ref()),
+            Seed::from(&bump),
+        ];
+
+        let config_signer = [Signer::from(&config_seeds)];
+        MintTo {
+            mint: self.accounts.mint_lp,
+            account: self.accounts.user_lp_ata,
+            mint_authority: self.accounts.config,
+            amount: lp,
+            token_program: self.accounts.token_program,
+        }
+        .invoke_signed(&config_signer)?;
+
+        // 记录协议费和价格累加器
+        drop(config);
+        let now = Clock::get()?.unix_timestamp;
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.update_price_cumulative(reserve_x, reserve_y, now)?;
+        match p {
+            LiquidityPair::X => config.add_protocol_fees_x(protocol_fee)?,
+            LiquidityPair::Y => config.add_protocol_fees_y(protocol_fee)?,
+        }
+        // 交换之后 LP 的储备 协议费不属于 LP
+        let (swapped_x, swapped_y) = match p {
+            LiquidityPair::X => (curve.x() - protocol_fee, curve.y()),
+            LiquidityPair::Y => (curve.x(), curve.y() - protocol_fee),
+        };
+        // 按比例注入不改变价格 用交换之后的储备更新波动率
+        config.update_volatility(swapped_x, swapped_y, now)?;
+
+        // 拆成一次交换和一次注入报告 换到的 out 没有离开金库 又作为注入的一侧回到储备
+        let (amount_x, amount_y) = match p {
+            LiquidityPair::X => (received - s, out),
+            LiquidityPair::Y => (out, received - s),
+        };
+        AmmEvent::Swap(SwapEvent {
+            pool: *self.accounts.config.address(),
+            user: *self.accounts.user.address(),
+            is_x: self.instruction_data.is_x,
+            amount_in: s,
+            amount_out: out,
+            fee,
+            reserve_x: swapped_x,
+            reserve_y: swapped_y,
+        })
+        .emit();
+        AmmEvent::Deposit(DepositEvent {
+            pool: *self.accounts.config.address(),
+            user: *self.accounts.user.address(),
+            amount_x,
+            amount_y,
+            lp_minted: lp,
+            reserve_x: swapped_x + amount_x,
+            reserve_y: swapped_y + amount_y,
+        })
+        .emit();
+        Ok(())
+    }
+}
@@ -0,0 +1,300 @@
+use crate::instructions::helper::{
+    AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck, MintInterface,
+    ProgramAccount, SignerAccount,
+};
+use crate::instructions::token_interface::{
+    check_lp_mint, check_mint, mint_unchecked, token_account_unchecked, Burn, TokenProgram,
+    TransferChecked, TransferFee,
+};
+use crate::{AmmError, AmmEvent, Config, ConstantProduct, LiquidityPair, SwapEvent, WithdrawEvent};
+use pinocchio::cpi::{Seed, Signer};
+use pinocchio::{
+    error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    AccountView, ProgramResult,
+};
+
+pub struct ZapOutAccounts<'a> {
+    // 销毁 LP 代币并只取回一种代币的用户。
+    pub user: &'a AccountView,
+    // 代表池流动性的铸币账户。
+    pub mint_lp: &'a AccountView,
+    // 存储所有存入池中的 X 代币的代币账户。
+    pub vault_x: &'a AccountView,
+    // 存储所有存入池中的 Y 代币的代币账户。
+    pub vault_y: &'a AccountView,
+    // 用户接收代币的关联账户。is_x 为 true 时是 X 代币账户 否则是 Y 代币账户。
+    pub user_dst_ata: &'a AccountView,
+    // 用户的 LP 代币关联账户。这是 LP 代币将被销毁的来源账户。
+    pub user_lp_ata: &'a AccountView,
+    // AMM 池的配置账户。
+    pub config: &'a AccountView,
+    // LP 铸币账户所属的代币程序账户。
+    pub token_program: &'a AccountView,
+    // 池中代币 X 的铸币账户。
+    pub mint_x: &'a AccountView,
+    // 池中代币 Y 的铸币账户。
+    pub mint_y: &'a AccountView,
+    // 代币 X 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_x: &'a AccountView,
+    // 代币 Y 所属的代币程序账户 SPL Token 或 Token-2022。
+    pub token_program_y: &'a AccountView,
+}
+
+impl<'a> TryFrom<&'a [AccountView]> for ZapOutAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountView]) -> Result<Self, Self::Error> {
+        let [user, mint_lp, vault_x, vault_y, user_dst_ata, user_lp_ata, config, token_program, mint_x, mint_y, token_program_x, token_program_y] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(user)?;
+        ProgramAccount::check(config)?;
+        MintInterface::check(mint_lp)?;
+        MintInterface::check(mint_x)?;
+        MintInterface::check(mint_y)?;
+        TokenProgram::check(token_program)?;
+        TokenProgram::check(token_program_x)?;
+        TokenProgram::check(token_program_y)?;
+        Ok(Self {
+            user,
+            mint_lp,
+            vault_x,
+            vault_y,
+            user_dst_ata,
+            user_lp_ata,
+            config,
+            token_program,
+            mint_x,
+            mint_y,
+            token_program_x,
+            token_program_y,
+        })
+    }
+}
+
+pub struct ZapOutInstructionData {
+    // 用户希望取回的是代币 X 还是代币 Y。
+    pub is_x: bool,
+    // 用户希望销毁的 LP 代币数量。
+    pub amount: u64,
+    // 用户愿意收到的最少代币数量。
+    pub min_out: u64,
+    // 此订单的过期时间。确保交易必须在一定时间内完成非常重要。
+    pub expiration: i64,
+}
+
+impl TryFrom<&[u8]> for ZapOutInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<bool>() + size_of::<u64>() * 3 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let is_x = data[0] != 0;
+        let amount = u64::from_le_bytes(data[1..9].try_into().unwrap());
+        let min_out = u64::from_le_bytes(data[9..17].try_into().unwrap());
+        let expiration = i64::from_le_bytes(data[17..25].try_into().unwrap());
+
+        // Instruction Checks
+        if amount == 0 {
+            return Err(AmmError::ZeroAmount.into());
+        }
+        if min_out == 0 {
+            return Err(AmmError::ZeroAmount.into());
+        }
+        if Clock::get()?.unix_timestamp > expiration {
+            // 超时
+            return Err(AmmError::Expired.into());
+        }
+        Ok(Self {
+            is_x,
+            amount,
+            min_out,
+            expiration,
+        })
+    }
+}
+
+// 单边提取流动性 用户只取回一种代币
+// 先按比例取出两种代币 再把不需要的一侧在池子里换成需要的一侧 不需要的代币本来就在金库里 所以只需要一次转账
+pub struct ZapOut<'a> {
+    pub accounts: ZapOutAccounts<'a>,
+    pub instruction_data: ZapOutInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountView])> for ZapOut<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountView])) -> Result<Self, Self::Error> {
+        let accounts = ZapOutAccounts::try_from(accounts)?;
+        let instruction_data = ZapOutInstructionData::try_from(data)?;
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> ZapOut<'a> {
+    pub fn process(&self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+        // 单边提取包含一次交换 所以和 Swap 一样要求池子可以交易
+        config.check_tradable()?;
+        check_mint(
+            self.accounts.mint_x,
+            config.mint_x(),
+            self.accounts.token_program_x,
+        )?;
+        check_mint(
+            self.accounts.mint_y,
+            config.mint_y(),
+            self.accounts.token_program_y,
+        )?;
+        check_lp_mint(
+            self.accounts.mint_lp,
+            self.accounts.config,
+            self.accounts.token_program,
+        )?;
+        // 按提取方向选出接收一侧的账户
+        let (mint_out, vault_out, token_program_out) = match self.instruction_data.is_x {
+            true => (
+                self.accounts.mint_x,
+                self.accounts.vault_x,
+                self.accounts.token_program_x,
+            ),
+            false => (
+                self.accounts.mint_y,
+                self.accounts.vault_y,
+                self.accounts.token_program_y,
+            ),
+        };
+        AssociatedTokenAccount::check(
+            self.accounts.user_dst_ata,
+            self.accounts.user,
+            mint_out.address(),
+            token_program_out,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.user_lp_ata,
+            self.accounts.user,
+            self.accounts.mint_lp.address(),
+            self.accounts.token_program,
+        )?;
+
+        config.check_vaults(
+            self.accounts.vault_x.address(),
+            self.accounts.vault_y.address(),
+        )?;
+
+        let mint_lp = unsafe { mint_unchecked(self.accounts.mint_lp)? };
+        let vault_x = unsafe { token_account_unchecked(self.accounts.vault_x)? };
+        let vault_y = unsafe { token_account_unchecked(self.accounts.vault_y)? };
+        let (reserve_x, reserve_y) = config.reserves(vault_x.amount(), vault_y.amount())?;
+
+        // 先按比例取出两种代币
+        let (x, y) = ConstantProduct::xy_withdraw_amounts_from_l(
+            reserve_x,
+            reserve_y,
+            mint_lp.supply(),
+            self.instruction_data.amount,
+        )?;
+        // 再把不需要的一侧按取出之后的储备换成需要的一侧 这部分交换和 Swap 一样收取手续费
+        let mut curve = config.curve(reserve_x - x, reserve_y - y, Clock::get()?.unix_timestamp)?;
+        let (swap_in, (_, fee, out)) = match self.instruction_data.is_x {
+            true => (y, curve.swap(LiquidityPair::Y, y, 0)?),
+            false => (x, curve.swap(LiquidityPair::X, x, 0)?),
+        };
+        let (_, protocol_fee) = ConstantProduct::split_fee(fee, config.protocol_fee())?;
+        let total = match self.instruction_data.is_x {
+            true => x + out,
+            false => y + out,
+        };
+        // Check for slippage
+        // 带转账费的 Token-2022 代币 按用户实际收到的数量检查滑点
+        let epoch = Clock::get()?.epoch;
+        let received = TransferFee::from_mint(mint_out, epoch)?.amount_after_fee(total)?;
+        if received < self.instruction_data.min_out {
+            return Err(AmmError::SlippageExceeded.into());
+        }
+
+        // 销毁用户的 LP 代币
+        Burn {
+            mint: self.accounts.mint_lp,
+            account: self.accounts.user_lp_ata,
+            amount: self.instruction_data.amount,
+            authority: self.accounts.user,
+            token_program: self.accounts.token_program,
+        }
+        .invoke()?;
+
+        // 构建config签名
+        let seed_bytes = config.seed().to_le_bytes();
+        let mut seed_array = [0u8; 8];
+        seed_array.copy_from_slice(&seed_bytes);
+        let bump = config.config_bump();
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(&seed_array),
+            Seed::from(config.mint_x().as_ref()),
+            Seed::from(config.mint_y().as_ref()),
+            Seed::from(&bump),
+        ];
+
+        let config_signer = [Signer::from(&config_seeds)];
+        let mint_out_state = unsafe { mint_unchecked(mint_out)? };
+        TransferChecked {
+            from: vault_out,
+            mint: mint_out,
+            to: self.accounts.user_dst_ata,
+            authority: self.accounts.config,
+            amount: total,
+            decimals: mint_out_state.decimals(),
+            token_program: token_program_out,
+        }
+        .invoke_signed(&config_signer)?;
+
+        // 记录协议费和价格累加器 协议费记在交换时支付的一侧
+        drop(config);
+        let now = Clock::get()?.unix_timestamp;
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.update_price_cumulative(reserve_x, reserve_y, now)?;
+        match self.instruction_data.is_x {
+            true => config.add_protocol_fees_y(protocol_fee)?,
+            false => config.add_protocol_fees_x(protocol_fee)?,
+        }
+        // 交换之后 LP 的储备 协议费不属于 LP
+        let (swapped_x, swapped_y) = match self.instruction_data.is_x {
+            true => (curve.x(), curve.y() - protocol_fee),
+            false => (curve.x() - protocol_fee, curve.y()),
+        };
+        // 用交换之后的储备更新波动率
+        config.update_volatility(swapped_x, swapped_y, now)?;
+
+        // 拆成一次提取和一次交换报告 不需要的一侧没有离开金库 又作为交换的输入回到储备
+        AmmEvent::Withdraw(WithdrawEvent {
+            pool: *self.accounts.config.address(),
+            user: *self.accounts.user.address(),
+            amount_x: x,
+            amount_y: y,
+            lp_burned: self.instruction_data.amount,
+            reserve_x: reserve_x - x,
+            reserve_y: reserve_y - y,
+        })
+        .emit();
+        AmmEvent::Swap(SwapEvent {
+            pool: *self.accounts.config.address(),
+            user: *self.accounts.user.address(),
+            is_x: !self.instruction_data.is_x,
+            amount_in: swap_in,
+            amount_out: out,
+            fee,
+            reserve_x: swapped_x,
+            reserve_y: swapped_y,
+        })
+        .emit();
+        Ok(())
+    }
+}
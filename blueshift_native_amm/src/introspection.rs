@@ -0,0 +1,118 @@
+use pinocchio::{error::ProgramError, Address};
+
+// 指令 sysvar 的只读解析 用来在链上检查同一笔交易里的其他指令
+// 账户数据的布局：
+// - u16 指令数量
+// - 每条指令一个 u16 偏移
+// - 每条指令：u16 账户数量 | 每个账户 1 字节标志 + 32 字节地址 | 32 字节程序地址 | u16 数据长度 | 数据
+// - 最后 2 字节：当前正在执行的顶层指令的下标
+// 所有整数都是小端
+
+// Sysvar1nstructions1111111111111111111111111
+pub const INSTRUCTIONS_SYSVAR_ID: Address = Address::new_from_array([
+    0x06, 0xa7, 0xd5, 0x17, 0x18, 0x7b, 0xd1, 0x66, 0x35, 0xda, 0xd4, 0x04, 0x55, 0xfd, 0xc2, 0xc0,
+    0xc1, 0x24, 0xc6, 0x8f, 0x21, 0x56, 0x75, 0xa5, 0xdb, 0xba, 0xcb, 0x5f, 0x08, 0x00, 0x00, 0x00,
+]);
+
+// 每个账户占用的字节数：1 字节标志 + 32 字节地址
+const ACCOUNT_META_LEN: usize = 1 + 32;
+
+pub struct Instructions<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Instructions<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, ProgramError> {
+        // 至少要有指令数量和当前下标
+        if data.len() < 4 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self { data })
+    }
+
+    // 交易中顶层指令的数量
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        read_u16(self.data, 0).unwrap_or(0) as usize
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // 当前正在执行的顶层指令的下标 通过 CPI 调用时也是外层那条顶层指令的下标
+    #[inline(always)]
+    pub fn current_index(&self) -> Result<usize, ProgramError> {
+        Ok(read_u16(self.data, self.data.len() - 2)? as usize)
+    }
+
+    pub fn get(&self, index: usize) -> Result<IntrospectedInstruction<'a>, ProgramError> {
+        if index >= self.len() {
+            return Err(ProgramError::InvalidArgument);
+        }
+        // 指令不能越过最后 2 字节的当前下标
+        let body = &self.data[..self.data.len() - 2];
+        let offset = read_u16(body, 2 + index * 2)? as usize;
+        let num_accounts = read_u16(body, offset)? as usize;
+        let accounts_start = offset + 2;
+        let program_id_start = accounts_start + num_accounts * ACCOUNT_META_LEN;
+        let data_len = read_u16(body, program_id_start + 32)? as usize;
+        let data_start = program_id_start + 32 + 2;
+        let instruction = body
+            .get(offset..data_start + data_len)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        Ok(IntrospectedInstruction {
+            data: instruction,
+            num_accounts,
+        })
+    }
+}
+
+pub struct IntrospectedInstruction<'a> {
+    // 从账户数量开始 到指令数据结束
+    data: &'a [u8],
+    num_accounts: usize,
+}
+
+impl<'a> IntrospectedInstruction<'a> {
+    #[inline(always)]
+    pub fn num_accounts(&self) -> usize {
+        self.num_accounts
+    }
+
+    // 第 index 个账户的地址
+    pub fn account(&self, index: usize) -> Result<&'a Address, ProgramError> {
+        if index >= self.num_accounts {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        let start = 2 + index * ACCOUNT_META_LEN + 1;
+        Ok(address_at(self.data, start))
+    }
+
+    #[inline(always)]
+    pub fn program_id(&self) -> &'a Address {
+        address_at(self.data, 2 + self.num_accounts * ACCOUNT_META_LEN)
+    }
+
+    #[inline(always)]
+    pub fn data(&self) -> &'a [u8] {
+        &self.data[2 + self.num_accounts * ACCOUNT_META_LEN + 32 + 2..]
+    }
+}
+
+#[inline(always)]
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, ProgramError> {
+    let bytes = data
+        .get(offset..offset + 2)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+// 调用之前已经检查过长度
+#[inline(always)]
+fn address_at(data: &[u8], offset: usize) -> &Address {
+    let bytes: &[u8; 32] = data[offset..offset + 32].try_into().unwrap();
+    // Address 是 [u8; 32] 的 repr(transparent) 包装
+    unsafe { &*(bytes as *const [u8; 32] as *const Address) }
+}
@@ -3,9 +3,27 @@ use solana_address::declare_id;
 
 entrypoint!(process_instruction);
 
+pub mod concentrated;
+pub use concentrated::*;
+
+pub mod curve;
+pub use curve::*;
+
+pub mod errors;
+pub use errors::*;
+
+pub mod events;
+pub use events::*;
+
 pub mod instructions;
 pub use instructions::*;
 
+pub mod introspection;
+pub use introspection::*;
+
+pub mod stable_swap;
+pub use stable_swap::*;
+
 pub mod state;
 pub use state::*;
 
@@ -22,6 +40,42 @@ fn process_instruction(
         1 => Deposit::try_from((data, accounts))?.process(),
         2 => Withdraw::try_from((data, accounts))?.process(),
         3 => Swap::try_from((data, accounts))?.process(),
+        4 => UpdateFee::try_from((data, accounts))?.process(),
+        5 => SetState::try_from((data, accounts))?.process(),
+        6 => TransferAuthority::try_from((data, accounts))?.process(),
+        7 => AcceptAuthority::try_from(accounts)?.process(),
+        8 => SetProtocolFee::try_from((data, accounts))?.process(),
+        9 => CollectProtocolFees::try_from(accounts)?.process(),
+        10 => Quote::try_from((data, accounts))?.process(),
+        11 => InitializeOracle::try_from(accounts)?.process(),
+        12 => RecordObservation::try_from(accounts)?.process(),
+        13 => RouteSwap::try_from((data, accounts))?.process(),
+        14 => ZapIn::try_from((data, accounts))?.process(),
+        15 => ZapOut::try_from((data, accounts))?.process(),
+        16 => RampAmp::try_from((data, accounts))?.process(),
+        17 => SetDynamicFee::try_from((data, accounts))?.process(),
+        18 => FlashBorrow::try_from((data, accounts))?.process(),
+        19 => FlashRepay::try_from((data, accounts))?.process(),
+        20 => InitializeFactory::try_from((data, accounts))?.process(),
+        21 => SetFactory::try_from((data, accounts))?.process(),
+        22 => PlaceOrder::try_from((data, accounts))?.process(),
+        23 => CancelOrder::try_from(accounts)?.process(),
+        24 => ExecuteOrder::try_from(accounts)?.process(),
+        25 => InitializeConcentrated::try_from((data, accounts))?.process(),
+        26 => OpenPosition::try_from((data, accounts))?.process(),
+        27 => IncreaseLiquidity::try_from((data, accounts))?.process(),
+        28 => DecreaseLiquidity::try_from((data, accounts))?.process(),
+        29 => InitializeFarm::try_from((data, accounts))?.process(),
+        30 => SetEmissionRate::try_from((data, accounts))?.process(),
+        31 => FundRewards::try_from((data, accounts))?.process(),
+        32 => Stake::try_from((data, accounts))?.process(),
+        33 => Unstake::try_from((data, accounts))?.process(),
+        34 => Claim::try_from(accounts)?.process(),
+        35 => MigrateConfig::try_from(accounts)?.process(),
+        36 => SetLpFrozen::try_from((data, accounts))?.process(),
+        37 => SetPermissioned::try_from((data, accounts))?.process(),
+        38 => AddToAllowList::try_from((data, accounts))?.process(),
+        39 => RemoveFromAllowList::try_from(accounts)?.process(),
         _ => Err(ProgramError::InvalidInstructionData),
     }
 }
@@ -0,0 +1,273 @@
+use crate::{mul_div_ceil, mul_div_floor, AmmError, LiquidityPair, BPS_DENOMINATOR};
+use pinocchio::error::ProgramError;
+
+// StableSwap 曲线 (Curve 的两币种版本)
+// 不变量：A * n^n * (x + y) + D = A * n^n * D + D^(n+1) / (n^n * x * y)  其中 n = 2
+// A 越大 价格在 x == y 附近越接近 1:1 适合稳定币这类价格挂钩的交易对
+// 和 ConstantProduct 一样 所有除法都让池子占便宜：用户得到的向下取整 需要支付的向上取整
+// D^3 这类中间结果会超出 u128 统一通过 mul_div 在 256 位中计算
+
+// 放大系数的范围
+pub const MIN_AMP: u64 = 1;
+pub const MAX_AMP: u64 = 1_000_000;
+// 调整放大系数至少要经过的时间 防止瞬间改变价格曲线
+pub const MIN_RAMP_DURATION: i64 = 86_400;
+// 一次调整放大系数最多变化的倍数
+pub const MAX_AMP_CHANGE: u64 = 10;
+// 牛顿迭代的最大次数
+const MAX_ITERATIONS: usize = 255;
+// 币种数量
+const N_COINS: u128 = 2;
+
+#[derive(Clone, Copy, Debug)]
+pub struct StableSwap {
+    x: u64,   // Balance of Token X
+    y: u64,   // Balance of Token Y
+    amp: u64, // Amplification coefficient
+    fee: u16, // Fee in basis points, ie: 100 = 1%
+}
+
+impl StableSwap {
+    pub fn init(x: u64, y: u64, amp: u64, fee: u16) -> Result<StableSwap, ProgramError> {
+        if x == 0 || y == 0 {
+            return Err(AmmError::InsufficientLiquidity.into());
+        }
+        if !(MIN_AMP..=MAX_AMP).contains(&amp) {
+            return Err(AmmError::InvalidAmp.into());
+        }
+        if fee as u64 >= BPS_DENOMINATOR {
+            return Err(AmmError::InvalidFee.into());
+        }
+        Ok(StableSwap { x, y, amp, fee })
+    }
+
+    #[inline(always)]
+    pub fn x(&self) -> u64 {
+        self.x
+    }
+    #[inline(always)]
+    pub fn y(&self) -> u64 {
+        self.y
+    }
+    #[inline(always)]
+    pub fn amp(&self) -> u64 {
+        self.amp
+    }
+
+    // 当前储备的不变量 D
+    pub fn d(&self) -> Result<u128, ProgramError> {
+        Self::compute_d(self.amp, self.x, self.y)
+    }
+
+    // 按交换方向返回 (支付一侧的储备, 获取一侧的储备)
+    #[inline(always)]
+    fn reserves(&self, p: LiquidityPair) -> (u64, u64) {
+        match p {
+            LiquidityPair::X => (self.x, self.y),
+            LiquidityPair::Y => (self.y, self.x),
+        }
+    }
+
+    #[inline(always)]
+    fn set_reserves(&mut self, p: LiquidityPair, reserve_in: u64, reserve_out: u64) {
+        match p {
+            LiquidityPair::X => {
+                self.x = reserve_in;
+                self.y = reserve_out;
+            }
+            LiquidityPair::Y => {
+                self.x = reserve_out;
+                self.y = reserve_in;
+            }
+        }
+    }
+
+    // 和 ConstantProduct::swap 的参数和返回值一致
+    // 返回 (支付的数量, 手续费, 得到的数量)
+    pub fn swap(
+        &mut self,
+        p: LiquidityPair,
+        a: u64,
+        min: u64,
+    ) -> Result<(u64, u64, u64), ProgramError> {
+        let (reserve_in, reserve_out) = self.reserves(p);
+        // 扣掉手续费之后实际参与定价的数量 向下取整 相当于手续费向上取整
+        let a2 = mul_div_floor(a, BPS_DENOMINATOR - self.fee as u64, BPS_DENOMINATOR)?;
+        let out = self.delta_out(reserve_in, reserve_out, a2)?;
+        if out < min {
+            return Err(AmmError::SlippageExceeded.into());
+        }
+        let fee = a - a2;
+        let new_in = reserve_in.checked_add(a).ok_or(AmmError::CurveOverflow)?;
+        self.set_reserves(p, new_in, reserve_out - out);
+
+        Ok((a, fee, out))
+    }
+
+    // 和 ConstantProduct::swap_exact_out 的参数和返回值一致
+    pub fn swap_exact_out(
+        &mut self,
+        p: LiquidityPair,
+        out: u64,
+        max_in: u64,
+    ) -> Result<(u64, u64, u64), ProgramError> {
+        let (reserve_in, reserve_out) = self.reserves(p);
+        let a2 = self.delta_in(reserve_in, reserve_out, out)?;
+        // 加上手续费之后用户需要支付的数量 向上取整
+        let a = mul_div_ceil(a2, BPS_DENOMINATOR, BPS_DENOMINATOR - self.fee as u64)?;
+        if a > max_in {
+            return Err(AmmError::SlippageExceeded.into());
+        }
+        let fee = a - a2;
+        let new_in = reserve_in.checked_add(a).ok_or(AmmError::CurveOverflow)?;
+        self.set_reserves(p, new_in, reserve_out - out);
+
+        Ok((a, fee, out))
+    }
+
+    // 支付 a (已扣除手续费) 能得到的数量 新的储备向上取整再多留 1 保证 D 不会变小
+    fn delta_out(&self, reserve_in: u64, reserve_out: u64, a: u64) -> Result<u64, ProgramError> {
+        if a == 0 {
+            return Ok(0);
+        }
+        let d = Self::compute_d(self.amp, reserve_in, reserve_out)?;
+        let new_in = reserve_in as u128 + a as u128;
+        let new_out = Self::compute_y(self.amp, new_in, d)? + 1;
+        Ok((reserve_out as u128).saturating_sub(new_out) as u64)
+    }
+
+    // 得到 out 需要支付的数量 (未加手续费) 向上取整
+    fn delta_in(&self, reserve_in: u64, reserve_out: u64, out: u64) -> Result<u64, ProgramError> {
+        // 不能把池子里的代币全部取走
+        if out >= reserve_out {
+            return Err(AmmError::InsufficientLiquidity.into());
+        }
+        let d = Self::compute_d(self.amp, reserve_in, reserve_out)?;
+        let new_in = Self::compute_y(self.amp, (reserve_out - out) as u128, d)? + 1;
+        let a = new_in.saturating_sub(reserve_in as u128);
+        u64::try_from(a).map_err(|_| AmmError::CurveOverflow.into())
+    }
+
+    // 价格影响 以基点表示
+    // 现货价格是曲线在当前储备处的斜率 dy/dx = (Ann * x + D_P) * y / ((Ann * y + D_P) * x)
+    // 其中 D_P = D^3 / (4xy)
+    pub fn price_impact(
+        &self,
+        p: LiquidityPair,
+        net_in: u64,
+        out: u64,
+    ) -> Result<u64, ProgramError> {
+        let (reserve_in, reserve_out) = self.reserves(p);
+        let d = Self::compute_d(self.amp, reserve_in, reserve_out)?;
+        let ann = self.amp as u128 * N_COINS * N_COINS;
+        let d_p = Self::d_p(d, reserve_in as u128, reserve_out as u128)?;
+        let spot_out = mul_div(
+            mul_div(
+                net_in as u128,
+                ann * reserve_in as u128 + d_p,
+                ann * reserve_out as u128 + d_p,
+            )?,
+            reserve_out as u128,
+            reserve_in as u128,
+        )?;
+        if spot_out == 0 {
+            return Ok(0);
+        }
+        Ok((spot_out.saturating_sub(out as u128) * BPS_DENOMINATOR as u128 / spot_out) as u64)
+    }
+
+    // D_P = D^3 / (n^n * x * y)
+    #[inline(always)]
+    fn d_p(d: u128, x: u128, y: u128) -> Result<u128, ProgramError> {
+        mul_div(mul_div(d, d, x * N_COINS)?, d, y * N_COINS)
+    }
+
+    // 牛顿迭代求不变量 D
+    // D' = (Ann * S + n * D_P) * D / ((Ann - 1) * D + (n + 1) * D_P)
+    pub fn compute_d(amp: u64, x: u64, y: u64) -> Result<u128, ProgramError> {
+        let s = x as u128 + y as u128;
+        if x == 0 || y == 0 {
+            return Err(AmmError::InsufficientLiquidity.into());
+        }
+        let ann = amp as u128 * N_COINS * N_COINS;
+        let mut d = s;
+        for _ in 0..MAX_ITERATIONS {
+            let d_p = Self::d_p(d, x as u128, y as u128)?;
+            let d_prev = d;
+            d = mul_div(
+                ann * s + N_COINS * d_p,
+                d,
+                (ann - 1) * d + (N_COINS + 1) * d_p,
+            )?;
+            if d.abs_diff(d_prev) <= 1 {
+                return Ok(d);
+            }
+        }
+        Err(AmmError::CurveOverflow.into())
+    }
+
+    // 已知一侧的储备 x 和不变量 D 牛顿迭代求另一侧的储备 y
+    // y' = (y^2 + c) / (2y + b - D)  其中 c = D^3 / (n^n * x * Ann)  b = x + D / Ann
+    pub fn compute_y(amp: u64, x: u128, d: u128) -> Result<u128, ProgramError> {
+        if x == 0 {
+            return Err(AmmError::InsufficientLiquidity.into());
+        }
+        let ann = amp as u128 * N_COINS * N_COINS;
+        let c = mul_div(mul_div(d, d, x * N_COINS)?, d, ann * N_COINS)?;
+        let b = x + d / ann;
+        let mut y = d;
+        for _ in 0..MAX_ITERATIONS {
+            let y_prev = y;
+            let denominator = (2 * y + b).checked_sub(d).ok_or(AmmError::CurveOverflow)?;
+            y = mul_div(y, y, denominator)? + c / denominator;
+            if y.abs_diff(y_prev) <= 1 {
+                return Ok(y);
+            }
+        }
+        Err(AmmError::CurveOverflow.into())
+    }
+}
+
+// floor(a * b / c) 乘积在 256 位中计算 结果超出 u128 时报错
+pub fn mul_div(a: u128, b: u128, c: u128) -> Result<u128, ProgramError> {
+    if c == 0 {
+        return Err(AmmError::CurveOverflow.into());
+    }
+    let (hi, lo) = mul_wide(a, b);
+    if hi == 0 {
+        return Ok(lo / c);
+    }
+    // 商超出 u128
+    if hi >= c {
+        return Err(AmmError::CurveOverflow.into());
+    }
+    // 逐位长除法 余数始终小于 c
+    let (mut rem, mut q) = (hi, 0u128);
+    for i in (0..128).rev() {
+        let carry = rem >> 127;
+        rem = (rem << 1) | ((lo >> i) & 1);
+        q <<= 1;
+        if carry == 1 || rem >= c {
+            rem = rem.wrapping_sub(c);
+            q |= 1;
+        }
+    }
+    Ok(q)
+}
+
+// 两个 u128 相乘 返回 256 位结果的 (高 128 位, 低 128 位)
+#[inline(always)]
+pub(crate) fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a1, a0) = (a >> 64, a & MASK);
+    let (b1, b0) = (b >> 64, b & MASK);
+    let p00 = a0 * b0;
+    let p01 = a0 * b1;
+    let p10 = a1 * b0;
+    let p11 = a1 * b1;
+    // 中间一列 最多三个 64 位数相加 不会溢出 u128
+    let mid = (p00 >> 64) + (p01 & MASK) + (p10 & MASK);
+    let lo = (mid << 64) | (p00 & MASK);
+    let hi = p11 + (p01 >> 64) + (p10 >> 64) + (mid >> 64);
+    (hi, lo)
+}
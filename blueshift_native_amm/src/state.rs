@@ -2,6 +2,65 @@ use pinocchio::account::{Ref, RefMut};
 use pinocchio::error::ProgramError;
 use pinocchio::{AccountView, Address};
 
+use crate::{
+    mul_div, mul_div_ceil, AmmError, ConstantProduct, Curve, StableSwap, BPS_DENOMINATOR, MAX_AMP,
+    MAX_AMP_CHANGE, MIN_AMP, MIN_RAMP_DURATION,
+};
+
+// 动态费率模式下 波动率每经过这么多秒衰减一半
+pub const VOLATILITY_HALF_LIFE: i64 = 60;
+// 波动率的上限 以基点表示
+pub const MAX_VOLATILITY: u64 = BPS_DENOMINATOR;
+
+// Config 的布局版本 每次从 reserved 中划出新字段时加一
+// 版本 1 在末尾预留了 64 字节
+// 版本 2 用这 64 字节记录金库地址 长度不变 金库地址不能默认为 0 旧账户要通过 MigrateConfig 写入
+// 版本 3 预留字节已经用完 在末尾追加 permissioned 和新的预留字节
+// permissioned 默认 0 表示任何人都可以注入流动性 旧账户通过 MigrateConfig 扩容即可
+pub const CONFIG_VERSION: u8 = 3;
+// 最早部署的 Config 没有版本号 之后的字段都追加在末尾 所以旧账户总是当前布局的前缀
+pub const CONFIG_LEGACY_LEN: usize = 108;
+// 版本 1 和版本 2 的长度 到 vault_y 为止
+pub const CONFIG_V1_LEN: usize = core::mem::offset_of!(Config, vault_y) + size_of::<Address>();
+// 预留给之后新增字段的字节 新字段在旧账户里读出来是 0 所以 0 必须是合法的默认值
+pub const CONFIG_RESERVED_LEN: usize = 63;
+
+// 程序拥有的定长状态账户 校验长度和所有者之后直接把账户数据当作结构体读写
+// Config 有版本号和旧布局 单独实现自己的 load
+/// # Safety
+/// 实现者必须是 #[repr(C)] 并且只由字节数组 (或同样只由字节数组组成的结构体) 构成
+/// 这样对齐是 1 任意字节都是合法的值 指针转换不会产生未对齐或非法的引用
+pub unsafe trait ProgramState: Sized {
+    #[inline(always)]
+    fn load(account: &AccountView) -> Result<Ref<'_, Self>, ProgramError> {
+        check_state_account::<Self>(account)?;
+        Ok(Ref::map(account.try_borrow()?, |data| unsafe {
+            &*(data.as_ptr() as *const Self)
+        }))
+    }
+
+    #[inline(always)]
+    fn load_mut(account: &AccountView) -> Result<RefMut<'_, Self>, ProgramError> {
+        check_state_account::<Self>(account)?;
+        Ok(RefMut::map(account.try_borrow_mut()?, |data| unsafe {
+            &mut *(data.as_mut_ptr() as *mut Self)
+        }))
+    }
+}
+
+#[inline(always)]
+fn check_state_account<T: ProgramState>(account: &AccountView) -> Result<(), ProgramError> {
+    // 对齐是 1 的要求在编译时检查 其余的约束由实现者保证
+    const { assert!(align_of::<T>() == 1) };
+    if account.data_len() != size_of::<T>() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if !account.owned_by(&crate::ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+    Ok(())
+}
+
 #[repr(C)]
 pub struct Config {
     state: u8,
@@ -11,6 +70,89 @@ pub struct Config {
     mint_y: Address,
     fee: [u8; 2],
     config_bump: [u8; 1],
+    pending_authority: Address,
+    protocol_fee: [u8; 2],
+    protocol_fees_x: [u8; 8],
+    protocol_fees_y: [u8; 8],
+    price_x_cumulative: [u8; 16],
+    price_y_cumulative: [u8; 16],
+    last_update: [u8; 8],
+    curve_type: u8,
+    initial_amp: [u8; 8],
+    target_amp: [u8; 8],
+    ramp_start: [u8; 8],
+    ramp_stop: [u8; 8],
+    fee_mode: u8,
+    min_fee: [u8; 2],
+    max_fee: [u8; 2],
+    volatility: [u8; 8],
+    last_price: [u8; 16],
+    volatility_update: [u8; 8],
+    flash_loan: u8,
+    flash_loan_amount: [u8; 8],
+    flash_loan_balance: [u8; 8],
+    version: u8,
+    vault_x: Address,
+    vault_y: Address,
+    // 以下是版本 3 追加的字段
+    permissioned: u8,
+    reserved: [u8; CONFIG_RESERVED_LEN],
+}
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlashLoan {
+    // 没有未归还的闪电贷
+    None = 0u8,
+    // 借出了代币 X
+    X = 1u8,
+    // 借出了代币 Y
+    Y = 2u8,
+}
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeMode {
+    // 使用 fee 中固定的交换费
+    Static = 0u8,
+    // 交换费随最近的价格波动在 min_fee 和 max_fee 之间变化
+    Dynamic = 1u8,
+}
+
+impl TryFrom<u8> for FeeMode {
+    type Error = ProgramError;
+
+    fn try_from(fee_mode: u8) -> Result<Self, Self::Error> {
+        match fee_mode {
+            0 => Ok(FeeMode::Static),
+            1 => Ok(FeeMode::Dynamic),
+            _ => Err(AmmError::InvalidFee.into()),
+        }
+    }
+}
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurveType {
+    // 恒定乘积 x * y = k
+    ConstantProduct = 0u8,
+    // StableSwap 适合价格挂钩的交易对
+    StableSwap = 1u8,
+    // 集中流动性 流动性由各个价格区间的头寸提供 状态在单独的 ClPool 账户里
+    Concentrated = 2u8,
+}
+
+impl TryFrom<u8> for CurveType {
+    type Error = ProgramError;
+
+    fn try_from(curve_type: u8) -> Result<Self, Self::Error> {
+        match curve_type {
+            0 => Ok(CurveType::ConstantProduct),
+            1 => Ok(CurveType::StableSwap),
+            2 => Ok(CurveType::Concentrated),
+            _ => Err(AmmError::InvalidCurve.into()),
+        }
+    }
 }
 
 #[repr(u8)]
@@ -21,38 +163,80 @@ pub enum AmmState {
     WithdrawOnly = 3u8,
 }
 
+impl TryFrom<u8> for AmmState {
+    type Error = ProgramError;
+
+    fn try_from(state: u8) -> Result<Self, Self::Error> {
+        match state {
+            0 => Ok(AmmState::Uninitialized),
+            1 => Ok(AmmState::Initialized),
+            2 => Ok(AmmState::Disabled),
+            3 => Ok(AmmState::WithdrawOnly),
+            _ => Err(AmmError::InvalidPoolState.into()),
+        }
+    }
+}
+
 impl Config {
     pub const LEN: usize = size_of::<Config>();
+    pub const VERSION_OFFSET: usize = core::mem::offset_of!(Config, version);
 
     #[inline(always)]
-    pub fn load(account: &AccountView) -> Result<Ref<Self>, ProgramError> {
-        if account.data_len() != Self::LEN {
-            return Err(ProgramError::InvalidAccountData);
-        }
+    pub fn load(account: &AccountView) -> Result<Ref<'_, Self>, ProgramError> {
+        Self::check_len(account)?;
         if !account.owned_by(&crate::ID) {
             return Err(ProgramError::InvalidAccountOwner);
         }
-        Ok(Ref::map(account.try_borrow()?, |data| unsafe {
+        let data = account.try_borrow()?;
+        Self::check_version(&data)?;
+        Ok(Ref::map(data, |data| unsafe {
             Self::from_bytes_unchecked(data)
         }))
     }
 
     #[inline(always)]
     pub fn load_unchecked(account: &AccountView) -> Result<&Self, ProgramError> {
-        if account.data_len() != Self::LEN {
-            return Err(ProgramError::InvalidAccountData);
-        }
+        Self::check_len(account)?;
         if !account.owned_by(&crate::ID) {
             return Err(ProgramError::InvalidAccountOwner);
         }
-        Ok(unsafe { Self::from_bytes_unchecked(&account.borrow_unchecked()) })
+        let data = unsafe { account.borrow_unchecked() };
+        Self::check_version(data)?;
+        Ok(unsafe { Self::from_bytes_unchecked(data) })
+    }
+
+    // 旧布局的长度是确定的 只有这些长度才报告需要迁移
+    // 不能按长度范围判断 程序的其他账户 (Order Position Farm 等) 的长度也落在这个范围里
+    // 这是有意的不兼容: 旧账户没有金库地址 也没有写入新字段的空间 扩容需要有人补足租金
+    // 所以所有指令都拒绝旧布局 直到有人调用 MigrateConfig 任何人都可以支付租金完成迁移
+    #[inline(always)]
+    pub fn check_len(account: &AccountView) -> Result<(), ProgramError> {
+        match account.data_len() {
+            Self::LEN => Ok(()),
+            CONFIG_LEGACY_LEN | CONFIG_V1_LEN => Err(AmmError::ConfigOutdated.into()),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
     }
 
+    // 不比 CONFIG_VERSION 新的版本都可以直接读取 之后划出的字段在旧版本里还是全 0 的预留字节
+    // 刚创建还没有 set_inner 的账户版本号是 0
+    #[inline(always)]
+    pub fn check_version(data: &[u8]) -> Result<(), ProgramError> {
+        if data[Self::VERSION_OFFSET] > CONFIG_VERSION {
+            return Err(AmmError::UnsupportedConfigVersion.into());
+        }
+        Ok(())
+    }
+
+    /// # Safety
+    /// 调用者需要保证 bytes 至少有 Config::LEN 字节 并且没有其他可变借用
     #[inline(always)]
     pub unsafe fn from_bytes_unchecked(bytes: &[u8]) -> &Self {
         unsafe { &*(bytes.as_ptr() as *const Config) }
     }
 
+    /// # Safety
+    /// 调用者需要保证 bytes 至少有 Config::LEN 字节 并且没有其他借用
     #[inline(always)]
     pub unsafe fn from_bytes_unchecked_mut(bytes: &mut [u8]) -> &mut Self {
         unsafe { &mut *(bytes.as_mut_ptr() as *mut Config) }
@@ -86,29 +270,283 @@ impl Config {
     pub fn config_bump(&self) -> [u8; 1] {
         self.config_bump
     }
+    #[inline(always)]
+    pub fn pending_authority(&self) -> &Address {
+        &self.pending_authority
+    }
+    #[inline(always)]
+    pub fn protocol_fee(&self) -> u16 {
+        u16::from_le_bytes(self.protocol_fee)
+    }
+    #[inline(always)]
+    pub fn protocol_fees_x(&self) -> u64 {
+        u64::from_le_bytes(self.protocol_fees_x)
+    }
+    #[inline(always)]
+    pub fn protocol_fees_y(&self) -> u64 {
+        u64::from_le_bytes(self.protocol_fees_y)
+    }
+    #[inline(always)]
+    pub fn price_x_cumulative(&self) -> u128 {
+        u128::from_le_bytes(self.price_x_cumulative)
+    }
+    #[inline(always)]
+    pub fn price_y_cumulative(&self) -> u128 {
+        u128::from_le_bytes(self.price_y_cumulative)
+    }
+    #[inline(always)]
+    pub fn last_update(&self) -> i64 {
+        i64::from_le_bytes(self.last_update)
+    }
+    #[inline(always)]
+    pub fn curve_type(&self) -> u8 {
+        self.curve_type
+    }
+    #[inline(always)]
+    pub fn initial_amp(&self) -> u64 {
+        u64::from_le_bytes(self.initial_amp)
+    }
+    #[inline(always)]
+    pub fn target_amp(&self) -> u64 {
+        u64::from_le_bytes(self.target_amp)
+    }
+    #[inline(always)]
+    pub fn ramp_start(&self) -> i64 {
+        i64::from_le_bytes(self.ramp_start)
+    }
+    #[inline(always)]
+    pub fn ramp_stop(&self) -> i64 {
+        i64::from_le_bytes(self.ramp_stop)
+    }
+    #[inline(always)]
+    pub fn fee_mode(&self) -> u8 {
+        self.fee_mode
+    }
+    #[inline(always)]
+    pub fn min_fee(&self) -> u16 {
+        u16::from_le_bytes(self.min_fee)
+    }
+    #[inline(always)]
+    pub fn max_fee(&self) -> u16 {
+        u16::from_le_bytes(self.max_fee)
+    }
+    #[inline(always)]
+    pub fn last_price(&self) -> u128 {
+        u128::from_le_bytes(self.last_price)
+    }
+    #[inline(always)]
+    pub fn volatility_update(&self) -> i64 {
+        i64::from_le_bytes(self.volatility_update)
+    }
+
+    #[inline(always)]
+    pub fn flash_loan(&self) -> u8 {
+        self.flash_loan
+    }
+    #[inline(always)]
+    pub fn flash_loan_amount(&self) -> u64 {
+        u64::from_le_bytes(self.flash_loan_amount)
+    }
+    #[inline(always)]
+    pub fn flash_loan_balance(&self) -> u64 {
+        u64::from_le_bytes(self.flash_loan_balance)
+    }
+    #[inline(always)]
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+    #[inline(always)]
+    pub fn vault_x(&self) -> &Address {
+        &self.vault_x
+    }
+    #[inline(always)]
+    pub fn vault_y(&self) -> &Address {
+        &self.vault_y
+    }
+    #[inline(always)]
+    pub fn permissioned(&self) -> bool {
+        self.permissioned != 0
+    }
+
+    // 白名单池子要求注入流动性的用户在额外账户的第一个位置传入自己的白名单账户
+    #[inline(always)]
+    pub fn check_allow_listed(
+        &self,
+        config: &Address,
+        user: &Address,
+        remaining: &[AccountView],
+    ) -> Result<(), ProgramError> {
+        if !self.permissioned() {
+            return Ok(());
+        }
+        let [entry, ..] = remaining else {
+            return Err(AmmError::NotAllowListed.into());
+        };
+        AllowListEntry::check(entry, config, user)
+    }
+
+    // 金库地址在 Initialize 时推导并记录 校验只需要比较地址 不用每次重新推导关联代币账户
+    #[inline(always)]
+    pub fn check_vault(&self, is_x: bool, vault: &Address) -> Result<(), ProgramError> {
+        let expected = match is_x {
+            true => &self.vault_x,
+            false => &self.vault_y,
+        };
+        if expected.ne(vault) {
+            return Err(AmmError::InvalidVault.into());
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn check_vaults(&self, vault_x: &Address, vault_y: &Address) -> Result<(), ProgramError> {
+        self.check_vault(true, vault_x)?;
+        self.check_vault(false, vault_y)
+    }
+
+    #[inline(always)]
+    pub fn check_no_flash_loan(&self) -> Result<(), ProgramError> {
+        if self.flash_loan != FlashLoan::None as u8 {
+            return Err(AmmError::FlashLoanActive.into());
+        }
+        Ok(())
+    }
+
+    // 闪电贷的手续费 和交换费的费率相同 向上取整
+    #[inline(always)]
+    pub fn flash_loan_fee(&self, amount: u64, now: i64) -> Result<u64, ProgramError> {
+        mul_div_ceil(amount, self.effective_fee(now) as u64, BPS_DENOMINATOR)
+    }
+
+    // 衰减到 now 的波动率 以基点表示
+    #[inline(always)]
+    pub fn volatility(&self, now: i64) -> u64 {
+        let volatility = u64::from_le_bytes(self.volatility);
+        let half_lives = now.saturating_sub(self.volatility_update()) / VOLATILITY_HALF_LIFE;
+        match half_lives {
+            ..=0 => volatility,
+            1..=63 => volatility >> half_lives,
+            _ => 0,
+        }
+    }
+
+    // 当前生效的交换费 动态模式下是 min_fee 加上波动率 不超过 max_fee
+    #[inline(always)]
+    pub fn effective_fee(&self, now: i64) -> u16 {
+        match FeeMode::try_from(self.fee_mode) {
+            Ok(FeeMode::Dynamic) => {
+                let fee = (self.min_fee() as u64).saturating_add(self.volatility(now));
+                fee.min(self.max_fee() as u64) as u16
+            }
+            _ => self.fee(),
+        }
+    }
+
+    // 当前生效的放大系数 调整期间从 initial_amp 线性变化到 target_amp
+    #[inline(always)]
+    pub fn amp(&self, now: i64) -> u64 {
+        let (start, stop) = (self.ramp_start(), self.ramp_stop());
+        let (initial, target) = (self.initial_amp(), self.target_amp());
+        if now >= stop {
+            return target;
+        }
+        if now <= start {
+            return initial;
+        }
+        let elapsed = (now - start) as u128;
+        let duration = (stop - start) as u128;
+        match target > initial {
+            true => initial + ((target - initial) as u128 * elapsed / duration) as u64,
+            false => initial - ((initial - target) as u128 * elapsed / duration) as u64,
+        }
+    }
+
+    // 按池子的曲线类型构造曲线
+    #[inline(always)]
+    pub fn curve(&self, reserve_x: u64, reserve_y: u64, now: i64) -> Result<Curve, ProgramError> {
+        match CurveType::try_from(self.curve_type)? {
+            CurveType::ConstantProduct => Ok(Curve::ConstantProduct(ConstantProduct::init(
+                reserve_x,
+                reserve_y,
+                self.effective_fee(now),
+            )?)),
+            CurveType::StableSwap => Ok(Curve::StableSwap(StableSwap::init(
+                reserve_x,
+                reserve_y,
+                self.amp(now),
+                self.effective_fee(now),
+            )?)),
+            // 集中流动性池的价格由 ClPool 决定 不能按金库余额构造曲线
+            CurveType::Concentrated => Err(AmmError::InvalidCurve.into()),
+        }
+    }
 
     #[inline(always)]
-    pub fn load_mut(account_info: &AccountView) -> Result<RefMut<Self>, ProgramError> {
-        if account_info.data_len() != Self::LEN {
-            return Err(ProgramError::InvalidAccountData);
+    pub fn check_not_concentrated(&self) -> Result<(), ProgramError> {
+        if self.curve_type == CurveType::Concentrated as u8 {
+            return Err(AmmError::InvalidCurve.into());
         }
+        Ok(())
+    }
+
+    // 金库里的代币包含了还没被领取的协议费 这部分不属于 LP 需要从储备中扣除
+    #[inline(always)]
+    pub fn reserves(&self, vault_x: u64, vault_y: u64) -> Result<(u64, u64), ProgramError> {
+        // 闪电贷借出期间金库余额不代表储备 所有按储备计算的操作都要等到归还之后
+        self.check_no_flash_loan()?;
+        // 集中流动性池的金库余额属于各个头寸 没有可替代的 LP 储备
+        self.check_not_concentrated()?;
+        let x = vault_x
+            .checked_sub(self.protocol_fees_x())
+            .ok_or(AmmError::CurveOverflow)?;
+        let y = vault_y
+            .checked_sub(self.protocol_fees_y())
+            .ok_or(AmmError::CurveOverflow)?;
+        Ok((x, y))
+    }
+
+    #[inline(always)]
+    pub fn load_mut(account_info: &AccountView) -> Result<RefMut<'_, Self>, ProgramError> {
+        Self::check_len(account_info)?;
         if !account_info.owned_by(&crate::ID) {
             return Err(ProgramError::InvalidAccountOwner);
         }
-        Ok(RefMut::map(account_info.try_borrow_mut()?, |data| unsafe {
+        let data = account_info.try_borrow_mut()?;
+        Self::check_version(&data)?;
+        Ok(RefMut::map(data, |data| unsafe {
             Self::from_bytes_unchecked_mut(data)
         }))
     }
 
     #[inline(always)]
     pub fn set_state(&mut self, state: u8) -> Result<(), ProgramError> {
-        if state.ge(&(AmmState::WithdrawOnly as u8)) {
-            return Err(ProgramError::InvalidAccountData);
-        }
-        self.state = state as u8;
+        AmmState::try_from(state)?;
+        self.state = state;
         Ok(())
     }
 
+    // 状态机：Initialized 允许所有操作 WithdrawOnly 只允许提取 Disabled 拒绝所有操作
+    // Swap 和 Deposit 只能在 Initialized 状态下进行
+    #[inline(always)]
+    pub fn check_tradable(&self) -> Result<(), ProgramError> {
+        match AmmState::try_from(self.state)? {
+            AmmState::Initialized => Ok(()),
+            AmmState::Uninitialized => Err(AmmError::PoolNotInitialized.into()),
+            AmmState::Disabled => Err(AmmError::PoolDisabled.into()),
+            AmmState::WithdrawOnly => Err(AmmError::PoolWithdrawOnly.into()),
+        }
+    }
+
+    // Withdraw 可以在 Initialized 和 WithdrawOnly 状态下进行
+    #[inline(always)]
+    pub fn check_withdrawable(&self) -> Result<(), ProgramError> {
+        match AmmState::try_from(self.state)? {
+            AmmState::Initialized | AmmState::WithdrawOnly => Ok(()),
+            AmmState::Uninitialized => Err(AmmError::PoolNotInitialized.into()),
+            AmmState::Disabled => Err(AmmError::PoolDisabled.into()),
+        }
+    }
+
     #[inline(always)]
     pub fn set_seed(&mut self, seed: u64) -> Result<(), ProgramError> {
         self.seed = seed.to_le_bytes();
@@ -136,7 +574,7 @@ impl Config {
     #[inline(always)]
     pub fn set_fee(&mut self, fee: u16) -> Result<(), ProgramError> {
         if fee.ge(&10_000) {
-            return Err(ProgramError::InvalidAccountData);
+            return Err(AmmError::InvalidFee.into());
         }
         self.fee = fee.to_le_bytes();
         Ok(())
@@ -148,6 +586,224 @@ impl Config {
         Ok(())
     }
 
+    #[inline(always)]
+    pub fn set_pending_authority(&mut self, pending_authority: Address) -> Result<(), ProgramError> {
+        self.pending_authority = pending_authority;
+        Ok(())
+    }
+
+    // protocol_fee 是协议从交换费中抽取的比例 以基点表示 10_000 表示全部交换费都归协议
+    #[inline(always)]
+    pub fn set_protocol_fee(&mut self, protocol_fee: u16) -> Result<(), ProgramError> {
+        if protocol_fee.gt(&10_000) {
+            return Err(AmmError::InvalidFee.into());
+        }
+        self.protocol_fee = protocol_fee.to_le_bytes();
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn add_protocol_fees_x(&mut self, amount: u64) -> Result<(), ProgramError> {
+        self.protocol_fees_x = self
+            .protocol_fees_x()
+            .checked_add(amount)
+            .ok_or(AmmError::CurveOverflow)?
+            .to_le_bytes();
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn add_protocol_fees_y(&mut self, amount: u64) -> Result<(), ProgramError> {
+        self.protocol_fees_y = self
+            .protocol_fees_y()
+            .checked_add(amount)
+            .ok_or(AmmError::CurveOverflow)?
+            .to_le_bytes();
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn clear_protocol_fees(&mut self) -> Result<(), ProgramError> {
+        self.protocol_fees_x = [0u8; 8];
+        self.protocol_fees_y = [0u8; 8];
+        Ok(())
+    }
+
+    // 价格累加器 和 Uniswap V2 一样 在每次改变储备之前 把上一段时间内的价格 * 经过的秒数 累加起来
+    // 价格使用 Q64.64 定点数：price_x = reserve_y / reserve_x 即 1 个 X 值多少 Y
+    // 累加器允许溢出回绕 计算 TWAP 时使用 wrapping_sub 即可
+    // reserve_x 和 reserve_y 必须是本次操作之前的储备
+    #[inline(always)]
+    pub fn update_price_cumulative(
+        &mut self,
+        reserve_x: u64,
+        reserve_y: u64,
+        now: i64,
+    ) -> Result<(), ProgramError> {
+        let last_update = self.last_update();
+        if now > last_update && reserve_x != 0 && reserve_y != 0 {
+            let elapsed = (now - last_update) as u128;
+            let price_x = ((reserve_y as u128) << 64) / reserve_x as u128;
+            let price_y = ((reserve_x as u128) << 64) / reserve_y as u128;
+            self.price_x_cumulative = self
+                .price_x_cumulative()
+                .wrapping_add(price_x.wrapping_mul(elapsed))
+                .to_le_bytes();
+            self.price_y_cumulative = self
+                .price_y_cumulative()
+                .wrapping_add(price_y.wrapping_mul(elapsed))
+                .to_le_bytes();
+        }
+        if now > last_update {
+            self.last_update = now.to_le_bytes();
+        }
+        Ok(())
+    }
+
+    // 切换费率模式 动态模式下 min_fee <= max_fee < 100%
+    // 静态模式忽略 min_fee 和 max_fee 继续使用 fee
+    #[inline(always)]
+    pub fn set_dynamic_fee(
+        &mut self,
+        fee_mode: u8,
+        min_fee: u16,
+        max_fee: u16,
+    ) -> Result<(), ProgramError> {
+        FeeMode::try_from(fee_mode)?;
+        if min_fee > max_fee || max_fee as u64 >= BPS_DENOMINATOR {
+            return Err(AmmError::InvalidFee.into());
+        }
+        self.fee_mode = fee_mode;
+        self.min_fee = min_fee.to_le_bytes();
+        self.max_fee = max_fee.to_le_bytes();
+        Ok(())
+    }
+
+    // 每次交换之后用新的储备更新波动率 两种费率模式都会记录 切换到动态模式时立即生效
+    // 先把已有的波动率衰减到 now 再加上这次交换相对上一次价格的变化 以基点表示
+    // reserve_x 和 reserve_y 必须是本次交换之后的储备
+    #[inline(always)]
+    pub fn update_volatility(
+        &mut self,
+        reserve_x: u64,
+        reserve_y: u64,
+        now: i64,
+    ) -> Result<(), ProgramError> {
+        if reserve_x == 0 || reserve_y == 0 {
+            return Ok(());
+        }
+        let price = ((reserve_y as u128) << 64) / reserve_x as u128;
+        let last_price = self.last_price();
+        let mut volatility = self.volatility(now);
+        if last_price != 0 {
+            // 价格变化超出 u128 时按上限处理
+            let change = mul_div(
+                price.abs_diff(last_price),
+                BPS_DENOMINATOR as u128,
+                last_price,
+            )
+            .map_or(MAX_VOLATILITY, |change| {
+                change.min(MAX_VOLATILITY as u128) as u64
+            });
+            volatility = volatility.saturating_add(change).min(MAX_VOLATILITY);
+        }
+        // 只按整数个半衰期推进更新时间 频繁交换时不足一个半衰期的时间不会被丢掉
+        let last_update = self.volatility_update();
+        let half_lives = now.saturating_sub(last_update) / VOLATILITY_HALF_LIFE;
+        if half_lives > 0 {
+            self.volatility_update = match half_lives {
+                1..=63 => last_update + half_lives * VOLATILITY_HALF_LIFE,
+                _ => now,
+            }
+            .to_le_bytes();
+        }
+        self.volatility = volatility.to_le_bytes();
+        self.last_price = price.to_le_bytes();
+        Ok(())
+    }
+
+    // 借出之前记录借出的一侧、数量和金库余额 同一时间只能有一笔闪电贷
+    #[inline(always)]
+    pub fn start_flash_loan(
+        &mut self,
+        side: FlashLoan,
+        amount: u64,
+        vault_balance: u64,
+    ) -> Result<(), ProgramError> {
+        self.check_no_flash_loan()?;
+        // 闪电贷的手续费留在金库里归 LP 集中流动性池没有办法把它分给头寸
+        self.check_not_concentrated()?;
+        if side == FlashLoan::None {
+            return Err(AmmError::InvalidFlashLoan.into());
+        }
+        self.flash_loan = side as u8;
+        self.flash_loan_amount = amount.to_le_bytes();
+        self.flash_loan_balance = vault_balance.to_le_bytes();
+        Ok(())
+    }
+
+    // 归还时清除借款记录 返回借出之前的金库余额
+    #[inline(always)]
+    pub fn finish_flash_loan(&mut self, side: FlashLoan, amount: u64) -> Result<u64, ProgramError> {
+        if side == FlashLoan::None
+            || self.flash_loan != side as u8
+            || self.flash_loan_amount() != amount
+        {
+            return Err(AmmError::InvalidFlashLoan.into());
+        }
+        let balance = self.flash_loan_balance();
+        self.flash_loan = FlashLoan::None as u8;
+        self.flash_loan_amount = [0u8; 8];
+        self.flash_loan_balance = [0u8; 8];
+        Ok(balance)
+    }
+
+    // 曲线类型只能在初始化时设置 ConstantProduct 和集中流动性池不使用放大系数
+    #[inline(always)]
+    pub fn set_curve(&mut self, curve_type: u8, amp: u64) -> Result<(), ProgramError> {
+        let amp = match CurveType::try_from(curve_type)? {
+            CurveType::ConstantProduct | CurveType::Concentrated => 0,
+            CurveType::StableSwap => {
+                if !(MIN_AMP..=MAX_AMP).contains(&amp) {
+                    return Err(AmmError::InvalidAmp.into());
+                }
+                amp
+            }
+        };
+        self.curve_type = curve_type;
+        self.initial_amp = amp.to_le_bytes();
+        self.target_amp = amp.to_le_bytes();
+        self.ramp_start = [0u8; 8];
+        self.ramp_stop = [0u8; 8];
+        Ok(())
+    }
+
+    // 从当前的放大系数开始 在 stop 之前线性调整到 target
+    // 调整至少持续 MIN_RAMP_DURATION 秒 并且一次最多变化 MAX_AMP_CHANGE 倍
+    #[inline(always)]
+    pub fn ramp_amp(&mut self, target: u64, now: i64, stop: i64) -> Result<(), ProgramError> {
+        if CurveType::try_from(self.curve_type)? != CurveType::StableSwap {
+            return Err(AmmError::InvalidCurve.into());
+        }
+        if !(MIN_AMP..=MAX_AMP).contains(&target) {
+            return Err(AmmError::InvalidAmp.into());
+        }
+        if stop.saturating_sub(now) < MIN_RAMP_DURATION {
+            return Err(AmmError::InvalidAmp.into());
+        }
+        let current = self.amp(now);
+        if target > current.saturating_mul(MAX_AMP_CHANGE)
+            || target.saturating_mul(MAX_AMP_CHANGE) < current
+        {
+            return Err(AmmError::InvalidAmp.into());
+        }
+        self.initial_amp = current.to_le_bytes();
+        self.target_amp = target.to_le_bytes();
+        self.ramp_start = now.to_le_bytes();
+        self.ramp_stop = stop.to_le_bytes();
+        Ok(())
+    }
+
     #[inline(always)]
     pub fn set_inner(
         &mut self,
@@ -165,16 +821,1297 @@ impl Config {
         self.set_mint_y(mint_y)?;
         self.set_fee(fee)?;
         self.set_config_bump(config_bump)?;
+        self.version = CONFIG_VERSION;
+        Ok(())
+    }
+
+    // 开启之后只有白名单里的用户可以注入流动性
+    #[inline(always)]
+    pub fn set_permissioned(&mut self, permissioned: bool) {
+        self.permissioned = permissioned as u8;
+    }
+
+    #[inline(always)]
+    pub fn set_vaults(&mut self, vault_x: Address, vault_y: Address) {
+        self.vault_x = vault_x;
+        self.vault_y = vault_y;
+    }
+
+    // 把旧版本升级到当前版本 需要非 0 默认值的字段在这里按版本逐级设置
+    #[inline(always)]
+    pub fn migrate(&mut self, vault_x: Address, vault_y: Address) -> Result<(), ProgramError> {
+        if self.version > CONFIG_VERSION {
+            return Err(AmmError::UnsupportedConfigVersion.into());
+        }
+        if self.version < 2 {
+            self.set_vaults(vault_x, vault_y);
+        }
+        self.version = CONFIG_VERSION;
         Ok(())
     }
     #[inline(always)]
     pub fn has_authority(&self) -> Option<Address> {
-        let bytes = self.authority();
-        let chunks: &[u64; 4] = unsafe { &*(bytes.to_bytes().as_ptr() as *const [u64; 4]) };
-        if chunks.iter().any(|&x| x != 0) {
-            Some(self.authority.clone())
+        if self.authority.as_ref().iter().any(|&x| x != 0) {
+            Some(self.authority)
+        } else {
+            None
+        }
+    }
+
+    // 两步转移权限时 由当前权限持有者提名的新权限地址 全0表示没有待接受的提名
+    #[inline(always)]
+    pub fn has_pending_authority(&self) -> Option<Address> {
+        if self.pending_authority.as_ref().iter().any(|&x| x != 0) {
+            Some(self.pending_authority)
         } else {
             None
         }
     }
-}
\ No newline at end of file
+
+    // 管理指令统一使用的权限校验 没有设置权限的池子是不可变的 任何人都不能修改
+    #[inline(always)]
+    pub fn check_authority(&self, authority: &Address) -> Result<(), ProgramError> {
+        match self.has_authority() {
+            Some(current) if current.eq(authority) => Ok(()),
+            Some(_) => Err(ProgramError::IncorrectAuthority),
+            None => Err(ProgramError::Immutable),
+        }
+    }
+}
+
+// 价格预言机最多保存的观测数量 写满之后覆盖最旧的观测
+pub const OBSERVATIONS: usize = 16;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Observation {
+    timestamp: [u8; 8],
+    price_x_cumulative: [u8; 16],
+    price_y_cumulative: [u8; 16],
+}
+
+impl Observation {
+    #[inline(always)]
+    pub fn new(timestamp: i64, price_x_cumulative: u128, price_y_cumulative: u128) -> Self {
+        Self {
+            timestamp: timestamp.to_le_bytes(),
+            price_x_cumulative: price_x_cumulative.to_le_bytes(),
+            price_y_cumulative: price_y_cumulative.to_le_bytes(),
+        }
+    }
+    #[inline(always)]
+    pub fn timestamp(&self) -> i64 {
+        i64::from_le_bytes(self.timestamp)
+    }
+    #[inline(always)]
+    pub fn price_x_cumulative(&self) -> u128 {
+        u128::from_le_bytes(self.price_x_cumulative)
+    }
+    #[inline(always)]
+    pub fn price_y_cumulative(&self) -> u128 {
+        u128::from_le_bytes(self.price_y_cumulative)
+    }
+
+    // 计算两次观测之间的时间加权平均价格 返回 (price_x, price_y) 都是 Q64.64 定点数
+    // older 必须早于 self
+    #[inline(always)]
+    pub fn twap(&self, older: &Observation) -> Result<(u128, u128), ProgramError> {
+        let elapsed = self.timestamp() - older.timestamp();
+        if elapsed <= 0 {
+            return Err(AmmError::StaleObservation.into());
+        }
+        let price_x = self
+            .price_x_cumulative()
+            .wrapping_sub(older.price_x_cumulative())
+            / elapsed as u128;
+        let price_y = self
+            .price_y_cumulative()
+            .wrapping_sub(older.price_y_cumulative())
+            / elapsed as u128;
+        Ok((price_x, price_y))
+    }
+}
+
+// 每个池子一个的价格观测环形缓冲区 PDA: [b"oracle", config]
+#[repr(C)]
+pub struct Oracle {
+    config: Address,
+    index: u8,
+    bump: [u8; 1],
+    observations: [Observation; OBSERVATIONS],
+}
+
+unsafe impl ProgramState for Oracle {}
+
+impl Oracle {
+    pub const LEN: usize = size_of::<Oracle>();
+
+    #[inline(always)]
+    pub fn config(&self) -> &Address {
+        &self.config
+    }
+    #[inline(always)]
+    pub fn bump(&self) -> [u8; 1] {
+        self.bump
+    }
+    #[inline(always)]
+    pub fn observations(&self) -> &[Observation; OBSERVATIONS] {
+        &self.observations
+    }
+
+    // 最新写入的观测
+    #[inline(always)]
+    pub fn latest(&self) -> &Observation {
+        &self.observations[self.index as usize % OBSERVATIONS]
+    }
+
+    // 距离最新观测 ago 次之前的观测 ago 必须小于 OBSERVATIONS
+    #[inline(always)]
+    pub fn observation_ago(&self, ago: usize) -> Option<&Observation> {
+        if ago >= OBSERVATIONS {
+            return None;
+        }
+        let index = (self.index as usize + OBSERVATIONS - ago) % OBSERVATIONS;
+        let observation = &self.observations[index];
+        // 还没有写入过的槽位时间戳为 0
+        match observation.timestamp() {
+            0 => None,
+            _ => Some(observation),
+        }
+    }
+
+    #[inline(always)]
+    pub fn set_inner(&mut self, config: Address, bump: [u8; 1]) {
+        self.config = config;
+        self.index = 0;
+        self.bump = bump;
+    }
+
+    // 写入一条新的观测 同一秒内只保留第一条
+    #[inline(always)]
+    pub fn write(&mut self, observation: Observation) -> bool {
+        if observation.timestamp() <= self.latest().timestamp() {
+            return false;
+        }
+        self.index = ((self.index as usize + 1) % OBSERVATIONS) as u8;
+        self.observations[self.index as usize] = observation;
+        true
+    }
+}
+
+// 每个注册表分页最多记录的池子数量
+pub const POOLS_PER_PAGE: usize = 64;
+
+// 全局唯一的池子工厂 PDA: [b"factory"]
+// 记录新池子的默认费率 所有池子都要在 Initialize 中登记到这里
+#[repr(C)]
+pub struct Factory {
+    admin: Address,
+    default_fee: [u8; 2],
+    default_protocol_fee: [u8; 2],
+    paused: u8,
+    pool_count: [u8; 8],
+    bump: [u8; 1],
+}
+
+unsafe impl ProgramState for Factory {}
+
+impl Factory {
+    pub const LEN: usize = size_of::<Factory>();
+
+    #[inline(always)]
+    pub fn admin(&self) -> &Address {
+        &self.admin
+    }
+    #[inline(always)]
+    pub fn default_fee(&self) -> u16 {
+        u16::from_le_bytes(self.default_fee)
+    }
+    #[inline(always)]
+    pub fn default_protocol_fee(&self) -> u16 {
+        u16::from_le_bytes(self.default_protocol_fee)
+    }
+    #[inline(always)]
+    pub fn is_paused(&self) -> bool {
+        self.paused != 0
+    }
+    // 已经登记的池子总数 也是下一个池子在注册表中的序号
+    #[inline(always)]
+    pub fn pool_count(&self) -> u64 {
+        u64::from_le_bytes(self.pool_count)
+    }
+    #[inline(always)]
+    pub fn bump(&self) -> [u8; 1] {
+        self.bump
+    }
+
+    #[inline(always)]
+    pub fn set_inner(
+        &mut self,
+        admin: Address,
+        default_fee: u16,
+        default_protocol_fee: u16,
+        bump: [u8; 1],
+    ) -> Result<(), ProgramError> {
+        self.admin = admin;
+        self.set_defaults(default_fee, default_protocol_fee)?;
+        self.paused = 0;
+        self.pool_count = 0u64.to_le_bytes();
+        self.bump = bump;
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn set_admin(&mut self, admin: Address) {
+        self.admin = admin;
+    }
+
+    // 只影响之后创建的池子 已有池子的费率由各自的权限持有者管理
+    #[inline(always)]
+    pub fn set_defaults(&mut self, fee: u16, protocol_fee: u16) -> Result<(), ProgramError> {
+        if fee >= 10_000 || protocol_fee > 10_000 {
+            return Err(AmmError::InvalidFee.into());
+        }
+        self.default_fee = fee.to_le_bytes();
+        self.default_protocol_fee = protocol_fee.to_le_bytes();
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused as u8;
+    }
+
+    #[inline(always)]
+    pub fn check_admin(&self, admin: &Address) -> Result<(), ProgramError> {
+        if self.admin.ne(admin) {
+            return Err(ProgramError::IncorrectAuthority);
+        }
+        Ok(())
+    }
+
+    // 暂停期间不能创建新池子 已有池子的交易由各自的 SetState 控制
+    #[inline(always)]
+    pub fn check_not_paused(&self) -> Result<(), ProgramError> {
+        if self.is_paused() {
+            return Err(AmmError::FactoryPaused.into());
+        }
+        Ok(())
+    }
+
+    // 登记一个新池子 返回它在注册表中的 (分页, 页内位置)
+    #[inline(always)]
+    pub fn register_pool(&mut self) -> Result<(u64, usize), ProgramError> {
+        let index = self.pool_count();
+        self.pool_count = index
+            .checked_add(1)
+            .ok_or(AmmError::CurveOverflow)?
+            .to_le_bytes();
+        Ok(Self::page_of(index))
+    }
+
+    // 注册表序号对应的 (分页, 页内位置)
+    #[inline(always)]
+    pub fn page_of(index: u64) -> (u64, usize) {
+        (
+            index / POOLS_PER_PAGE as u64,
+            (index % POOLS_PER_PAGE as u64) as usize,
+        )
+    }
+}
+
+// 每个交易对一个的记录 PDA: [b"pair", mint_x, mint_y]
+// 同一交易对可以有多个不同 seed 的池子 这里记录数量和最早创建的池子
+#[repr(C)]
+pub struct Pair {
+    mint_x: Address,
+    mint_y: Address,
+    first_pool: Address,
+    pool_count: [u8; 8],
+    bump: [u8; 1],
+}
+
+unsafe impl ProgramState for Pair {}
+
+impl Pair {
+    pub const LEN: usize = size_of::<Pair>();
+
+    #[inline(always)]
+    pub fn mint_x(&self) -> &Address {
+        &self.mint_x
+    }
+    #[inline(always)]
+    pub fn mint_y(&self) -> &Address {
+        &self.mint_y
+    }
+    #[inline(always)]
+    pub fn first_pool(&self) -> &Address {
+        &self.first_pool
+    }
+    #[inline(always)]
+    pub fn pool_count(&self) -> u64 {
+        u64::from_le_bytes(self.pool_count)
+    }
+    #[inline(always)]
+    pub fn bump(&self) -> [u8; 1] {
+        self.bump
+    }
+
+    #[inline(always)]
+    pub fn set_inner(&mut self, mint_x: Address, mint_y: Address, bump: [u8; 1]) {
+        self.mint_x = mint_x;
+        self.mint_y = mint_y;
+        self.first_pool = Address::default();
+        self.pool_count = 0u64.to_le_bytes();
+        self.bump = bump;
+    }
+
+    #[inline(always)]
+    pub fn add_pool(&mut self, config: &Address) -> Result<(), ProgramError> {
+        let count = self.pool_count();
+        if count == 0 {
+            self.first_pool = *config;
+        }
+        self.pool_count = count
+            .checked_add(1)
+            .ok_or(AmmError::CurveOverflow)?
+            .to_le_bytes();
+        Ok(())
+    }
+}
+
+// 按创建顺序记录所有池子的分页注册表 PDA: [b"registry", page u64]
+// 第 n 个池子在第 n / POOLS_PER_PAGE 页 客户端从 Factory::pool_count 算出总页数
+#[repr(C)]
+pub struct RegistryPage {
+    page: [u8; 8],
+    len: u8,
+    bump: [u8; 1],
+    pools: [Address; POOLS_PER_PAGE],
+}
+
+unsafe impl ProgramState for RegistryPage {}
+
+impl RegistryPage {
+    pub const LEN: usize = size_of::<RegistryPage>();
+
+    #[inline(always)]
+    pub fn page(&self) -> u64 {
+        u64::from_le_bytes(self.page)
+    }
+    #[inline(always)]
+    pub fn bump(&self) -> [u8; 1] {
+        self.bump
+    }
+    // 这一页已经登记的池子 按创建顺序排列
+    #[inline(always)]
+    pub fn pools(&self) -> &[Address] {
+        &self.pools[..self.len as usize]
+    }
+
+    #[inline(always)]
+    pub fn set_inner(&mut self, page: u64, bump: [u8; 1]) {
+        self.page = page.to_le_bytes();
+        self.len = 0;
+        self.bump = bump;
+    }
+
+    // 池子必须写在 Factory::register_pool 分配的位置 保证注册表的顺序和序号一致
+    #[inline(always)]
+    pub fn push(&mut self, slot: usize, config: &Address) -> Result<(), ProgramError> {
+        if slot != self.len as usize || slot >= POOLS_PER_PAGE {
+            return Err(AmmError::InvalidRegistry.into());
+        }
+        self.pools[slot] = *config;
+        self.len += 1;
+        Ok(())
+    }
+}
+
+// 限价单和定投单 PDA: [b"order", config, owner, seed]
+// 输入代币托管在订单的关联代币账户里 任何 keeper 都可以在条件满足时执行
+// 每次执行支付 amount_in 并且至少收到 min_out 限价单就是只执行一次的订单
+#[repr(C)]
+pub struct Order {
+    owner: Address,
+    config: Address,
+    mint_in: Address,
+    seed: [u8; 8],
+    is_x: u8,
+    amount_in: [u8; 8],
+    min_out: [u8; 8],
+    tip: [u8; 8],
+    executions_left: [u8; 8],
+    interval: [u8; 8],
+    next_execution: [u8; 8],
+    expiration: [u8; 8],
+    bump: [u8; 1],
+}
+
+unsafe impl ProgramState for Order {}
+
+impl Order {
+    pub const LEN: usize = size_of::<Order>();
+
+    #[inline(always)]
+    pub fn owner(&self) -> &Address {
+        &self.owner
+    }
+    #[inline(always)]
+    pub fn config(&self) -> &Address {
+        &self.config
+    }
+    #[inline(always)]
+    pub fn mint_in(&self) -> &Address {
+        &self.mint_in
+    }
+    #[inline(always)]
+    pub fn seed(&self) -> u64 {
+        u64::from_le_bytes(self.seed)
+    }
+    // true 表示支付 X 获取 Y
+    #[inline(always)]
+    pub fn is_x(&self) -> bool {
+        self.is_x != 0
+    }
+    // 每次执行从托管账户转入池子的数量
+    #[inline(always)]
+    pub fn amount_in(&self) -> u64 {
+        u64::from_le_bytes(self.amount_in)
+    }
+    // 每次执行用户至少收到的数量
+    #[inline(always)]
+    pub fn min_out(&self) -> u64 {
+        u64::from_le_bytes(self.min_out)
+    }
+    // 每次执行付给 keeper 的输入代币数量
+    #[inline(always)]
+    pub fn tip(&self) -> u64 {
+        u64::from_le_bytes(self.tip)
+    }
+    #[inline(always)]
+    pub fn executions_left(&self) -> u64 {
+        u64::from_le_bytes(self.executions_left)
+    }
+    // 两次执行之间至少间隔的秒数
+    #[inline(always)]
+    pub fn interval(&self) -> i64 {
+        i64::from_le_bytes(self.interval)
+    }
+    #[inline(always)]
+    pub fn next_execution(&self) -> i64 {
+        i64::from_le_bytes(self.next_execution)
+    }
+    #[inline(always)]
+    pub fn expiration(&self) -> i64 {
+        i64::from_le_bytes(self.expiration)
+    }
+    #[inline(always)]
+    pub fn bump(&self) -> [u8; 1] {
+        self.bump
+    }
+
+    // 下单时需要托管的输入代币总数
+    #[inline(always)]
+    pub fn escrow_amount(amount_in: u64, tip: u64, executions: u64) -> Result<u64, ProgramError> {
+        amount_in
+            .checked_add(tip)
+            .and_then(|amount| amount.checked_mul(executions))
+            .ok_or(AmmError::CurveOverflow.into())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline(always)]
+    pub fn set_inner(
+        &mut self,
+        owner: Address,
+        config: Address,
+        mint_in: Address,
+        seed: u64,
+        is_x: bool,
+        amount_in: u64,
+        min_out: u64,
+        tip: u64,
+        executions: u64,
+        interval: i64,
+        start: i64,
+        expiration: i64,
+        bump: [u8; 1],
+    ) {
+        self.owner = owner;
+        self.config = config;
+        self.mint_in = mint_in;
+        self.seed = seed.to_le_bytes();
+        self.is_x = is_x as u8;
+        self.amount_in = amount_in.to_le_bytes();
+        self.min_out = min_out.to_le_bytes();
+        self.tip = tip.to_le_bytes();
+        self.executions_left = executions.to_le_bytes();
+        self.interval = interval.to_le_bytes();
+        self.next_execution = start.to_le_bytes();
+        self.expiration = expiration.to_le_bytes();
+        self.bump = bump;
+    }
+
+    // 时间条件 价格条件在执行时由 min_out 检查
+    #[inline(always)]
+    pub fn check_executable(&self, now: i64) -> Result<(), ProgramError> {
+        if self.executions_left() == 0 {
+            return Err(AmmError::OrderFilled.into());
+        }
+        if now > self.expiration() {
+            return Err(AmmError::Expired.into());
+        }
+        if now < self.next_execution() {
+            return Err(AmmError::OrderNotReady.into());
+        }
+        Ok(())
+    }
+
+    // 记录一次执行 下一次执行时间按固定间隔推进
+    // keeper 错过的间隔直接跳过 不会在恢复之后连续执行多次
+    #[inline(always)]
+    pub fn advance(&mut self, now: i64) -> Result<(), ProgramError> {
+        self.check_executable(now)?;
+        self.executions_left = (self.executions_left() - 1).to_le_bytes();
+        let interval = self.interval();
+        if interval > 0 {
+            let missed = (now - self.next_execution()) / interval + 1;
+            let next = missed
+                .checked_mul(interval)
+                .and_then(|delay| self.next_execution().checked_add(delay))
+                .ok_or(AmmError::CurveOverflow)?;
+            self.next_execution = next.to_le_bytes();
+        }
+        Ok(())
+    }
+}
+
+// 每个 tick 数组账户保存的 tick 数量
+pub const TICKS_PER_ARRAY: usize = 32;
+
+// 集中流动性池的状态 PDA: [b"cl_pool", config]
+// 价格以 sqrt(price) 的 Q64.64 定点数保存 liquidity 是当前价格所在区间内所有头寸的流动性之和
+// 手续费按单位流动性累加到 fee_growth_global 头寸按自己区间内的增量领取
+#[repr(C)]
+pub struct ClPool {
+    config: Address,
+    sqrt_price: [u8; 16],
+    tick: [u8; 4],
+    liquidity: [u8; 16],
+    tick_spacing: [u8; 2],
+    fee_growth_global_x: [u8; 16],
+    fee_growth_global_y: [u8; 16],
+    bump: [u8; 1],
+}
+
+unsafe impl ProgramState for ClPool {}
+
+impl ClPool {
+    pub const LEN: usize = size_of::<ClPool>();
+
+    #[inline(always)]
+    pub fn config(&self) -> &Address {
+        &self.config
+    }
+    // 当前价格的平方根 Q64.64
+    #[inline(always)]
+    pub fn sqrt_price(&self) -> u128 {
+        u128::from_le_bytes(self.sqrt_price)
+    }
+    // 当前价格所在的 tick 即 sqrt_price_at_tick(tick) <= sqrt_price 的最大 tick
+    #[inline(always)]
+    pub fn tick(&self) -> i32 {
+        i32::from_le_bytes(self.tick)
+    }
+    #[inline(always)]
+    pub fn liquidity(&self) -> u128 {
+        u128::from_le_bytes(self.liquidity)
+    }
+    #[inline(always)]
+    pub fn tick_spacing(&self) -> u16 {
+        u16::from_le_bytes(self.tick_spacing)
+    }
+    #[inline(always)]
+    pub fn fee_growth_global_x(&self) -> u128 {
+        u128::from_le_bytes(self.fee_growth_global_x)
+    }
+    #[inline(always)]
+    pub fn fee_growth_global_y(&self) -> u128 {
+        u128::from_le_bytes(self.fee_growth_global_y)
+    }
+    #[inline(always)]
+    pub fn bump(&self) -> [u8; 1] {
+        self.bump
+    }
+
+    #[inline(always)]
+    pub fn set_inner(
+        &mut self,
+        config: Address,
+        sqrt_price: u128,
+        tick: i32,
+        tick_spacing: u16,
+        bump: [u8; 1],
+    ) {
+        self.config = config;
+        self.sqrt_price = sqrt_price.to_le_bytes();
+        self.tick = tick.to_le_bytes();
+        self.liquidity = 0u128.to_le_bytes();
+        self.tick_spacing = tick_spacing.to_le_bytes();
+        self.fee_growth_global_x = 0u128.to_le_bytes();
+        self.fee_growth_global_y = 0u128.to_le_bytes();
+        self.bump = bump;
+    }
+
+    #[inline(always)]
+    pub fn set_price(&mut self, sqrt_price: u128, tick: i32) {
+        self.sqrt_price = sqrt_price.to_le_bytes();
+        self.tick = tick.to_le_bytes();
+    }
+
+    #[inline(always)]
+    pub fn set_liquidity(&mut self, liquidity: u128) {
+        self.liquidity = liquidity.to_le_bytes();
+    }
+
+    // 按流动性的变化量调整当前区间的流动性 结果不能为负
+    #[inline(always)]
+    pub fn add_liquidity(&mut self, delta: i128) -> Result<(), ProgramError> {
+        let liquidity = self
+            .liquidity()
+            .checked_add_signed(delta)
+            .ok_or(AmmError::InsufficientLiquidity)?;
+        self.set_liquidity(liquidity);
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn set_fee_growth_global(&mut self, fee_growth_x: u128, fee_growth_y: u128) {
+        self.fee_growth_global_x = fee_growth_x.to_le_bytes();
+        self.fee_growth_global_y = fee_growth_y.to_le_bytes();
+    }
+
+    // tick 必须在范围内并且对齐 tick_spacing
+    #[inline(always)]
+    pub fn check_tick(&self, tick: i32) -> Result<(), ProgramError> {
+        if !(crate::MIN_TICK..=crate::MAX_TICK).contains(&tick)
+            || tick % self.tick_spacing() as i32 != 0
+        {
+            return Err(AmmError::InvalidTick.into());
+        }
+        Ok(())
+    }
+}
+
+// 单个 tick 的状态 liquidity_gross 为 0 表示没有头寸以它为边界
+// fee_growth_outside 是这个 tick 另一侧累计的手续费 价格穿过时翻转
+#[repr(C)]
+pub struct Tick {
+    liquidity_net: [u8; 16],
+    liquidity_gross: [u8; 16],
+    fee_growth_outside_x: [u8; 16],
+    fee_growth_outside_y: [u8; 16],
+}
+
+impl Tick {
+    // 价格从左向右穿过这个 tick 时当前流动性的变化量
+    #[inline(always)]
+    pub fn liquidity_net(&self) -> i128 {
+        i128::from_le_bytes(self.liquidity_net)
+    }
+    #[inline(always)]
+    pub fn liquidity_gross(&self) -> u128 {
+        u128::from_le_bytes(self.liquidity_gross)
+    }
+    #[inline(always)]
+    pub fn fee_growth_outside_x(&self) -> u128 {
+        u128::from_le_bytes(self.fee_growth_outside_x)
+    }
+    #[inline(always)]
+    pub fn fee_growth_outside_y(&self) -> u128 {
+        u128::from_le_bytes(self.fee_growth_outside_y)
+    }
+    #[inline(always)]
+    pub fn is_initialized(&self) -> bool {
+        self.liquidity_gross() != 0
+    }
+
+    // 头寸以这个 tick 为下界或上界增减流动性
+    // 第一次被使用时 约定此前所有的手续费都发生在当前价格一侧
+    #[allow(clippy::too_many_arguments)]
+    #[inline(always)]
+    pub fn update(
+        &mut self,
+        tick: i32,
+        current_tick: i32,
+        delta: i128,
+        fee_growth_global_x: u128,
+        fee_growth_global_y: u128,
+        upper: bool,
+    ) -> Result<(), ProgramError> {
+        let gross = self.liquidity_gross();
+        let gross_after = gross
+            .checked_add_signed(delta)
+            .ok_or(AmmError::InsufficientLiquidity)?;
+        if gross == 0 && tick <= current_tick {
+            self.fee_growth_outside_x = fee_growth_global_x.to_le_bytes();
+            self.fee_growth_outside_y = fee_growth_global_y.to_le_bytes();
+        }
+        let net = match upper {
+            true => self.liquidity_net().checked_sub(delta),
+            false => self.liquidity_net().checked_add(delta),
+        }
+        .ok_or(AmmError::CurveOverflow)?;
+        self.liquidity_net = net.to_le_bytes();
+        self.liquidity_gross = gross_after.to_le_bytes();
+        Ok(())
+    }
+
+    // 价格穿过这个 tick 另一侧的手续费变成 global 减去原来的值
+    #[inline(always)]
+    pub fn cross(&mut self, fee_growth_global_x: u128, fee_growth_global_y: u128) -> i128 {
+        self.fee_growth_outside_x = fee_growth_global_x
+            .wrapping_sub(self.fee_growth_outside_x())
+            .to_le_bytes();
+        self.fee_growth_outside_y = fee_growth_global_y
+            .wrapping_sub(self.fee_growth_outside_y())
+            .to_le_bytes();
+        self.liquidity_net()
+    }
+
+    // 最后一个头寸移除之后 tick 回到未使用的状态
+    #[inline(always)]
+    pub fn clear(&mut self) {
+        self.liquidity_net = [0u8; 16];
+        self.liquidity_gross = [0u8; 16];
+        self.fee_growth_outside_x = [0u8; 16];
+        self.fee_growth_outside_y = [0u8; 16];
+    }
+}
+
+// 连续 TICKS_PER_ARRAY 个对齐 tick_spacing 的 tick PDA: [b"tick_array", config, start_tick]
+// start_tick 是 TICKS_PER_ARRAY * tick_spacing 的整数倍
+#[repr(C)]
+pub struct TickArray {
+    config: Address,
+    start_tick: [u8; 4],
+    bump: [u8; 1],
+    ticks: [Tick; TICKS_PER_ARRAY],
+}
+
+unsafe impl ProgramState for TickArray {}
+
+impl TickArray {
+    pub const LEN: usize = size_of::<TickArray>();
+
+    #[inline(always)]
+    pub fn config(&self) -> &Address {
+        &self.config
+    }
+    #[inline(always)]
+    pub fn start_tick(&self) -> i32 {
+        i32::from_le_bytes(self.start_tick)
+    }
+    #[inline(always)]
+    pub fn bump(&self) -> [u8; 1] {
+        self.bump
+    }
+
+    #[inline(always)]
+    pub fn set_inner(&mut self, config: Address, start_tick: i32, bump: [u8; 1]) {
+        self.config = config;
+        self.start_tick = start_tick.to_le_bytes();
+        self.bump = bump;
+        for tick in self.ticks.iter_mut() {
+            tick.clear();
+        }
+    }
+
+    // 包含 tick 的数组的起始 tick 向负无穷取整
+    #[inline(always)]
+    pub fn start_tick_of(tick: i32, tick_spacing: u16) -> i32 {
+        let span = tick_spacing as i32 * TICKS_PER_ARRAY as i32;
+        tick.div_euclid(span) * span
+    }
+
+    // tick 在数组中的位置 不在这个数组里或者没有对齐时报错
+    #[inline(always)]
+    pub fn offset(&self, tick: i32, tick_spacing: u16) -> Result<usize, ProgramError> {
+        let spacing = tick_spacing as i32;
+        let offset = tick - self.start_tick();
+        if offset < 0 || offset % spacing != 0 || offset / spacing >= TICKS_PER_ARRAY as i32 {
+            return Err(AmmError::InvalidTick.into());
+        }
+        Ok((offset / spacing) as usize)
+    }
+
+    #[inline(always)]
+    pub fn tick_mut(&mut self, tick: i32, tick_spacing: u16) -> Result<&mut Tick, ProgramError> {
+        let offset = self.offset(tick, tick_spacing)?;
+        Ok(&mut self.ticks[offset])
+    }
+
+    // 头寸的上下界在同一个数组里时 同时取出两个 tick
+    #[inline(always)]
+    pub fn tick_pair_mut(
+        &mut self,
+        lower: i32,
+        upper: i32,
+        tick_spacing: u16,
+    ) -> Result<(&mut Tick, &mut Tick), ProgramError> {
+        let lower = self.offset(lower, tick_spacing)?;
+        let upper = self.offset(upper, tick_spacing)?;
+        if lower >= upper {
+            return Err(AmmError::InvalidTick.into());
+        }
+        let (left, right) = self.ticks.split_at_mut(upper);
+        Ok((&mut left[lower], &mut right[0]))
+    }
+
+    // 在数组范围内找下一个有流动性的 tick
+    // 向左找 <= tick 的最大值 向右找 > tick 的最小值 和交换中穿过 tick 的方向一致
+    #[inline(always)]
+    pub fn next_initialized(&self, tick: i32, tick_spacing: u16, left: bool) -> Option<i32> {
+        let spacing = tick_spacing as i32;
+        let start = self.start_tick();
+        let position = (tick - start).div_euclid(spacing);
+        match left {
+            true => (0..TICKS_PER_ARRAY as i32)
+                .rev()
+                .filter(|i| *i <= position)
+                .find(|i| self.ticks[*i as usize].is_initialized()),
+            false => (0..TICKS_PER_ARRAY as i32)
+                .filter(|i| *i > position)
+                .find(|i| self.ticks[*i as usize].is_initialized()),
+        }
+        .map(|i| start + i * spacing)
+    }
+}
+
+// 集中流动性头寸 PDA: [b"position", config, owner, tick_lower, tick_upper]
+// fee_growth_inside_last 是上次结算时区间内的单位流动性手续费 差值乘以流动性就是新增的手续费
+#[repr(C)]
+pub struct Position {
+    owner: Address,
+    config: Address,
+    tick_lower: [u8; 4],
+    tick_upper: [u8; 4],
+    liquidity: [u8; 16],
+    fee_growth_inside_x_last: [u8; 16],
+    fee_growth_inside_y_last: [u8; 16],
+    fees_owed_x: [u8; 8],
+    fees_owed_y: [u8; 8],
+    bump: [u8; 1],
+}
+
+unsafe impl ProgramState for Position {}
+
+impl Position {
+    pub const LEN: usize = size_of::<Position>();
+
+    #[inline(always)]
+    pub fn owner(&self) -> &Address {
+        &self.owner
+    }
+    #[inline(always)]
+    pub fn config(&self) -> &Address {
+        &self.config
+    }
+    #[inline(always)]
+    pub fn tick_lower(&self) -> i32 {
+        i32::from_le_bytes(self.tick_lower)
+    }
+    #[inline(always)]
+    pub fn tick_upper(&self) -> i32 {
+        i32::from_le_bytes(self.tick_upper)
+    }
+    #[inline(always)]
+    pub fn liquidity(&self) -> u128 {
+        u128::from_le_bytes(self.liquidity)
+    }
+    #[inline(always)]
+    pub fn fee_growth_inside_x_last(&self) -> u128 {
+        u128::from_le_bytes(self.fee_growth_inside_x_last)
+    }
+    #[inline(always)]
+    pub fn fee_growth_inside_y_last(&self) -> u128 {
+        u128::from_le_bytes(self.fee_growth_inside_y_last)
+    }
+    #[inline(always)]
+    pub fn fees_owed_x(&self) -> u64 {
+        u64::from_le_bytes(self.fees_owed_x)
+    }
+    #[inline(always)]
+    pub fn fees_owed_y(&self) -> u64 {
+        u64::from_le_bytes(self.fees_owed_y)
+    }
+    #[inline(always)]
+    pub fn bump(&self) -> [u8; 1] {
+        self.bump
+    }
+
+    #[inline(always)]
+    pub fn set_inner(
+        &mut self,
+        owner: Address,
+        config: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        bump: [u8; 1],
+    ) {
+        self.owner = owner;
+        self.config = config;
+        self.tick_lower = tick_lower.to_le_bytes();
+        self.tick_upper = tick_upper.to_le_bytes();
+        self.liquidity = 0u128.to_le_bytes();
+        self.fee_growth_inside_x_last = 0u128.to_le_bytes();
+        self.fee_growth_inside_y_last = 0u128.to_le_bytes();
+        self.fees_owed_x = 0u64.to_le_bytes();
+        self.fees_owed_y = 0u64.to_le_bytes();
+        self.bump = bump;
+    }
+
+    // 结算区间内新增的手续费 然后按变化量调整头寸的流动性
+    #[inline(always)]
+    pub fn update(
+        &mut self,
+        delta: i128,
+        fee_growth_inside_x: u128,
+        fee_growth_inside_y: u128,
+    ) -> Result<(), ProgramError> {
+        let liquidity = self.liquidity();
+        let owed_x = mul_div(
+            liquidity,
+            fee_growth_inside_x.wrapping_sub(self.fee_growth_inside_x_last()),
+            crate::Q64,
+        )?;
+        let owed_y = mul_div(
+            liquidity,
+            fee_growth_inside_y.wrapping_sub(self.fee_growth_inside_y_last()),
+            crate::Q64,
+        )?;
+        let fees_owed_x = u64::try_from(owed_x)
+            .ok()
+            .and_then(|owed| owed.checked_add(self.fees_owed_x()))
+            .ok_or(AmmError::CurveOverflow)?;
+        let fees_owed_y = u64::try_from(owed_y)
+            .ok()
+            .and_then(|owed| owed.checked_add(self.fees_owed_y()))
+            .ok_or(AmmError::CurveOverflow)?;
+        let liquidity = liquidity
+            .checked_add_signed(delta)
+            .ok_or(AmmError::InsufficientLiquidity)?;
+        self.liquidity = liquidity.to_le_bytes();
+        self.fee_growth_inside_x_last = fee_growth_inside_x.to_le_bytes();
+        self.fee_growth_inside_y_last = fee_growth_inside_y.to_le_bytes();
+        self.fees_owed_x = fees_owed_x.to_le_bytes();
+        self.fees_owed_y = fees_owed_y.to_le_bytes();
+        Ok(())
+    }
+
+    // 领取之后清空欠付的手续费
+    #[inline(always)]
+    pub fn clear_fees(&mut self) {
+        self.fees_owed_x = 0u64.to_le_bytes();
+        self.fees_owed_y = 0u64.to_le_bytes();
+    }
+}
+
+// LP 质押挖矿 PDA: [b"farm", config]
+// 奖励按秒释放 reward_per_share 是每单位 LP 累计的奖励 Q64.64
+// 只释放已经注入的奖励 undistributed 是还没有分配给质押者的部分
+#[repr(C)]
+pub struct Farm {
+    config: Address,
+    lp_mint: Address,
+    reward_mint: Address,
+    emission_rate: [u8; 8],
+    reward_per_share: [u8; 16],
+    total_staked: [u8; 8],
+    undistributed: [u8; 8],
+    last_update: [u8; 8],
+    bump: [u8; 1],
+}
+
+unsafe impl ProgramState for Farm {}
+
+impl Farm {
+    pub const LEN: usize = size_of::<Farm>();
+
+    #[inline(always)]
+    pub fn config(&self) -> &Address {
+        &self.config
+    }
+    #[inline(always)]
+    pub fn lp_mint(&self) -> &Address {
+        &self.lp_mint
+    }
+    #[inline(always)]
+    pub fn reward_mint(&self) -> &Address {
+        &self.reward_mint
+    }
+    // 每秒释放的奖励数量
+    #[inline(always)]
+    pub fn emission_rate(&self) -> u64 {
+        u64::from_le_bytes(self.emission_rate)
+    }
+    #[inline(always)]
+    pub fn reward_per_share(&self) -> u128 {
+        u128::from_le_bytes(self.reward_per_share)
+    }
+    #[inline(always)]
+    pub fn total_staked(&self) -> u64 {
+        u64::from_le_bytes(self.total_staked)
+    }
+    #[inline(always)]
+    pub fn undistributed(&self) -> u64 {
+        u64::from_le_bytes(self.undistributed)
+    }
+    #[inline(always)]
+    pub fn last_update(&self) -> i64 {
+        i64::from_le_bytes(self.last_update)
+    }
+    #[inline(always)]
+    pub fn bump(&self) -> [u8; 1] {
+        self.bump
+    }
+
+    #[inline(always)]
+    pub fn set_inner(
+        &mut self,
+        config: Address,
+        lp_mint: Address,
+        reward_mint: Address,
+        emission_rate: u64,
+        now: i64,
+        bump: [u8; 1],
+    ) {
+        self.config = config;
+        self.lp_mint = lp_mint;
+        self.reward_mint = reward_mint;
+        self.emission_rate = emission_rate.to_le_bytes();
+        self.reward_per_share = 0u128.to_le_bytes();
+        self.total_staked = 0u64.to_le_bytes();
+        self.undistributed = 0u64.to_le_bytes();
+        self.last_update = now.to_le_bytes();
+        self.bump = bump;
+    }
+
+    // 把上次更新以来释放的奖励按质押数量累加到 reward_per_share
+    // 没有人质押时不释放 奖励留到之后再分配
+    #[inline(always)]
+    pub fn update(&mut self, now: i64) -> Result<(), ProgramError> {
+        let elapsed = now - self.last_update();
+        if elapsed <= 0 {
+            return Ok(());
+        }
+        self.last_update = now.to_le_bytes();
+        let total_staked = self.total_staked();
+        if total_staked == 0 {
+            return Ok(());
+        }
+        let reward = (self.emission_rate() as u128 * elapsed as u128)
+            .min(self.undistributed() as u128) as u64;
+        let reward_per_share = self
+            .reward_per_share()
+            .checked_add(mul_div(reward as u128, crate::Q64, total_staked as u128)?)
+            .ok_or(AmmError::CurveOverflow)?;
+        self.reward_per_share = reward_per_share.to_le_bytes();
+        self.undistributed = (self.undistributed() - reward).to_le_bytes();
+        Ok(())
+    }
+
+    // 新的释放速度只影响之后的时间
+    #[inline(always)]
+    pub fn set_emission_rate(&mut self, emission_rate: u64, now: i64) -> Result<(), ProgramError> {
+        self.update(now)?;
+        self.emission_rate = emission_rate.to_le_bytes();
+        Ok(())
+    }
+
+    // 注入的奖励只从现在开始释放
+    #[inline(always)]
+    pub fn fund(&mut self, amount: u64, now: i64) -> Result<(), ProgramError> {
+        self.update(now)?;
+        let undistributed = self
+            .undistributed()
+            .checked_add(amount)
+            .ok_or(AmmError::CurveOverflow)?;
+        self.undistributed = undistributed.to_le_bytes();
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn stake(&mut self, amount: u64) -> Result<(), ProgramError> {
+        let total_staked = self
+            .total_staked()
+            .checked_add(amount)
+            .ok_or(AmmError::CurveOverflow)?;
+        self.total_staked = total_staked.to_le_bytes();
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn unstake(&mut self, amount: u64) -> Result<(), ProgramError> {
+        let total_staked = self
+            .total_staked()
+            .checked_sub(amount)
+            .ok_or(AmmError::InsufficientStake)?;
+        self.total_staked = total_staked.to_le_bytes();
+        Ok(())
+    }
+}
+
+// 用户在农场中的质押 PDA: [b"stake", farm, owner]
+// reward_per_share_paid 是上次结算时农场的 reward_per_share 差值乘以质押数量就是新增的奖励
+#[repr(C)]
+pub struct StakePosition {
+    owner: Address,
+    farm: Address,
+    amount: [u8; 8],
+    reward_per_share_paid: [u8; 16],
+    rewards_owed: [u8; 8],
+    bump: [u8; 1],
+}
+
+unsafe impl ProgramState for StakePosition {}
+
+impl StakePosition {
+    pub const LEN: usize = size_of::<StakePosition>();
+
+    #[inline(always)]
+    pub fn owner(&self) -> &Address {
+        &self.owner
+    }
+    #[inline(always)]
+    pub fn farm(&self) -> &Address {
+        &self.farm
+    }
+    #[inline(always)]
+    pub fn amount(&self) -> u64 {
+        u64::from_le_bytes(self.amount)
+    }
+    #[inline(always)]
+    pub fn reward_per_share_paid(&self) -> u128 {
+        u128::from_le_bytes(self.reward_per_share_paid)
+    }
+    #[inline(always)]
+    pub fn rewards_owed(&self) -> u64 {
+        u64::from_le_bytes(self.rewards_owed)
+    }
+    #[inline(always)]
+    pub fn bump(&self) -> [u8; 1] {
+        self.bump
+    }
+
+    #[inline(always)]
+    pub fn set_inner(
+        &mut self,
+        owner: Address,
+        farm: Address,
+        reward_per_share: u128,
+        bump: [u8; 1],
+    ) {
+        self.owner = owner;
+        self.farm = farm;
+        self.amount = 0u64.to_le_bytes();
+        self.reward_per_share_paid = reward_per_share.to_le_bytes();
+        self.rewards_owed = 0u64.to_le_bytes();
+        self.bump = bump;
+    }
+
+    // 按农场当前的 reward_per_share 结算奖励 质押数量变化之前必须先结算
+    #[inline(always)]
+    pub fn settle(&mut self, reward_per_share: u128) -> Result<(), ProgramError> {
+        let earned = mul_div(
+            self.amount() as u128,
+            reward_per_share - self.reward_per_share_paid(),
+            crate::Q64,
+        )?;
+        let rewards_owed = u64::try_from(earned)
+            .ok()
+            .and_then(|earned| earned.checked_add(self.rewards_owed()))
+            .ok_or(AmmError::CurveOverflow)?;
+        self.rewards_owed = rewards_owed.to_le_bytes();
+        self.reward_per_share_paid = reward_per_share.to_le_bytes();
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn deposit(&mut self, amount: u64) -> Result<(), ProgramError> {
+        let total = self
+            .amount()
+            .checked_add(amount)
+            .ok_or(AmmError::CurveOverflow)?;
+        self.amount = total.to_le_bytes();
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn withdraw(&mut self, amount: u64) -> Result<(), ProgramError> {
+        let total = self
+            .amount()
+            .checked_sub(amount)
+            .ok_or(AmmError::InsufficientStake)?;
+        self.amount = total.to_le_bytes();
+        Ok(())
+    }
+
+    // 领取之后清空欠付的奖励
+    #[inline(always)]
+    pub fn take_rewards(&mut self) -> u64 {
+        let rewards = self.rewards_owed();
+        self.rewards_owed = 0u64.to_le_bytes();
+        rewards
+    }
+}
+
+// 白名单池子里允许注入流动性的用户 PDA: [b"allow", config, user]
+// 账户存在就表示在白名单里 移出白名单时关闭账户
+#[repr(C)]
+pub struct AllowListEntry {
+    config: Address,
+    user: Address,
+    bump: [u8; 1],
+}
+
+unsafe impl ProgramState for AllowListEntry {}
+
+impl AllowListEntry {
+    pub const LEN: usize = size_of::<AllowListEntry>();
+
+    // 白名单账户只能由程序写入 记录的池子和用户一致就说明用户在这个池子的白名单里
+    #[inline(always)]
+    pub fn check(
+        account: &AccountView,
+        config: &Address,
+        user: &Address,
+    ) -> Result<(), ProgramError> {
+        let entry = Self::load(account).map_err(|_| AmmError::NotAllowListed)?;
+        if entry.config.ne(config) || entry.user.ne(user) {
+            return Err(AmmError::NotAllowListed.into());
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn config(&self) -> &Address {
+        &self.config
+    }
+    #[inline(always)]
+    pub fn user(&self) -> &Address {
+        &self.user
+    }
+    #[inline(always)]
+    pub fn bump(&self) -> [u8; 1] {
+        self.bump
+    }
+
+    #[inline(always)]
+    pub fn set_inner(&mut self, config: Address, user: Address, bump: [u8; 1]) {
+        self.config = config;
+        self.user = user;
+        self.bump = bump;
+    }
+}
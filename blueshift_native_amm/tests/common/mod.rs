@@ -0,0 +1,289 @@
+// 指令级测试共用的账户构造 和 benches/compute_units.rs 的布局相同
+// 这些测试需要编译好的程序 用 cargo test-sbf -- --include-ignored 运行
+#![allow(dead_code)]
+
+use blueshift_native_amm::{Config, ID};
+use mollusk_svm::{result::InstructionResult, Mollusk};
+use pinocchio::Address;
+use solana_account::Account;
+use solana_instruction::{AccountMeta, Instruction};
+use solana_pubkey::Pubkey;
+
+pub const SEED: u64 = 1;
+pub const FEE: u16 = 30;
+pub const DECIMALS: u8 = 6;
+pub const RESERVE: u64 = 1_000_000_000_000;
+pub const LP_SUPPLY: u64 = 1_000_000_000_000;
+pub const BALANCE: u64 = 1_000_000_000;
+
+// SPL Token 代币账户中 amount 和 state 的位置
+const AMOUNT_OFFSET: usize = 64;
+const STATE_OFFSET: usize = 108;
+
+pub fn mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&pubkey(&ID), "blueshift_native_amm");
+    mollusk_svm_programs_token::token::add_program(&mut mollusk);
+    mollusk_svm_programs_token::associated_token::add_program(&mut mollusk);
+    mollusk
+}
+
+pub fn pubkey(address: &Address) -> Pubkey {
+    Pubkey::new_from_array(address.to_bytes())
+}
+
+// 和关联代币账户程序相同的推导方式
+pub fn ata(wallet: &Address, mint: &Address) -> Address {
+    Address::find_program_address(
+        &[wallet.as_ref(), pinocchio_token::ID.as_ref(), mint.as_ref()],
+        &pinocchio_associated_token_account::ID,
+    )
+    .0
+}
+
+pub fn account(mollusk: &Mollusk, data: Vec<u8>, owner: &Address) -> Account {
+    Account {
+        lamports: mollusk.sysvars.rent.minimum_balance(data.len()),
+        data,
+        owner: pubkey(owner),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+// 有 SOL 的钱包 可以签名和支付租金
+pub fn wallet() -> Account {
+    Account::new(10_000_000_000, 0, &pubkey(&pinocchio_system::ID))
+}
+
+// 还没有创建的账户
+pub fn empty() -> Account {
+    Account::default()
+}
+
+// 程序账户的状态只由字节数组组成 直接按内存布局读写
+pub fn bytes<T>(state: &T) -> Vec<u8> {
+    unsafe { core::slice::from_raw_parts(state as *const T as *const u8, size_of::<T>()) }.to_vec()
+}
+
+pub fn state<T>(account: &Account) -> T {
+    assert_eq!(account.data.len(), size_of::<T>());
+    unsafe { core::ptr::read_unaligned(account.data.as_ptr() as *const T) }
+}
+
+// SPL Token 的铸币账户布局 mint_authority | supply | decimals | is_initialized | freeze_authority
+// 和 Initialize 创建的 LP 铸币账户一样 freeze_authority 与 mint_authority 相同
+pub fn mint(mollusk: &Mollusk, authority: &Address, supply: u64) -> Account {
+    let mut data = vec![0u8; 82];
+    data[0..4].copy_from_slice(&1u32.to_le_bytes());
+    data[4..36].copy_from_slice(authority.as_ref());
+    data[36..44].copy_from_slice(&supply.to_le_bytes());
+    data[44] = DECIMALS;
+    data[45] = 1;
+    data[46..50].copy_from_slice(&1u32.to_le_bytes());
+    data[50..82].copy_from_slice(authority.as_ref());
+    account(mollusk, data, &pinocchio_token::ID)
+}
+
+// SPL Token 的代币账户布局 mint | owner | amount | delegate | state | ...
+pub fn token_account(mollusk: &Mollusk, mint: &Address, owner: &Address, amount: u64) -> Account {
+    let mut data = vec![0u8; 165];
+    data[0..32].copy_from_slice(mint.as_ref());
+    data[32..64].copy_from_slice(owner.as_ref());
+    data[AMOUNT_OFFSET..AMOUNT_OFFSET + 8].copy_from_slice(&amount.to_le_bytes());
+    data[STATE_OFFSET] = 1;
+    account(mollusk, data, &pinocchio_token::ID)
+}
+
+pub fn get<'a>(result: &'a InstructionResult, address: &Address) -> &'a Account {
+    result.get_account(&pubkey(address)).unwrap()
+}
+
+pub fn amount(result: &InstructionResult, address: &Address) -> u64 {
+    let data = &get(result, address).data;
+    u64::from_le_bytes(data[AMOUNT_OFFSET..AMOUNT_OFFSET + 8].try_into().unwrap())
+}
+
+// 1 是正常 2 是冻结
+pub fn token_state(result: &InstructionResult, address: &Address) -> u8 {
+    get(result, address).data[STATE_OFFSET]
+}
+
+pub fn meta(address: &Address, writable: bool, signer: bool) -> AccountMeta {
+    match writable {
+        true => AccountMeta::new(pubkey(address), signer),
+        false => AccountMeta::new_readonly(pubkey(address), signer),
+    }
+}
+
+pub fn instruction(data: Vec<u8>, metas: Vec<AccountMeta>) -> Instruction {
+    Instruction::new_with_bytes(pubkey(&ID), &data, metas)
+}
+
+// 一个已经注入流动性的恒定乘积池 用户是池子的 authority 持有两种代币和 LP
+pub struct Pool {
+    pub user: Address,
+    pub config: Address,
+    pub mint_x: Address,
+    pub mint_y: Address,
+    pub mint_lp: Address,
+    pub vault_x: Address,
+    pub vault_y: Address,
+    pub user_x: Address,
+    pub user_y: Address,
+    pub user_lp: Address,
+    pub locked_lp: Address,
+    pub accounts: Vec<(Pubkey, Account)>,
+}
+
+impl Pool {
+    pub fn new(mollusk: &Mollusk) -> Self {
+        let user = Address::new_from_array([9; 32]);
+        let mint_x = Address::new_from_array([1; 32]);
+        let mint_y = Address::new_from_array([2; 32]);
+        let (config, bump) = Address::find_program_address(
+            &[
+                b"config",
+                &SEED.to_le_bytes(),
+                mint_x.as_ref(),
+                mint_y.as_ref(),
+            ],
+            &ID,
+        );
+        let (mint_lp, _) = Address::find_program_address(&[b"mint_lp", config.as_ref()], &ID);
+        let (vault_x, vault_y) = (ata(&config, &mint_x), ata(&config, &mint_y));
+        let (user_x, user_y, user_lp) = (
+            ata(&user, &mint_x),
+            ata(&user, &mint_y),
+            ata(&user, &mint_lp),
+        );
+        let locked_lp = ata(&config, &mint_lp);
+
+        // Config 只由字节数组组成 全零就是一个合法的值
+        let mut state: Config = unsafe { core::mem::zeroed() };
+        state
+            .set_inner(SEED, user, mint_x, mint_y, FEE, [bump])
+            .unwrap();
+        state.set_vaults(vault_x, vault_y);
+
+        let accounts = vec![
+            (pubkey(&user), wallet()),
+            (pubkey(&config), account(mollusk, bytes(&state), &ID)),
+            (pubkey(&mint_x), mint(mollusk, &user, u64::MAX / 2)),
+            (pubkey(&mint_y), mint(mollusk, &user, u64::MAX / 2)),
+            (pubkey(&mint_lp), mint(mollusk, &config, LP_SUPPLY)),
+            (
+                pubkey(&vault_x),
+                token_account(mollusk, &mint_x, &config, RESERVE),
+            ),
+            (
+                pubkey(&vault_y),
+                token_account(mollusk, &mint_y, &config, RESERVE),
+            ),
+            (
+                pubkey(&user_x),
+                token_account(mollusk, &mint_x, &user, BALANCE),
+            ),
+            (
+                pubkey(&user_y),
+                token_account(mollusk, &mint_y, &user, BALANCE),
+            ),
+            (
+                pubkey(&user_lp),
+                token_account(mollusk, &mint_lp, &user, BALANCE),
+            ),
+            (
+                pubkey(&locked_lp),
+                token_account(mollusk, &mint_lp, &config, 0),
+            ),
+            mollusk_svm_programs_token::token::keyed_account(),
+            mollusk_svm_programs_token::associated_token::keyed_account(),
+            mollusk_svm::program::keyed_account_for_system_program(),
+        ];
+        Self {
+            user,
+            config,
+            mint_x,
+            mint_y,
+            mint_lp,
+            vault_x,
+            vault_y,
+            user_x,
+            user_y,
+            user_lp,
+            locked_lp,
+            accounts,
+        }
+    }
+
+    // 修改池子的配置 例如开启白名单
+    pub fn configure(mut self, f: impl FnOnce(&mut Config)) -> Self {
+        let config = pubkey(&self.config);
+        let (_, account) = self
+            .accounts
+            .iter_mut()
+            .find(|(address, _)| *address == config)
+            .unwrap();
+        let mut state: Config = state(account);
+        f(&mut state);
+        account.data = bytes(&state);
+        self
+    }
+
+    // 用 1_000_000 个代币 X 或 Y 交换
+    pub fn swap(&self, is_x: bool) -> Instruction {
+        let mut data = vec![3, is_x as u8];
+        data.extend_from_slice(&1_000_000u64.to_le_bytes());
+        data.extend_from_slice(&1u64.to_le_bytes());
+        data.extend_from_slice(&i64::MAX.to_le_bytes());
+        instruction(
+            data,
+            vec![
+                meta(&self.user, true, true),
+                meta(&self.user_x, true, false),
+                meta(&self.user_y, true, false),
+                meta(&self.vault_x, true, false),
+                meta(&self.vault_y, true, false),
+                meta(&self.config, true, false),
+                meta(&self.mint_x, false, false),
+                meta(&self.mint_y, false, false),
+                meta(&pinocchio_token::ID, false, false),
+                meta(&pinocchio_token::ID, false, false),
+            ],
+        )
+    }
+
+    // Deposit 注入 1_000_000 LP 对应的代币 remaining 是额外账户 例如白名单账户
+    pub fn deposit(&self, remaining: &[Address]) -> Instruction {
+        let mut data = vec![1];
+        data.extend_from_slice(&1_000_000u64.to_le_bytes());
+        data.extend_from_slice(&u64::MAX.to_le_bytes());
+        data.extend_from_slice(&u64::MAX.to_le_bytes());
+        data.extend_from_slice(&i64::MAX.to_le_bytes());
+        let mut metas = vec![
+            meta(&self.user, true, true),
+            meta(&self.mint_lp, true, false),
+            meta(&self.vault_x, true, false),
+            meta(&self.vault_y, true, false),
+            meta(&self.user_x, true, false),
+            meta(&self.user_y, true, false),
+            meta(&self.user_lp, true, false),
+            meta(&self.config, true, false),
+            meta(&pinocchio_token::ID, false, false),
+            meta(&self.mint_x, false, false),
+            meta(&self.mint_y, false, false),
+            meta(&pinocchio_token::ID, false, false),
+            meta(&pinocchio_token::ID, false, false),
+            meta(&self.locked_lp, true, false),
+            meta(&pinocchio_system::ID, false, false),
+            meta(&pinocchio_associated_token_account::ID, false, false),
+        ];
+        metas.extend(remaining.iter().map(|address| meta(address, false, false)));
+        instruction(data, metas)
+    }
+
+    // 加入测试需要的其他账户
+    pub fn with(mut self, address: &Address, account: Account) -> Self {
+        self.accounts.push((pubkey(address), account));
+        self
+    }
+}
@@ -0,0 +1,290 @@
+use blueshift_native_amm::{
+    amount_x_delta, amount_y_delta, compute_swap_step, first_tick_array_start, sqrt_price_at_tick,
+    tick_at_sqrt_price, AmmError, ClPool, LiquidityPair, Position, TickArray, MAX_SQRT_PRICE,
+    MAX_TICK, MIN_SQRT_PRICE, MIN_TICK, Q64,
+};
+use pinocchio::{error::ProgramError, Address};
+
+const SPACING: u16 = 10;
+
+fn config() -> Address {
+    Address::new_from_array([1; 32])
+}
+
+fn pool(tick: i32) -> ClPool {
+    // ClPool TickArray Position 只由地址和字节数组组成 全零就是一个合法的值
+    let mut pool: ClPool = unsafe { core::mem::zeroed() };
+    pool.set_inner(
+        config(),
+        sqrt_price_at_tick(tick).unwrap(),
+        tick,
+        SPACING,
+        [255],
+    );
+    pool
+}
+
+fn tick_array(start_tick: i32) -> Box<TickArray> {
+    let mut array: Box<TickArray> = Box::new(unsafe { core::mem::zeroed() });
+    array.set_inner(config(), start_tick, [255]);
+    array
+}
+
+fn position(tick_lower: i32, tick_upper: i32) -> Position {
+    let mut position: Position = unsafe { core::mem::zeroed() };
+    position.set_inner(
+        Address::new_from_array([2; 32]),
+        config(),
+        tick_lower,
+        tick_upper,
+        [255],
+    );
+    position
+}
+
+// 区间的两个边界分别在 lower 和 upper 两个数组里
+fn modify(
+    pool: &mut ClPool,
+    position: &mut Position,
+    lower: &mut TickArray,
+    upper: &mut TickArray,
+    delta: i128,
+) -> Result<(u64, u64), ProgramError> {
+    let (tick_lower, tick_upper) = (position.tick_lower(), position.tick_upper());
+    pool.modify_position(
+        position,
+        lower.tick_mut(tick_lower, SPACING)?,
+        upper.tick_mut(tick_upper, SPACING)?,
+        delta,
+    )
+}
+
+#[test]
+fn sqrt_price_at_tick_bounds() {
+    assert_eq!(sqrt_price_at_tick(0).unwrap(), Q64);
+    assert_eq!(sqrt_price_at_tick(MIN_TICK).unwrap(), MIN_SQRT_PRICE);
+    assert_eq!(sqrt_price_at_tick(MAX_TICK).unwrap(), MAX_SQRT_PRICE);
+    assert_eq!(
+        sqrt_price_at_tick(MAX_TICK + 1),
+        Err(AmmError::InvalidTick.into())
+    );
+    assert_eq!(
+        sqrt_price_at_tick(MIN_TICK - 1),
+        Err(AmmError::InvalidTick.into())
+    );
+    // 1.0001^(1/2) 约等于 1.00005
+    let one = sqrt_price_at_tick(1).unwrap();
+    assert!(one > Q64 && one - Q64 < Q64 / 19_000 && one - Q64 > Q64 / 21_000);
+}
+
+#[test]
+fn sqrt_price_is_monotonic_and_round_trips() {
+    for tick in [
+        MIN_TICK,
+        -200_000,
+        -1_001,
+        -1,
+        0,
+        1,
+        999,
+        123_456,
+        MAX_TICK - 1,
+    ] {
+        let price = sqrt_price_at_tick(tick).unwrap();
+        assert!(sqrt_price_at_tick(tick + 1).unwrap() > price);
+        assert_eq!(tick_at_sqrt_price(price).unwrap(), tick);
+        // 两个 tick 之间的价格属于较小的那个
+        assert_eq!(tick_at_sqrt_price(price + 1).unwrap(), tick);
+    }
+    assert_eq!(
+        tick_at_sqrt_price(MIN_SQRT_PRICE - 1),
+        Err(AmmError::InvalidTick.into())
+    );
+}
+
+#[test]
+fn amount_deltas_round_against_the_user() {
+    let (a, b) = (
+        sqrt_price_at_tick(-100).unwrap(),
+        sqrt_price_at_tick(100).unwrap(),
+    );
+    let liquidity = 1_000_000_007u128;
+    let x_up = amount_x_delta(a, b, liquidity, true).unwrap();
+    let x_down = amount_x_delta(b, a, liquidity, false).unwrap();
+    let y_up = amount_y_delta(a, b, liquidity, true).unwrap();
+    let y_down = amount_y_delta(b, a, liquidity, false).unwrap();
+    assert!(x_up == x_down + 1 || x_up == x_down + 2);
+    assert_eq!(y_up, y_down + 1);
+    // 价格区间关于 1 对称 两种代币的数量相同
+    assert!(x_down.abs_diff(y_down) <= 1);
+}
+
+#[test]
+fn swap_step_stops_at_target_or_spends_everything() {
+    let price = Q64;
+    let target = sqrt_price_at_tick(-10).unwrap();
+    let liquidity = 1_000_000_000u128;
+
+    // 数量很小 到不了目标价格 剩下的全部算作手续费
+    let (next, amount_in, amount_out, fee) =
+        compute_swap_step(price, target, liquidity, 1_000, 30).unwrap();
+    assert!(next < price && next > target);
+    assert_eq!(amount_in + fee, 1_000);
+    assert!(fee >= 3);
+    assert!(amount_out < amount_in);
+
+    // 数量足够 正好停在目标价格
+    let (next, amount_in, _, fee) =
+        compute_swap_step(price, target, liquidity, 10_000_000, 30).unwrap();
+    assert_eq!(next, target);
+    assert_eq!(
+        amount_in as u128,
+        amount_x_delta(target, price, liquidity, true).unwrap()
+    );
+    assert!(amount_in + fee < 10_000_000);
+}
+
+#[test]
+fn first_tick_array_follows_swap_direction() {
+    // 一个数组覆盖 32 * 10 = 320 个 tick
+    assert_eq!(first_tick_array_start(5, SPACING, LiquidityPair::X), 0);
+    assert_eq!(first_tick_array_start(-5, SPACING, LiquidityPair::X), -320);
+    assert_eq!(first_tick_array_start(305, SPACING, LiquidityPair::Y), 0);
+    // 当前 tick 在数组的最后一格 价格上升时下一个 tick 在下一个数组
+    assert_eq!(first_tick_array_start(315, SPACING, LiquidityPair::Y), 320);
+}
+
+#[test]
+fn in_range_position_earns_swap_fees() {
+    let mut pool = pool(0);
+    let mut lower = tick_array(-320);
+    let mut upper = tick_array(0);
+    let mut position = position(-100, 100);
+    let (x, y) = modify(
+        &mut pool,
+        &mut position,
+        &mut lower,
+        &mut upper,
+        1_000_000_000,
+    )
+    .unwrap();
+    assert!(x > 0 && y > 0);
+    assert_eq!(pool.liquidity(), 1_000_000_000);
+
+    // 价格下降 依次需要当前数组和左边的数组
+    let result = {
+        let mut arrays = [Some(&mut *upper), Some(&mut *lower)];
+        pool.swap(&mut arrays, LiquidityPair::X, 100_000, 30, 0)
+            .unwrap()
+    };
+    assert!(result.amount_out > 0 && result.amount_out < 100_000);
+    assert!(result.fee >= 300);
+    assert_eq!(pool.tick(), tick_at_sqrt_price(pool.sqrt_price()).unwrap());
+    assert!(pool.tick() < 0);
+
+    // 领取手续费 向下取整最多少 1
+    modify(&mut pool, &mut position, &mut lower, &mut upper, 0).unwrap();
+    assert!(position.fees_owed_x() + 1 >= result.fee && position.fees_owed_x() <= result.fee);
+    assert_eq!(position.fees_owed_y(), 0);
+
+    // 全部取出 拿回的代币不会多于存入的加上交换进来的
+    let (x_out, y_out) = modify(
+        &mut pool,
+        &mut position,
+        &mut lower,
+        &mut upper,
+        -1_000_000_000,
+    )
+    .unwrap();
+    assert!(x_out + position.fees_owed_x() <= x + 100_000);
+    assert!(y_out + result.amount_out <= y);
+    assert_eq!(pool.liquidity(), 0);
+    assert!(!lower.tick_mut(-100, SPACING).unwrap().is_initialized());
+}
+
+#[test]
+fn out_of_range_position_earns_nothing() {
+    let mut pool = pool(0);
+    let mut lower = tick_array(-320);
+    let mut upper = tick_array(0);
+    let mut active = position(-100, 100);
+    modify(
+        &mut pool,
+        &mut active,
+        &mut lower,
+        &mut upper,
+        1_000_000_000,
+    )
+    .unwrap();
+
+    // 区间在当前价格上方 只需要存入 X 两个边界在同一个数组里
+    let mut idle = position(200, 300);
+    let (x, y) = {
+        let (tick_lower, tick_upper) = upper.tick_pair_mut(200, 300, SPACING).unwrap();
+        pool.modify_position(&mut idle, tick_lower, tick_upper, 1_000_000)
+            .unwrap()
+    };
+    assert!(x > 0);
+    assert_eq!(y, 0);
+    assert_eq!(pool.liquidity(), 1_000_000_000);
+
+    // 价格上升但没有到达 200 只有在区间内的头寸得到手续费
+    {
+        let mut arrays = [Some(&mut *upper)];
+        pool.swap(&mut arrays, LiquidityPair::Y, 5_000_000, 30, 0)
+            .unwrap();
+    }
+    assert!(pool.tick() > 0 && pool.tick() < 200);
+    let (tick_lower, tick_upper) = upper.tick_pair_mut(200, 300, SPACING).unwrap();
+    pool.modify_position(&mut idle, tick_lower, tick_upper, 0)
+        .unwrap();
+    assert_eq!((idle.fees_owed_x(), idle.fees_owed_y()), (0, 0));
+    modify(&mut pool, &mut active, &mut lower, &mut upper, 0).unwrap();
+    assert!(active.fees_owed_y() > 0);
+}
+
+#[test]
+fn swap_crosses_ticks_and_needs_every_tick_array() {
+    let mut pool = pool(0);
+    let mut lower = tick_array(-320);
+    let mut upper = tick_array(0);
+    let mut position = position(-100, 100);
+    modify(
+        &mut pool,
+        &mut position,
+        &mut lower,
+        &mut upper,
+        1_000_000_000,
+    )
+    .unwrap();
+
+    // 穿过 -100 之后没有流动性 价格一直走到传入的最后一个数组也用不完
+    let mut far = tick_array(-640);
+    let mut arrays = [Some(&mut *upper), Some(&mut *lower), Some(&mut *far)];
+    assert_eq!(
+        pool.swap(&mut arrays, LiquidityPair::X, 100_000_000, 30, 0),
+        Err(AmmError::InvalidTickArray.into())
+    );
+
+    // 价格回升时从左向右穿过 -100 流动性恢复
+    let mut pool_back = self::pool(-150);
+    let mut lower = tick_array(-320);
+    let mut upper = tick_array(0);
+    let mut position = self::position(-100, 100);
+    modify(
+        &mut pool_back,
+        &mut position,
+        &mut lower,
+        &mut upper,
+        1_000_000_000,
+    )
+    .unwrap();
+    assert_eq!(pool_back.liquidity(), 0);
+    let mut arrays = [Some(&mut *lower), Some(&mut *upper)];
+    let result = pool_back
+        .swap(&mut arrays, LiquidityPair::Y, 1_000_000, 30, 0)
+        .unwrap();
+    assert!(pool_back.tick() >= -100);
+    assert_eq!(pool_back.liquidity(), 1_000_000_000);
+    assert!(result.amount_out > 0);
+}
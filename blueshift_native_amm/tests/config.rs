@@ -0,0 +1,190 @@
+use blueshift_native_amm::{
+    AllowListEntry, AmmError, ClPool, Config, Factory, Farm, Oracle, Order, Pair, Position,
+    RegistryPage, StakePosition, TickArray, CONFIG_LEGACY_LEN, CONFIG_RESERVED_LEN,
+    CONFIG_V1_LEN, CONFIG_VERSION,
+};
+use pinocchio::{AccountView, Address};
+
+fn config() -> Config {
+    // Config 只由字节数组组成 全零就是一个合法的值
+    let mut config: Config = unsafe { core::mem::zeroed() };
+    config
+        .set_inner(
+            42,
+            Address::new_from_array([1; 32]),
+            Address::new_from_array([2; 32]),
+            Address::new_from_array([3; 32]),
+            30,
+            [254],
+        )
+        .unwrap();
+    config
+}
+
+fn vault(byte: u8) -> Address {
+    Address::new_from_array([byte; 32])
+}
+
+fn bytes(config: &Config) -> Vec<u8> {
+    unsafe { core::slice::from_raw_parts(config as *const Config as *const u8, Config::LEN) }
+        .to_vec()
+}
+
+#[test]
+fn version_and_reserved_bytes_close_the_layout() {
+    assert_eq!(
+        Config::VERSION_OFFSET + 1 + 32 * 2 + 1 + CONFIG_RESERVED_LEN,
+        Config::LEN
+    );
+    assert_eq!(config().version(), CONFIG_VERSION);
+}
+
+#[test]
+fn legacy_layout_is_a_prefix() {
+    // 最早的布局: state | seed | authority | mint_x | mint_y | fee | config_bump
+    let data = bytes(&config());
+    assert_eq!(data[0], 1);
+    assert_eq!(data[1..9], 42u64.to_le_bytes());
+    assert_eq!(data[9..41], [1; 32]);
+    assert_eq!(data[41..73], [2; 32]);
+    assert_eq!(data[73..105], [3; 32]);
+    assert_eq!(data[105..107], 30u16.to_le_bytes());
+    assert_eq!(data[CONFIG_LEGACY_LEN - 1], 254);
+}
+
+#[test]
+fn migrate_upgrades_legacy_account_in_place() {
+    // 旧账户扩容之后新增的字节全是 0
+    let mut data = bytes(&config())[..CONFIG_LEGACY_LEN].to_vec();
+    data.resize(Config::LEN, 0);
+    assert_eq!(Config::check_version(&data), Ok(()));
+
+    let config = unsafe { Config::from_bytes_unchecked_mut(&mut data) };
+    assert_eq!(config.version(), 0);
+    config.migrate(vault(4), vault(5)).unwrap();
+    assert_eq!(config.version(), CONFIG_VERSION);
+    assert_eq!(config.vault_x(), &vault(4));
+    assert_eq!(config.vault_y(), &vault(5));
+    assert_eq!(config.seed(), 42);
+    assert_eq!(config.fee(), 30);
+    assert_eq!(config.config_bump(), [254]);
+    assert_eq!(config.mint_y(), &Address::new_from_array([3; 32]));
+    // 之后新增的字段默认都是 0
+    assert_eq!(config.protocol_fees_x(), 0);
+    assert_eq!(config.flash_loan(), 0);
+    assert_eq!(config.curve_type(), 0);
+    assert!(!config.permissioned());
+}
+
+#[test]
+fn migrate_fills_vaults_of_version_1_account() {
+    // 版本 1 的 64 字节预留在版本 2 里用来记录金库地址 长度不变
+    let mut source = config();
+    source.set_vaults(vault(0), vault(0));
+    let mut data = bytes(&source)[..CONFIG_V1_LEN].to_vec();
+    data[Config::VERSION_OFFSET] = 1;
+    data.resize(Config::LEN, 0);
+
+    let config = unsafe { Config::from_bytes_unchecked_mut(&mut data) };
+    config.migrate(vault(4), vault(5)).unwrap();
+    assert_eq!(config.version(), CONFIG_VERSION);
+    assert_eq!(config.vault_x(), &vault(4));
+    assert_eq!(config.vault_y(), &vault(5));
+    assert_eq!(config.seed(), 42);
+}
+
+#[test]
+fn version_2_account_grows_into_open_pool() {
+    // 版本 3 追加的字节扩容之后是 0 池子默认不开启白名单
+    let mut source = config();
+    source.set_vaults(vault(4), vault(5));
+    source.set_permissioned(true);
+    let mut data = bytes(&source)[..CONFIG_V1_LEN].to_vec();
+    data[Config::VERSION_OFFSET] = 2;
+    data.resize(Config::LEN, 0);
+
+    let config = unsafe { Config::from_bytes_unchecked_mut(&mut data) };
+    config.migrate(vault(6), vault(7)).unwrap();
+    assert_eq!(config.version(), CONFIG_VERSION);
+    assert_eq!(config.vault_x(), &vault(4));
+    assert!(!config.permissioned());
+}
+
+#[test]
+fn newer_versions_are_rejected() {
+    let mut data = bytes(&config());
+    data[Config::VERSION_OFFSET] = CONFIG_VERSION + 1;
+    assert_eq!(
+        Config::check_version(&data),
+        Err(AmmError::UnsupportedConfigVersion.into())
+    );
+    let config = unsafe { Config::from_bytes_unchecked_mut(&mut data) };
+    assert_eq!(
+        config.migrate(vault(4), vault(5)),
+        Err(AmmError::UnsupportedConfigVersion.into())
+    );
+}
+
+#[test]
+fn vaults_are_checked_by_address() {
+    let mut config = config();
+    config.set_vaults(vault(4), vault(5));
+    assert_eq!(config.check_vaults(&vault(4), &vault(5)), Ok(()));
+    assert_eq!(
+        config.check_vaults(&vault(5), &vault(4)),
+        Err(AmmError::InvalidVault.into())
+    );
+    assert_eq!(config.check_vault(true, &vault(4)), Ok(()));
+    assert_eq!(config.check_vault(false, &vault(5)), Ok(()));
+    assert_eq!(
+        config.check_vault(false, &vault(4)),
+        Err(AmmError::InvalidVault.into())
+    );
+
+    // 已经是当前版本的账户 再迁移也不会改写金库地址
+    config.migrate(vault(6), vault(7)).unwrap();
+    assert_eq!(config.vault_x(), &vault(4));
+    assert_eq!(config.vault_y(), &vault(5));
+}
+
+#[test]
+fn permissioned_pools_require_an_allow_list_entry() {
+    let mut config = config();
+    let pool = Address::new_from_array([8; 32]);
+    let user = Address::new_from_array([9; 32]);
+    let none: &[AccountView] = &[];
+    // 默认不开启白名单 不需要额外账户
+    assert_eq!(config.check_allow_listed(&pool, &user, none), Ok(()));
+
+    config.set_permissioned(true);
+    assert!(config.permissioned());
+    assert_eq!(
+        config.check_allow_listed(&pool, &user, none),
+        Err(AmmError::NotAllowListed.into())
+    );
+
+    config.set_permissioned(false);
+    assert_eq!(config.check_allow_listed(&pool, &user, none), Ok(()));
+}
+
+#[test]
+fn other_accounts_never_look_like_a_legacy_config() {
+    // MigrateConfig 只按长度识别旧布局 其他程序账户的长度不能和它相同
+    for len in [
+        Oracle::LEN,
+        Factory::LEN,
+        Pair::LEN,
+        RegistryPage::LEN,
+        Order::LEN,
+        ClPool::LEN,
+        TickArray::LEN,
+        Position::LEN,
+        Farm::LEN,
+        StakePosition::LEN,
+        AllowListEntry::LEN,
+    ] {
+        assert_ne!(len, CONFIG_LEGACY_LEN);
+        assert_ne!(len, CONFIG_V1_LEN);
+        assert_ne!(len, Config::LEN);
+    }
+}
@@ -0,0 +1,283 @@
+use blueshift_native_amm::{isqrt, ConstantProduct, LiquidityPair, MINIMUM_LIQUIDITY};
+
+#[test]
+fn split_fee_without_protocol_share_goes_to_lp() {
+    assert_eq!(ConstantProduct::split_fee(30, 0).unwrap(), (30, 0));
+}
+
+#[test]
+fn split_fee_with_protocol_share() {
+    // 协议抽取交换费的 20%
+    assert_eq!(ConstantProduct::split_fee(30, 2_000).unwrap(), (24, 6));
+    // 全部交换费归协议
+    assert_eq!(ConstantProduct::split_fee(30, 10_000).unwrap(), (0, 30));
+}
+
+#[test]
+fn split_fee_rounds_protocol_share_down() {
+    // 3 * 3_333 / 10_000 = 0.9999 协议部分向下取整 零头留给 LP
+    assert_eq!(ConstantProduct::split_fee(3, 3_333).unwrap(), (3, 0));
+    assert_eq!(ConstantProduct::split_fee(7, 5_000).unwrap(), (4, 3));
+}
+
+#[test]
+fn swap_fee_is_split_between_lp_and_protocol() {
+    let mut curve = ConstantProduct::init(1_000_000, 1_000_000, 30).unwrap();
+    let (deposit, fee, _) = curve.swap(LiquidityPair::X, 10_000, 1).unwrap();
+    assert_eq!(deposit, 10_000);
+    assert_eq!(fee, 30);
+
+    let (lp, protocol) = ConstantProduct::split_fee(fee, 2_000).unwrap();
+    assert_eq!(lp + protocol, fee);
+    assert_eq!(protocol, 6);
+}
+
+#[test]
+fn swap_charges_the_same_fee_in_both_directions() {
+    let mut curve_x = ConstantProduct::init(1_000_000, 1_000_000, 30).unwrap();
+    let mut curve_y = ConstantProduct::init(1_000_000, 1_000_000, 30).unwrap();
+
+    let swap_x = curve_x.swap(LiquidityPair::X, 10_000, 1).unwrap();
+    let swap_y = curve_y.swap(LiquidityPair::Y, 10_000, 1).unwrap();
+    assert_eq!(swap_x, swap_y);
+}
+
+#[test]
+fn swap_exact_out_charges_enough_for_the_requested_amount() {
+    let mut exact_out = ConstantProduct::init(1_000_000, 2_000_000, 30).unwrap();
+    let (amount_in, fee, amount_out) = exact_out
+        .swap_exact_out(LiquidityPair::X, 10_000, u64::MAX)
+        .unwrap();
+    assert_eq!(amount_out, 10_000);
+    assert!(fee > 0);
+
+    // 按反推出来的支付数量正向交换 得到的数量不能少于请求的数量
+    let mut exact_in = ConstantProduct::init(1_000_000, 2_000_000, 30).unwrap();
+    let (_, _, forward_out) = exact_in.swap(LiquidityPair::X, amount_in, 1).unwrap();
+    assert!(forward_out >= amount_out);
+}
+
+#[test]
+fn swap_exact_out_enforces_max_in() {
+    let mut curve = ConstantProduct::init(1_000_000, 1_000_000, 30).unwrap();
+    let (amount_in, _, _) = curve
+        .swap_exact_out(LiquidityPair::Y, 10_000, u64::MAX)
+        .unwrap();
+
+    let mut curve = ConstantProduct::init(1_000_000, 1_000_000, 30).unwrap();
+    assert!(curve
+        .swap_exact_out(LiquidityPair::Y, 10_000, amount_in - 1)
+        .is_err());
+    assert!(curve
+        .swap_exact_out(LiquidityPair::Y, 10_000, amount_in)
+        .is_ok());
+}
+
+#[test]
+fn swap_exact_out_cannot_drain_the_pool() {
+    let mut curve = ConstantProduct::init(1_000_000, 1_000_000, 30).unwrap();
+    assert!(curve
+        .swap_exact_out(LiquidityPair::X, 1_000_000, u64::MAX)
+        .is_err());
+}
+
+#[test]
+fn price_impact_grows_with_trade_size() {
+    let small = ConstantProduct::price_impact(1_000_000, 1_000_000, 1_000, 999).unwrap();
+    let mut curve = ConstantProduct::init(1_000_000, 1_000_000, 0).unwrap();
+    let (_, _, out) = curve.swap(LiquidityPair::X, 100_000, 1).unwrap();
+    let large = ConstantProduct::price_impact(1_000_000, 1_000_000, 100_000, out).unwrap();
+    assert_eq!(small, 10);
+    // 10% 的储备量大约产生 9% 的价格影响
+    assert_eq!(large, 909);
+}
+
+// 简单的 xorshift 伪随机数 保证每次运行的序列相同 失败时可以复现
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn range(&mut self, low: u64, high: u64) -> u64 {
+        low + self.next() % (high - low)
+    }
+}
+
+#[test]
+fn k_never_decreases_across_random_swaps() {
+    for seed in 1..=64u64 {
+        let mut rng = Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        let fee = rng.range(0, 1_000) as u16;
+        let mut curve = ConstantProduct::init(
+            rng.range(1_000, 1_000_000_000_000),
+            rng.range(1_000, 1_000_000_000_000),
+            fee,
+        )
+        .unwrap();
+
+        for _ in 0..256 {
+            let k_before = curve.k();
+            let p = match rng.next() % 2 {
+                0 => LiquidityPair::X,
+                _ => LiquidityPair::Y,
+            };
+            let reserve_out = match p {
+                LiquidityPair::X => curve.y(),
+                LiquidityPair::Y => curve.x(),
+            };
+            let result = match rng.next() % 2 {
+                0 => curve.swap(p, rng.range(1, reserve_out.max(2)), 0),
+                _ => curve.swap_exact_out(p, rng.range(1, reserve_out.max(2)), u64::MAX),
+            };
+            if result.is_ok() {
+                assert!(curve.k() >= k_before, "k decreased with seed {}", seed);
+            }
+        }
+    }
+}
+
+#[test]
+fn round_trip_swap_never_profits() {
+    for seed in 1..=64u64 {
+        let mut rng = Rng(seed.wrapping_mul(0xD1B5_4A32_D192_ED03));
+        let mut curve = ConstantProduct::init(
+            rng.range(1_000, 1_000_000_000),
+            rng.range(1_000, 1_000_000_000),
+            rng.range(0, 100) as u16,
+        )
+        .unwrap();
+        let amount = rng.range(1, curve.x());
+        let (_, _, y_out) = curve.swap(LiquidityPair::X, amount, 0).unwrap();
+        if y_out == 0 {
+            continue;
+        }
+        let (_, _, x_back) = curve.swap(LiquidityPair::Y, y_out, 0).unwrap();
+        assert!(x_back <= amount, "round trip profited with seed {}", seed);
+    }
+}
+
+#[test]
+fn deposit_rounds_up_and_withdraw_rounds_down() {
+    // 1/3 的流动性 需要存入的数量向上取整 取回的数量向下取整
+    let (x, y) = ConstantProduct::xy_deposit_amounts_from_l(1_000, 2_000, 3_000, 1_000).unwrap();
+    assert_eq!((x, y), (334, 667));
+    let (x, y) = ConstantProduct::xy_withdraw_amounts_from_l(1_000, 2_000, 3_000, 1_000).unwrap();
+    assert_eq!((x, y), (333, 666));
+}
+
+#[test]
+fn deposit_then_withdraw_never_profits() {
+    for seed in 1..=64u64 {
+        let mut rng = Rng(seed.wrapping_mul(0xA076_1D64_78BD_642F));
+        let x = rng.range(1, 1_000_000_000_000);
+        let y = rng.range(1, 1_000_000_000_000);
+        let l = rng.range(1, 1_000_000_000_000);
+        let a = rng.range(1, l);
+
+        let (dx, dy) = ConstantProduct::xy_deposit_amounts_from_l(x, y, l, a).unwrap();
+        let (wx, wy) =
+            ConstantProduct::xy_withdraw_amounts_from_l(x + dx, y + dy, l + a, a).unwrap();
+        assert!(
+            wx <= dx && wy <= dy,
+            "deposit round trip profited with seed {}",
+            seed
+        );
+    }
+}
+
+#[test]
+fn isqrt_rounds_down() {
+    assert_eq!(isqrt(0), 0);
+    assert_eq!(isqrt(1), 1);
+    assert_eq!(isqrt(15), 3);
+    assert_eq!(isqrt(16), 4);
+    assert_eq!(isqrt(u64::MAX as u128 * u64::MAX as u128), u64::MAX);
+    for seed in 1..=64u64 {
+        let mut rng = Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        let n = rng.range(1, u64::MAX) as u128 * rng.range(1, u64::MAX) as u128;
+        let r = isqrt(n) as u128;
+        assert!(
+            r * r <= n && (r + 1) * (r + 1) > n,
+            "isqrt failed with seed {}",
+            seed
+        );
+    }
+}
+
+#[test]
+fn initial_deposit_locks_minimum_liquidity() {
+    // sqrt(4_000_000 * 1_000_000) = 2_000_000 其中 MINIMUM_LIQUIDITY 被锁定
+    assert_eq!(
+        ConstantProduct::l_from_initial_xy(4_000_000, 1_000_000).unwrap(),
+        2_000_000 - MINIMUM_LIQUIDITY
+    );
+    // 流动性不足以覆盖锁定的部分
+    assert!(ConstantProduct::l_from_initial_xy(1_000, 1_000).is_err());
+    assert!(ConstantProduct::l_from_initial_xy(0, 1_000_000).is_err());
+}
+
+#[test]
+fn zap_swap_amount_leaves_both_sides_in_pool_ratio() {
+    for seed in 1..=64u64 {
+        let mut rng = Rng(seed.wrapping_mul(0xD6E8_FEB8_6659_FD93));
+        let x = rng.range(1_000, 1_000_000_000_000);
+        let y = rng.range(1_000, 1_000_000_000_000);
+        let a = rng.range(1, x);
+        let curve = ConstantProduct::init(x, y, 30).unwrap();
+
+        let s = curve.zap_swap_amount(LiquidityPair::X, a).unwrap();
+        let mut after = curve;
+        let (_, _, out) = after.swap(LiquidityPair::X, s, 0).unwrap();
+        // 剩下的 X 按比例不少于换到的 Y
+        assert!(
+            (a - s) as u128 * after.y() as u128 >= out as u128 * after.x() as u128,
+            "zap left too little with seed {}",
+            seed
+        );
+        // 再多换 1 个就会让 X 不够
+        if s < a {
+            let mut more = curve;
+            let (_, _, out) = more.swap(LiquidityPair::X, s + 1, 0).unwrap();
+            assert!(
+                ((a - s - 1) as u128 * more.y() as u128) < out as u128 * more.x() as u128,
+                "zap swap amount is not maximal with seed {}",
+                seed
+            );
+        }
+    }
+}
+
+#[test]
+fn zap_in_is_close_to_half_for_small_amounts() {
+    // 相对池子很小的单边注入 大约一半需要换成另一侧
+    let curve = ConstantProduct::init(1_000_000_000, 1_000_000_000, 0).unwrap();
+    let s = curve.zap_swap_amount(LiquidityPair::X, 1_000_000).unwrap();
+    assert!((499_000..=500_000).contains(&s), "{}", s);
+
+    let lp = ConstantProduct::l_from_xy(
+        1_000_000_000 + s,
+        1_000_000_000 - 999_000,
+        1_000_000_000,
+        1_000_000 - s,
+        999_000,
+    )
+    .unwrap();
+    assert!(lp > 0);
+}
+
+#[test]
+fn l_from_xy_uses_the_smaller_side() {
+    assert_eq!(
+        ConstantProduct::l_from_xy(1_000, 2_000, 3_000, 100, 100).unwrap(),
+        150
+    );
+    assert_eq!(
+        ConstantProduct::l_from_xy(1_000, 2_000, 3_000, 10, 2_000).unwrap(),
+        30
+    );
+}
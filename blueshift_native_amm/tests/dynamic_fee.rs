@@ -0,0 +1,92 @@
+use blueshift_native_amm::{Config, FeeMode, MAX_VOLATILITY, VOLATILITY_HALF_LIFE};
+
+// X 的储备取 2 的幂 Q64.64 价格没有取整误差
+const RESERVE_X: u64 = 1 << 20;
+
+fn zeroed_config() -> Config {
+    // Config 只由字节数组组成 全零就是一个合法的值
+    unsafe { core::mem::zeroed() }
+}
+
+#[test]
+fn static_mode_uses_fixed_fee() {
+    let mut config = zeroed_config();
+    config.set_fee(30).unwrap();
+    config.update_volatility(1_000, 1_000, 0).unwrap();
+    config.update_volatility(1_000, 2_000, 1).unwrap();
+    // 静态模式也会记录波动率 但不影响交换费
+    assert!(config.volatility(1) > 0);
+    assert_eq!(config.effective_fee(1), 30);
+}
+
+#[test]
+fn dynamic_fee_tracks_price_moves() {
+    let mut config = zeroed_config();
+    config.set_fee(30).unwrap();
+    config
+        .set_dynamic_fee(FeeMode::Dynamic as u8, 10, 100)
+        .unwrap();
+    // 第一笔交换只记录价格
+    config.update_volatility(RESERVE_X, 1_000_000, 0).unwrap();
+    assert_eq!(config.effective_fee(0), 10);
+    // 价格变化 0.5% 即 50 基点
+    config.update_volatility(RESERVE_X, 1_005_000, 0).unwrap();
+    assert_eq!(config.volatility(0), 50);
+    assert_eq!(config.effective_fee(0), 60);
+    // 不超过上限
+    config.update_volatility(RESERVE_X, 1_500_000, 0).unwrap();
+    assert_eq!(config.effective_fee(0), 100);
+}
+
+#[test]
+fn volatility_decays_by_half_lives() {
+    let mut config = zeroed_config();
+    config
+        .set_dynamic_fee(FeeMode::Dynamic as u8, 5, 1_000)
+        .unwrap();
+    config.update_volatility(RESERVE_X, 1_000_000, 0).unwrap();
+    config.update_volatility(RESERVE_X, 1_040_000, 0).unwrap();
+    assert_eq!(config.volatility(0), 400);
+    assert_eq!(config.volatility(VOLATILITY_HALF_LIFE - 1), 400);
+    assert_eq!(config.volatility(VOLATILITY_HALF_LIFE), 200);
+    assert_eq!(config.volatility(VOLATILITY_HALF_LIFE * 2), 100);
+    assert_eq!(config.volatility(VOLATILITY_HALF_LIFE * 100), 0);
+    assert_eq!(config.effective_fee(VOLATILITY_HALF_LIFE * 100), 5);
+}
+
+#[test]
+fn frequent_swaps_do_not_stall_decay() {
+    let mut config = zeroed_config();
+    config.update_volatility(RESERVE_X, 1_000_000, 0).unwrap();
+    config.update_volatility(RESERVE_X, 1_040_000, 0).unwrap();
+    // 每隔不到一个半衰期交换一次 价格不变 波动率仍然按时间衰减
+    let step = VOLATILITY_HALF_LIFE / 2 + 1;
+    let mut now = 0;
+    for _ in 0..4 {
+        now += step;
+        config.update_volatility(RESERVE_X, 1_040_000, now).unwrap();
+    }
+    assert_eq!(config.volatility(now), 400 >> (now / VOLATILITY_HALF_LIFE));
+}
+
+#[test]
+fn volatility_is_capped() {
+    let mut config = zeroed_config();
+    config.update_volatility(1, u64::MAX, 0).unwrap();
+    config.update_volatility(u64::MAX, 1, 0).unwrap();
+    config.update_volatility(1, u64::MAX, 0).unwrap();
+    assert_eq!(config.volatility(0), MAX_VOLATILITY);
+}
+
+#[test]
+fn dynamic_fee_bounds_are_validated() {
+    let mut config = zeroed_config();
+    assert!(config
+        .set_dynamic_fee(FeeMode::Dynamic as u8, 50, 10)
+        .is_err());
+    assert!(config
+        .set_dynamic_fee(FeeMode::Dynamic as u8, 0, 10_000)
+        .is_err());
+    assert!(config.set_dynamic_fee(2, 0, 10).is_err());
+    config.set_dynamic_fee(FeeMode::Static as u8, 0, 0).unwrap();
+}
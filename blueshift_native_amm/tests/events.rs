@@ -0,0 +1,155 @@
+use blueshift_native_amm::{
+    AmmEvent, DepositEvent, EventKind, InitializeEvent, SwapEvent, WithdrawEvent, EVENT_VERSION,
+};
+use pinocchio::Address;
+
+fn address(b: u8) -> Address {
+    Address::new_from_array([b; 32])
+}
+
+fn events() -> [AmmEvent; 4] {
+    [
+        AmmEvent::Initialize(InitializeEvent {
+            pool: address(1),
+            authority: address(2),
+            mint_x: address(3),
+            mint_y: address(4),
+            mint_lp: address(5),
+            seed: 42,
+            fee: 30,
+        }),
+        AmmEvent::Swap(SwapEvent {
+            pool: address(1),
+            user: address(6),
+            is_x: true,
+            amount_in: 1_000,
+            amount_out: 987,
+            fee: 3,
+            reserve_x: 1_001_000,
+            reserve_y: 999_013,
+        }),
+        AmmEvent::Deposit(DepositEvent {
+            pool: address(1),
+            user: address(6),
+            amount_x: 500,
+            amount_y: 600,
+            lp_minted: 550,
+            reserve_x: 1_500,
+            reserve_y: 1_600,
+        }),
+        AmmEvent::Withdraw(WithdrawEvent {
+            pool: address(1),
+            user: address(6),
+            amount_x: 100,
+            amount_y: 110,
+            lp_burned: 105,
+            reserve_x: 1_400,
+            reserve_y: 1_490,
+        }),
+    ]
+}
+
+// 测试里用的标准 base64 编码 模拟 "Program data: " 日志
+fn base64(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[test]
+fn events_round_trip() {
+    for event in events() {
+        let mut buf = [0u8; AmmEvent::MAX_LEN];
+        let len = event.encode(&mut buf);
+        assert_eq!(buf[0], EVENT_VERSION);
+        assert_eq!(buf[1], event.kind() as u8);
+        assert_eq!(AmmEvent::decode(&buf[..len]).unwrap(), event);
+    }
+}
+
+#[test]
+fn initialize_is_the_longest_event() {
+    let mut buf = [0u8; AmmEvent::MAX_LEN];
+    assert_eq!(events()[0].encode(&mut buf), AmmEvent::MAX_LEN);
+    assert_eq!(events()[0].kind(), EventKind::Initialize);
+}
+
+const PROGRAM: &str = "AMM1111111111111111111111111111111111111111";
+const OTHER: &str = "Other111111111111111111111111111111111111111";
+
+fn data_line(event: &AmmEvent) -> String {
+    let mut buf = [0u8; AmmEvent::MAX_LEN];
+    let len = event.encode(&mut buf);
+    format!("Program data: {}", base64(&buf[..len]))
+}
+
+#[test]
+fn events_decode_from_transaction_logs() {
+    let mut logs = vec![format!("Program {PROGRAM} invoke [1]")];
+    logs.extend(events().iter().map(data_line));
+    logs.push(format!("Program {PROGRAM} success"));
+    let lines = logs.iter().map(String::as_str);
+    assert_eq!(AmmEvent::from_logs(PROGRAM, lines), events().to_vec());
+
+    // 其他日志行不是事件
+    let logs = [
+        format!("Program {PROGRAM} invoke [1]"),
+        "Program log: Instruction: Swap".to_string(),
+        "Program data: not-base64!".to_string(),
+        format!("Program {PROGRAM} success"),
+    ];
+    assert!(AmmEvent::from_logs(PROGRAM, logs.iter().map(String::as_str)).is_empty());
+}
+
+#[test]
+fn ignores_events_written_by_other_programs() {
+    let swap = data_line(&events()[1]);
+    let logs = [
+        // 其他程序直接写出同样格式的事件
+        format!("Program {OTHER} invoke [1]"),
+        swap.clone(),
+        format!("Program {OTHER} success"),
+        // 本程序通过 CPI 调用的程序写出的事件 以及伪造调用记录的普通日志
+        format!("Program {PROGRAM} invoke [1]"),
+        format!("Program {OTHER} invoke [2]"),
+        format!("Program log: Program {PROGRAM} invoke [3]"),
+        swap.clone(),
+        format!("Program {OTHER} success"),
+        // 回到本程序之后写出的事件才会被解析
+        swap.clone(),
+        format!("Program {PROGRAM} success"),
+        // 调用结束之后的事件不属于本程序
+        swap.clone(),
+    ];
+    let parsed = AmmEvent::from_logs(PROGRAM, logs.iter().map(String::as_str));
+    assert_eq!(parsed, vec![events()[1]]);
+}
+
+#[test]
+fn decode_rejects_unknown_version_and_truncated_data() {
+    let mut buf = [0u8; AmmEvent::MAX_LEN];
+    let len = events()[1].encode(&mut buf);
+
+    assert!(AmmEvent::decode(&buf[..len - 1]).is_err());
+    assert!(AmmEvent::decode(&buf[..len + 1]).is_err());
+
+    buf[0] = EVENT_VERSION + 1;
+    assert!(AmmEvent::decode(&buf[..len]).is_err());
+
+    buf[0] = EVENT_VERSION;
+    buf[1] = 0xff;
+    assert!(AmmEvent::decode(&buf[..len]).is_err());
+}
@@ -0,0 +1,230 @@
+mod common;
+
+use blueshift_native_amm::{AmmError, Farm, StakePosition, ID};
+use common::*;
+use mollusk_svm::result::ProgramResult;
+use pinocchio::Address;
+use solana_account::Account;
+use solana_instruction::AccountMeta;
+
+fn farm(emission_rate: u64) -> Farm {
+    // Farm 和 StakePosition 只由地址和字节数组组成 全零就是一个合法的值
+    let mut farm: Farm = unsafe { core::mem::zeroed() };
+    farm.set_inner(
+        Address::new_from_array([1; 32]),
+        Address::new_from_array([2; 32]),
+        Address::new_from_array([3; 32]),
+        emission_rate,
+        0,
+        [255],
+    );
+    farm
+}
+
+fn stake(farm: &mut Farm, owner: u8, amount: u64, now: i64) -> StakePosition {
+    farm.update(now).unwrap();
+    let mut stake: StakePosition = unsafe { core::mem::zeroed() };
+    stake.set_inner(
+        Address::new_from_array([owner; 32]),
+        Address::new_from_array([9; 32]),
+        farm.reward_per_share(),
+        [255],
+    );
+    stake.deposit(amount).unwrap();
+    farm.stake(amount).unwrap();
+    stake
+}
+
+fn claim(farm: &mut Farm, stake: &mut StakePosition, now: i64) -> u64 {
+    farm.update(now).unwrap();
+    stake.settle(farm.reward_per_share()).unwrap();
+    stake.take_rewards()
+}
+
+#[test]
+fn rewards_split_by_stake_and_time() {
+    let mut farm = farm(100);
+    farm.fund(1_000_000, 0).unwrap();
+    let mut alice = stake(&mut farm, 4, 1_000, 0);
+
+    // 前 10 秒只有 alice 之后 bob 质押了三倍的数量
+    let mut bob = stake(&mut farm, 5, 3_000, 10);
+    let alice_rewards = claim(&mut farm, &mut alice, 30);
+    let bob_rewards = claim(&mut farm, &mut bob, 30);
+    assert_eq!(alice_rewards, 1_000 + 500);
+    assert_eq!(bob_rewards, 1_500);
+    assert_eq!(farm.undistributed(), 1_000_000 - 3_000);
+
+    // 已经领取过 同一时刻再领取没有奖励
+    assert_eq!(claim(&mut farm, &mut alice, 30), 0);
+}
+
+#[test]
+fn nothing_is_emitted_without_stakers() {
+    let mut farm = farm(100);
+    farm.fund(10_000, 0).unwrap();
+    farm.update(50).unwrap();
+    assert_eq!(farm.undistributed(), 10_000);
+    assert_eq!(farm.last_update(), 50);
+
+    // 质押之前的时间不算
+    let mut alice = stake(&mut farm, 4, 7, 50);
+    assert_eq!(claim(&mut farm, &mut alice, 60), 1_000 - 1);
+}
+
+#[test]
+fn emission_is_capped_by_funded_rewards() {
+    let mut farm = farm(100);
+    farm.fund(250, 0).unwrap();
+    let mut alice = stake(&mut farm, 4, 1_000, 0);
+    assert_eq!(claim(&mut farm, &mut alice, 10), 250);
+    assert_eq!(farm.undistributed(), 0);
+
+    // 奖励用完之后继续注入 从注入的时刻开始释放
+    farm.fund(1_000, 100).unwrap();
+    assert_eq!(claim(&mut farm, &mut alice, 105), 500);
+}
+
+#[test]
+fn rate_change_only_affects_later_time() {
+    let mut farm = farm(100);
+    farm.fund(1_000_000, 0).unwrap();
+    let mut alice = stake(&mut farm, 4, 1_000, 0);
+    farm.set_emission_rate(10, 10).unwrap();
+    // 每单位 LP 0.1 在 Q64.64 中不能精确表示 向下取整少 1
+    assert_eq!(claim(&mut farm, &mut alice, 20), 1_000 + 100 - 1);
+
+    // 速度为 0 时暂停释放
+    farm.set_emission_rate(0, 20).unwrap();
+    assert_eq!(claim(&mut farm, &mut alice, 1_000), 0);
+}
+
+#[test]
+fn unstake_keeps_earned_rewards() {
+    let mut farm = farm(100);
+    farm.fund(1_000_000, 0).unwrap();
+    let mut alice = stake(&mut farm, 4, 1_000, 0);
+
+    farm.update(10).unwrap();
+    alice.settle(farm.reward_per_share()).unwrap();
+    assert_eq!(
+        alice.withdraw(1_001),
+        Err(AmmError::InsufficientStake.into())
+    );
+    alice.withdraw(1_000).unwrap();
+    farm.unstake(1_000).unwrap();
+    assert_eq!(farm.unstake(1), Err(AmmError::InsufficientStake.into()));
+
+    // 取回之后不再产生奖励 之前的奖励仍然可以领取
+    assert_eq!(claim(&mut farm, &mut alice, 50), 1_000);
+    assert_eq!(farm.undistributed(), 1_000_000 - 1_000);
+}
+
+fn amount_data(discriminator: u8, amount: u64) -> Vec<u8> {
+    let mut data = vec![discriminator];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data
+}
+
+// Stake 和 Unstake 的账户顺序相同 Stake 多出创建质押账户需要的系统程序
+fn stake_metas(
+    pool: &Pool,
+    stake: &Address,
+    farm: &Address,
+    lp_vault: &Address,
+) -> Vec<AccountMeta> {
+    vec![
+        meta(&pool.user, true, true),
+        meta(&pool.user_lp, true, false),
+        meta(stake, true, false),
+        meta(farm, true, false),
+        meta(&pool.mint_lp, false, false),
+        meta(lp_vault, true, false),
+        meta(&pinocchio_token::ID, false, false),
+        meta(&pinocchio_system::ID, false, false),
+    ]
+}
+
+// 用户质押 LP 十秒之后领取奖励 农场里只有这一个质押者
+#[test]
+#[ignore = "需要 cargo test-sbf 编译出的程序"]
+fn staker_claims_emitted_rewards() {
+    let mut mollusk = mollusk();
+    mollusk.sysvars.clock.unix_timestamp = 0;
+    let pool = Pool::new(&mollusk);
+    let reward_mint = Address::new_from_array([3; 32]);
+    let (farm, bump) = Address::find_program_address(&[b"farm", pool.config.as_ref()], &ID);
+    let (stake, _) =
+        Address::find_program_address(&[b"stake", farm.as_ref(), pool.user.as_ref()], &ID);
+    let (lp_vault, reward_vault) = (ata(&farm, &pool.mint_lp), ata(&farm, &reward_mint));
+    let user_reward = ata(&pool.user, &reward_mint);
+
+    // Farm 只由地址和字节数组组成 全零就是一个合法的值
+    let mut initial: Farm = unsafe { core::mem::zeroed() };
+    initial.set_inner(pool.config, pool.mint_lp, reward_mint, 100, 0, [bump]);
+    initial.fund(1_000_000, 0).unwrap();
+    let accounts: Vec<(Address, Account)> = vec![
+        (farm, account(&mollusk, bytes(&initial), &ID)),
+        (stake, empty()),
+        (reward_mint, mint(&mollusk, &pool.user, 1_000_000)),
+        (lp_vault, token_account(&mollusk, &pool.mint_lp, &farm, 0)),
+        (
+            reward_vault,
+            token_account(&mollusk, &reward_mint, &farm, 1_000_000),
+        ),
+        (
+            user_reward,
+            token_account(&mollusk, &reward_mint, &pool.user, 0),
+        ),
+    ];
+    let metas = stake_metas(&pool, &stake, &farm, &lp_vault);
+    let pool = accounts.into_iter().fold(pool, |pool, (address, account)| {
+        pool.with(&address, account)
+    });
+
+    let result = mollusk.process_instruction(
+        &instruction(amount_data(32, 1_000), metas.clone()),
+        &pool.accounts,
+    );
+    assert_eq!(result.program_result, ProgramResult::Success);
+    assert_eq!(amount(&result, &pool.user_lp), BALANCE - 1_000);
+    assert_eq!(amount(&result, &lp_vault), 1_000);
+
+    mollusk.sysvars.clock.unix_timestamp = 10;
+    let claim = instruction(
+        vec![34],
+        vec![
+            meta(&pool.user, true, true),
+            meta(&user_reward, true, false),
+            meta(&stake, true, false),
+            meta(&farm, true, false),
+            meta(&reward_mint, false, false),
+            meta(&reward_vault, true, false),
+            meta(&pinocchio_token::ID, false, false),
+        ],
+    );
+    let result = mollusk.process_instruction(&claim, &result.resulting_accounts);
+    assert_eq!(result.program_result, ProgramResult::Success);
+    // 每秒释放 100 十秒全部归唯一的质押者
+    assert_eq!(amount(&result, &user_reward), 1_000);
+    assert_eq!(amount(&result, &reward_vault), 1_000_000 - 1_000);
+    let farm_state: Farm = state(get(&result, &farm));
+    assert_eq!(farm_state.total_staked(), 1_000);
+    assert_eq!(farm_state.undistributed(), 1_000_000 - 1_000);
+    assert_eq!(farm_state.last_update(), 10);
+    let position: StakePosition = state(get(&result, &stake));
+    assert_eq!(position.amount(), 1_000);
+    assert_eq!(position.rewards_owed(), 0);
+
+    // 取回的数量不能超过质押的数量
+    let mut unstake_metas = metas;
+    unstake_metas.pop();
+    let result = mollusk.process_instruction(
+        &instruction(amount_data(33, 1_001), unstake_metas),
+        &result.resulting_accounts,
+    );
+    assert_eq!(
+        result.program_result,
+        ProgramResult::Failure(AmmError::InsufficientStake.into())
+    );
+}
@@ -0,0 +1,203 @@
+mod common;
+
+use blueshift_native_amm::{
+    AmmError, Config, FlashLoan, FlashRepay, Instructions, ID, INSTRUCTIONS_SYSVAR_ID,
+};
+use common::*;
+use mollusk_svm::{result::ProgramResult, Mollusk};
+use pinocchio::Address;
+use solana_account::Account;
+use solana_instruction::Instruction;
+
+const LOAN: u64 = 1_000_000;
+
+fn zeroed_config() -> Config {
+    // Config 只由字节数组组成 全零就是一个合法的值
+    unsafe { core::mem::zeroed() }
+}
+
+// 按指令 sysvar 的布局序列化 (账户, 程序, 数据) 列表
+fn serialize(instructions: &[(&[Address], Address, &[u8])], current: u16) -> Vec<u8> {
+    let mut header = (instructions.len() as u16).to_le_bytes().to_vec();
+    let mut body = Vec::new();
+    let body_start = 2 + 2 * instructions.len();
+    for (accounts, program_id, data) in instructions {
+        header.extend_from_slice(&((body_start + body.len()) as u16).to_le_bytes());
+        body.extend_from_slice(&(accounts.len() as u16).to_le_bytes());
+        for account in accounts.iter() {
+            body.push(0);
+            body.extend_from_slice(account.as_ref());
+        }
+        body.extend_from_slice(program_id.as_ref());
+        body.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        body.extend_from_slice(data);
+    }
+    header.extend_from_slice(&body);
+    header.extend_from_slice(&current.to_le_bytes());
+    header
+}
+
+#[test]
+fn parses_instructions_sysvar() {
+    let (a, b, c) = (
+        Address::new_from_array([1; 32]),
+        Address::new_from_array([2; 32]),
+        Address::new_from_array([3; 32]),
+    );
+    let data = serialize(&[(&[a, b], c, &[9, 8, 7]), (&[], a, &[])], 1);
+    let instructions = Instructions::new(&data).unwrap();
+    assert_eq!(instructions.len(), 2);
+    assert_eq!(instructions.current_index().unwrap(), 1);
+
+    let first = instructions.get(0).unwrap();
+    assert_eq!(first.num_accounts(), 2);
+    assert_eq!(first.account(0).unwrap(), &a);
+    assert_eq!(first.account(1).unwrap(), &b);
+    assert!(first.account(2).is_err());
+    assert_eq!(first.program_id(), &c);
+    assert_eq!(first.data(), &[9, 8, 7]);
+
+    let second = instructions.get(1).unwrap();
+    assert_eq!(second.num_accounts(), 0);
+    assert_eq!(second.program_id(), &a);
+    assert!(second.data().is_empty());
+    assert!(instructions.get(2).is_err());
+}
+
+#[test]
+fn rejects_truncated_sysvar() {
+    let data = serialize(&[(&[], Address::new_from_array([1; 32]), &[1, 2, 3])], 0);
+    // 去掉数据的最后一个字节 当前下标也跟着错位
+    let mut truncated = data[..data.len() - 3].to_vec();
+    truncated.extend_from_slice(&0u16.to_le_bytes());
+    let instructions = Instructions::new(&truncated).unwrap();
+    assert!(instructions.get(0).is_err());
+    assert!(Instructions::new(&[0, 0]).is_err());
+}
+
+#[test]
+fn flash_loan_locks_reserves_until_repaid() {
+    let mut config = zeroed_config();
+    config.start_flash_loan(FlashLoan::X, 500, 1_000).unwrap();
+    assert!(config.reserves(500, 1_000).is_err());
+    assert!(config.check_no_flash_loan().is_err());
+    // 同一时间只能有一笔闪电贷
+    assert!(config.start_flash_loan(FlashLoan::Y, 1, 1).is_err());
+
+    // 归还的一侧和数量必须和借出时一致
+    assert!(config.finish_flash_loan(FlashLoan::Y, 500).is_err());
+    assert!(config.finish_flash_loan(FlashLoan::X, 499).is_err());
+    assert_eq!(config.finish_flash_loan(FlashLoan::X, 500).unwrap(), 1_000);
+    assert_eq!(config.reserves(1_001, 1_000).unwrap(), (1_001, 1_000));
+    assert!(config.finish_flash_loan(FlashLoan::X, 500).is_err());
+}
+
+#[test]
+fn flash_loan_fee_rounds_up() {
+    let mut config = zeroed_config();
+    config.set_fee(30).unwrap();
+    assert_eq!(config.flash_loan_fee(10_000, 0).unwrap(), 30);
+    assert_eq!(config.flash_loan_fee(1, 0).unwrap(), 1);
+    config.set_fee(0).unwrap();
+    assert_eq!(config.flash_loan_fee(10_000, 0).unwrap(), 0);
+}
+
+#[test]
+fn repay_discriminator_matches_dispatch() {
+    assert_eq!(FlashRepay::DISCRIMINATOR, 19);
+}
+
+// 借出和归还代币 X 的指令 账户顺序与 FlashBorrow FlashRepay 相同 FlashRepay 还需要另一侧的金库
+fn borrow(pool: &Pool) -> Instruction {
+    let mut data = vec![18, 1];
+    data.extend_from_slice(&LOAN.to_le_bytes());
+    instruction(
+        data,
+        vec![
+            meta(&pool.user, true, true),
+            meta(&pool.config, true, false),
+            meta(&pool.vault_x, true, false),
+            meta(&pool.user_x, true, false),
+            meta(&pool.mint_x, false, false),
+            meta(&pinocchio_token::ID, false, false),
+            meta(&INSTRUCTIONS_SYSVAR_ID, false, false),
+        ],
+    )
+}
+
+fn repay(pool: &Pool) -> Instruction {
+    let mut data = vec![FlashRepay::DISCRIMINATOR, 1];
+    data.extend_from_slice(&LOAN.to_le_bytes());
+    instruction(
+        data,
+        vec![
+            meta(&pool.user, true, true),
+            meta(&pool.config, true, false),
+            meta(&pool.vault_x, true, false),
+            meta(&pool.user_x, true, false),
+            meta(&pool.mint_x, false, false),
+            meta(&pinocchio_token::ID, false, false),
+            meta(&pool.vault_y, false, false),
+        ],
+    )
+}
+
+// 交易中的顶层指令 当前执行的是第一条
+fn instructions_sysvar(mollusk: &Mollusk, instructions: &[&Instruction]) -> Account {
+    let accounts: Vec<Vec<Address>> = instructions
+        .iter()
+        .map(|instruction| {
+            instruction
+                .accounts
+                .iter()
+                .map(|meta| Address::new_from_array(meta.pubkey.to_bytes()))
+                .collect()
+        })
+        .collect();
+    let serialized: Vec<(&[Address], Address, &[u8])> = instructions
+        .iter()
+        .zip(&accounts)
+        .map(|(instruction, accounts)| (accounts.as_slice(), ID, instruction.data.as_slice()))
+        .collect();
+    // 和其他 sysvar 账户相同的所有者
+    let (_, clock) = mollusk.sysvars.keyed_account_for_clock_sysvar();
+    Account {
+        owner: clock.owner,
+        ..account(mollusk, serialize(&serialized, 0), &ID)
+    }
+}
+
+#[test]
+#[ignore = "需要 cargo test-sbf 编译出的程序"]
+fn borrow_and_repay_in_one_transaction() {
+    let mollusk = mollusk();
+    let pool = Pool::new(&mollusk);
+    let (borrow, repay) = (borrow(&pool), repay(&pool));
+    let sysvar = instructions_sysvar(&mollusk, &[&borrow, &repay]);
+    let pool = pool.with(&INSTRUCTIONS_SYSVAR_ID, sysvar);
+
+    let result = mollusk.process_instruction_chain(&[borrow, repay], &pool.accounts);
+    assert_eq!(result.program_result, ProgramResult::Success);
+    // 手续费和交换费的费率相同 留在金库里
+    let fee = LOAN * FEE as u64 / 10_000;
+    assert_eq!(amount(&result, &pool.vault_x), RESERVE + fee);
+    assert_eq!(amount(&result, &pool.user_x), BALANCE - fee);
+    let config: Config = state(get(&result, &pool.config));
+    assert_eq!(config.flash_loan(), FlashLoan::None as u8);
+}
+
+#[test]
+#[ignore = "需要 cargo test-sbf 编译出的程序"]
+fn borrow_without_repay_is_rejected() {
+    let mollusk = mollusk();
+    let pool = Pool::new(&mollusk);
+    let borrow = borrow(&pool);
+    let sysvar = instructions_sysvar(&mollusk, &[&borrow]);
+    let pool = pool.with(&INSTRUCTIONS_SYSVAR_ID, sysvar);
+
+    let result = mollusk.process_instruction(&borrow, &pool.accounts);
+    assert_eq!(
+        result.program_result,
+        ProgramResult::Failure(AmmError::InvalidFlashLoan.into())
+    );
+}
@@ -0,0 +1,125 @@
+use blueshift_native_amm::{AmmError, InitializeInstructionData, LpMetadata, DEFAULT_FEE};
+use pinocchio::error::ProgramError;
+
+// seed | fee | mint_x | mint_y | config_bump | lp_bump 后面依次可选 authority、curve_type | amp 和 lp_decimals_offset
+fn data(mint_x: u8, mint_y: u8, fee: u16) -> Vec<u8> {
+    let mut data = 7u64.to_le_bytes().to_vec();
+    data.extend_from_slice(&fee.to_le_bytes());
+    data.extend_from_slice(&[mint_x; 32]);
+    data.extend_from_slice(&[mint_y; 32]);
+    // 程序自己推导 bump 这两个字节的值不影响结果
+    data.extend_from_slice(&[255, 0]);
+    data
+}
+
+#[test]
+fn parses_all_supported_lengths() {
+    // 76 字节和 108 字节是最早的两种格式
+    let base = data(1, 2, 30);
+    assert_eq!(base.len(), 76);
+    let parsed = InitializeInstructionData::try_from(base.as_slice()).unwrap();
+    let (seed, fee, authority, curve_type) =
+        (parsed.seed, parsed.fee, parsed.authority, parsed.curve_type);
+    assert_eq!((seed, fee), (7, 30));
+    assert_eq!(authority, [0; 32]);
+    assert_eq!(curve_type, 0);
+
+    let mut with_authority = base.clone();
+    with_authority.extend_from_slice(&[9; 32]);
+    assert_eq!(with_authority.len(), 108);
+    let parsed = InitializeInstructionData::try_from(with_authority.as_slice()).unwrap();
+    let (authority, curve_type, amp) = (parsed.authority, parsed.curve_type, parsed.amp);
+    assert_eq!(authority, [9; 32]);
+    assert_eq!((curve_type, amp), (0, [0; 8]));
+
+    let mut with_curve = with_authority.clone();
+    with_curve.push(1);
+    with_curve.extend_from_slice(&100u64.to_le_bytes());
+    let parsed = InitializeInstructionData::try_from(with_curve.as_slice()).unwrap();
+    let (curve_type, amp) = (parsed.curve_type, parsed.amp);
+    assert_eq!((curve_type, u64::from_le_bytes(amp)), (1, 100));
+    assert_eq!(parsed.lp_decimals_offset, 0);
+
+    let mut with_decimals = with_curve.clone();
+    with_decimals.push(-2i8 as u8);
+    let parsed = InitializeInstructionData::try_from(with_decimals.as_slice()).unwrap();
+    assert_eq!(parsed.lp_decimals_offset, -2);
+
+    with_decimals.push(0);
+    assert!(InitializeInstructionData::try_from(with_decimals.as_slice()).is_err());
+}
+
+// symbol_x_len | symbol_x | symbol_y_len | symbol_y | uri_len | uri
+fn metadata(symbol_x: &[u8], symbol_y: &[u8], uri: &[u8]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for value in [symbol_x, symbol_y, uri] {
+        data.push(value.len() as u8);
+        data.extend_from_slice(value);
+    }
+    data
+}
+
+#[test]
+fn names_lp_after_the_pair() {
+    let data = metadata(b"SOL", b"USDC", b"https://example.com/lp.json");
+    let metadata = LpMetadata::try_from(data.as_slice()).unwrap();
+    assert_eq!(metadata.uri, b"https://example.com/lp.json");
+
+    let mut buf = [0u8; 32];
+    let len = metadata.write_pair(b" LP", &mut buf);
+    assert_eq!(&buf[..len], b"SOL-USDC LP");
+    let len = metadata.write_pair(b"-LP", &mut buf);
+    assert_eq!(&buf[..len], b"SOL-USDC-LP");
+}
+
+#[test]
+fn rejects_malformed_metadata() {
+    let expected: ProgramError = AmmError::InvalidMetadata.into();
+    for data in [
+        // 缺少 symbol
+        metadata(b"", b"USDC", b""),
+        // symbol 太长
+        metadata(b"SOLSOLSOLSOL", b"USDC", b""),
+        // 不可见字符
+        metadata(b"SO L", b"USDC", b""),
+        metadata("SÖL".as_bytes(), b"USDC", b""),
+    ] {
+        assert_eq!(
+            LpMetadata::try_from(data.as_slice()).err(),
+            Some(expected.clone())
+        );
+    }
+
+    // 长度超出数据 或者末尾多出字节
+    let data = metadata(b"SOL", b"USDC", b"");
+    assert!(LpMetadata::try_from(&data[..data.len() - 1]).is_err());
+    let mut trailing = data.clone();
+    trailing.push(0);
+    assert!(LpMetadata::try_from(trailing.as_slice()).is_err());
+}
+
+#[test]
+fn rejects_non_canonical_mint_order() {
+    let expected: ProgramError = AmmError::InvalidMintOrder.into();
+    for (mint_x, mint_y) in [(2, 1), (3, 3)] {
+        let data = data(mint_x, mint_y, 30);
+        assert_eq!(
+            InitializeInstructionData::try_from(data.as_slice()).err(),
+            Some(expected.clone())
+        );
+    }
+}
+
+#[test]
+fn rejects_full_fee() {
+    let data = data(1, 2, 10_000);
+    assert!(InitializeInstructionData::try_from(data.as_slice()).is_err());
+}
+
+#[test]
+fn accepts_factory_default_fee() {
+    let data = data(1, 2, DEFAULT_FEE);
+    let parsed = InitializeInstructionData::try_from(data.as_slice()).unwrap();
+    let fee = parsed.fee;
+    assert_eq!(fee, DEFAULT_FEE);
+}
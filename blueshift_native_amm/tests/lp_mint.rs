@@ -0,0 +1,129 @@
+mod common;
+
+use common::*;
+use mollusk_svm::{result::ProgramResult, Mollusk};
+use pinocchio::{error::ProgramError, Address};
+
+// 攻击者自己创建的铸币账户 只给自己铸造了 1 个 供应量等于销毁数量时会按全部储备计算
+struct Foreign {
+    pool: Pool,
+    mint_lp: Address,
+    user_lp: Address,
+}
+
+fn foreign(mollusk: &Mollusk) -> Foreign {
+    let pool = Pool::new(mollusk);
+    let mint_lp = Address::new_from_array([8; 32]);
+    let user_lp = ata(&pool.user, &mint_lp);
+    let mint_account = mint(mollusk, &pool.user, 1);
+    let lp_account = token_account(mollusk, &mint_lp, &pool.user, 1);
+    Foreign {
+        pool: pool.with(&mint_lp, mint_account).with(&user_lp, lp_account),
+        mint_lp,
+        user_lp,
+    }
+}
+
+#[test]
+#[ignore = "需要 cargo test-sbf 编译出的程序"]
+fn foreign_lp_mint_cannot_zap_out() {
+    let mollusk = mollusk();
+    let Foreign {
+        pool,
+        mint_lp,
+        user_lp,
+    } = foreign(&mollusk);
+
+    let mut data = vec![15, 1];
+    data.extend_from_slice(&1u64.to_le_bytes());
+    data.extend_from_slice(&1u64.to_le_bytes());
+    data.extend_from_slice(&i64::MAX.to_le_bytes());
+    let zap_out = instruction(
+        data,
+        vec![
+            meta(&pool.user, true, true),
+            meta(&mint_lp, true, false),
+            meta(&pool.vault_x, true, false),
+            meta(&pool.vault_y, true, false),
+            meta(&pool.user_x, true, false),
+            meta(&user_lp, true, false),
+            meta(&pool.config, true, false),
+            meta(&pinocchio_token::ID, false, false),
+            meta(&pool.mint_x, false, false),
+            meta(&pool.mint_y, false, false),
+            meta(&pinocchio_token::ID, false, false),
+            meta(&pinocchio_token::ID, false, false),
+        ],
+    );
+    let result = mollusk.process_instruction(&zap_out, &pool.accounts);
+    assert_eq!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::InvalidAccountData)
+    );
+}
+
+#[test]
+#[ignore = "需要 cargo test-sbf 编译出的程序"]
+fn foreign_lp_mint_cannot_withdraw() {
+    let mollusk = mollusk();
+    let Foreign {
+        pool,
+        mint_lp,
+        user_lp,
+    } = foreign(&mollusk);
+
+    let mut data = vec![2];
+    data.extend_from_slice(&1u64.to_le_bytes());
+    data.extend_from_slice(&1u64.to_le_bytes());
+    data.extend_from_slice(&1u64.to_le_bytes());
+    data.extend_from_slice(&i64::MAX.to_le_bytes());
+    let withdraw = instruction(
+        data,
+        vec![
+            meta(&pool.user, true, true),
+            meta(&mint_lp, true, false),
+            meta(&pool.vault_x, true, false),
+            meta(&pool.vault_y, true, false),
+            meta(&pool.user_x, true, false),
+            meta(&pool.user_y, true, false),
+            meta(&user_lp, true, false),
+            meta(&pool.config, true, false),
+            meta(&pinocchio_token::ID, false, false),
+            meta(&pool.mint_x, false, false),
+            meta(&pool.mint_y, false, false),
+            meta(&pinocchio_token::ID, false, false),
+            meta(&pinocchio_token::ID, false, false),
+        ],
+    );
+    let result = mollusk.process_instruction(&withdraw, &pool.accounts);
+    assert_eq!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::InvalidAccountData)
+    );
+}
+
+#[test]
+#[ignore = "需要 cargo test-sbf 编译出的程序"]
+fn foreign_lp_mint_cannot_be_quoted() {
+    let mollusk = mollusk();
+    let Foreign { pool, mint_lp, .. } = foreign(&mollusk);
+
+    let mut data = vec![10, 2];
+    data.extend_from_slice(&1u64.to_le_bytes());
+    let quote = instruction(
+        data,
+        vec![
+            meta(&mint_lp, false, false),
+            meta(&pool.vault_x, false, false),
+            meta(&pool.vault_y, false, false),
+            meta(&pool.config, false, false),
+            meta(&pinocchio_token::ID, false, false),
+            meta(&pinocchio_token::ID, false, false),
+        ],
+    );
+    let result = mollusk.process_instruction(&quote, &pool.accounts);
+    assert_eq!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::InvalidAccountData)
+    );
+}
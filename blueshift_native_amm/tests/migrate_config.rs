@@ -0,0 +1,51 @@
+mod common;
+
+use blueshift_native_amm::{AmmError, Config, CONFIG_LEGACY_LEN, CONFIG_V1_LEN, CONFIG_VERSION};
+use common::*;
+use mollusk_svm::result::ProgramResult;
+
+// 旧布局的池子在迁移之前不能交易 迁移之后参数不变 可以继续交易
+#[test]
+#[ignore = "需要 cargo test-sbf 编译出的程序"]
+fn outdated_pool_trades_again_after_migration() {
+    let mollusk = mollusk();
+    for len in [CONFIG_LEGACY_LEN, CONFIG_V1_LEN] {
+        let mut pool = Pool::new(&mollusk);
+        let config = pubkey(&pool.config);
+        let (_, account) = pool
+            .accounts
+            .iter_mut()
+            .find(|(address, _)| *address == config)
+            .unwrap();
+        account.data.truncate(len);
+        account.lamports = mollusk.sysvars.rent.minimum_balance(len);
+
+        let swap = pool.swap(true);
+        let result = mollusk.process_instruction(&swap, &pool.accounts);
+        assert_eq!(
+            result.program_result,
+            ProgramResult::Failure(AmmError::ConfigOutdated.into())
+        );
+
+        let migrate = instruction(
+            vec![35],
+            vec![
+                meta(&pool.user, true, true),
+                meta(&pool.config, true, false),
+                meta(&pinocchio_system::ID, false, false),
+                meta(&pool.mint_x, false, false),
+                meta(&pool.mint_y, false, false),
+                meta(&pinocchio_token::ID, false, false),
+                meta(&pinocchio_token::ID, false, false),
+            ],
+        );
+        let result = mollusk.process_instruction_chain(&[migrate, swap], &pool.accounts);
+        assert_eq!(result.program_result, ProgramResult::Success);
+        let migrated: Config = state(get(&result, &pool.config));
+        assert_eq!(migrated.version(), CONFIG_VERSION);
+        assert_eq!(migrated.vault_x(), &pool.vault_x);
+        assert_eq!(migrated.vault_y(), &pool.vault_y);
+        assert_eq!(migrated.fee(), FEE);
+        assert_eq!(amount(&result, &pool.vault_x), RESERVE + 1_000_000);
+    }
+}
@@ -0,0 +1,28 @@
+use blueshift_native_amm::Observation;
+
+const Q64: u128 = 1 << 64;
+
+#[test]
+fn twap_between_two_observations() {
+    // 前 100 秒价格为 2 后 100 秒价格为 4 平均价格为 3
+    let older = Observation::new(1_000, 0, 0);
+    let newer = Observation::new(1_200, 2 * Q64 * 100 + 4 * Q64 * 100, Q64 * 50 + Q64 * 25);
+    let (price_x, price_y) = newer.twap(&older).unwrap();
+    assert_eq!(price_x, 3 * Q64);
+    assert_eq!(price_y, Q64 * 3 / 8);
+}
+
+#[test]
+fn twap_handles_accumulator_wraparound() {
+    let older = Observation::new(1_000, u128::MAX - Q64 + 1, 0);
+    let newer = Observation::new(1_010, 9 * Q64, 0);
+    let (price_x, _) = newer.twap(&older).unwrap();
+    assert_eq!(price_x, Q64);
+}
+
+#[test]
+fn twap_requires_elapsed_time() {
+    let older = Observation::new(1_000, 0, 0);
+    let newer = Observation::new(1_000, Q64, Q64);
+    assert!(newer.twap(&older).is_err());
+}
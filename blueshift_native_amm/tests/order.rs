@@ -0,0 +1,194 @@
+mod common;
+
+use blueshift_native_amm::{AmmError, Order, PlaceOrderInstructionData, ID};
+use common::*;
+use mollusk_svm::result::ProgramResult;
+use pinocchio::{error::ProgramError, Address};
+
+fn order(executions: u64, interval: i64, start: i64, expiration: i64) -> Order {
+    // Order 只由地址和字节数组组成 全零就是一个合法的值
+    let mut order: Order = unsafe { core::mem::zeroed() };
+    order.set_inner(
+        Address::new_from_array([1; 32]),
+        Address::new_from_array([2; 32]),
+        Address::new_from_array([3; 32]),
+        7,
+        true,
+        1_000,
+        900,
+        5,
+        executions,
+        interval,
+        start,
+        expiration,
+        [255],
+    );
+    order
+}
+
+// seed | is_x | amount_in | min_out | tip | executions | interval | start | expiration
+fn data(executions: u64, interval: i64, start: i64, expiration: i64) -> Vec<u8> {
+    let mut data = 7u64.to_le_bytes().to_vec();
+    data.push(1);
+    for value in [1_000u64, 900, 5, executions] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    for value in [interval, start, expiration] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data
+}
+
+#[test]
+fn limit_order_executes_once() {
+    let mut order = order(1, 0, 100, 1_000);
+    assert_eq!(
+        order.check_executable(99),
+        Err(AmmError::OrderNotReady.into())
+    );
+    order.advance(500).unwrap();
+    assert_eq!(order.executions_left(), 0);
+    assert_eq!(
+        order.check_executable(500),
+        Err(AmmError::OrderFilled.into())
+    );
+}
+
+#[test]
+fn dca_order_waits_for_each_interval() {
+    let mut order = order(3, 3_600, 0, i64::MAX);
+    order.advance(0).unwrap();
+    assert_eq!(order.next_execution(), 3_600);
+    assert_eq!(order.advance(3_599), Err(AmmError::OrderNotReady.into()));
+
+    // keeper 迟到时跳过错过的间隔 不会连续执行
+    order.advance(3_600 * 3 + 10).unwrap();
+    assert_eq!(order.next_execution(), 3_600 * 4);
+    assert_eq!(order.executions_left(), 1);
+}
+
+#[test]
+fn expired_order_cannot_execute() {
+    let order = order(1, 0, 0, 100);
+    assert_eq!(order.check_executable(101), Err(AmmError::Expired.into()));
+}
+
+#[test]
+fn escrow_covers_every_execution_and_tip() {
+    assert_eq!(Order::escrow_amount(1_000, 5, 3).unwrap(), 3_015);
+    assert!(Order::escrow_amount(u64::MAX, 1, 1).is_err());
+    assert!(Order::escrow_amount(u64::MAX / 2, 0, 3).is_err());
+}
+
+#[test]
+fn rejects_invalid_order_parameters() {
+    let invalid: ProgramError = AmmError::InvalidOrder.into();
+    // 多次执行必须有间隔
+    let data_without_interval = data(3, 0, 0, 100);
+    assert_eq!(
+        PlaceOrderInstructionData::try_from(data_without_interval.as_slice()).err(),
+        Some(invalid.clone())
+    );
+    // 过期时间早于开始时间
+    let data_expired = data(1, 0, 100, 50);
+    assert_eq!(
+        PlaceOrderInstructionData::try_from(data_expired.as_slice()).err(),
+        Some(invalid)
+    );
+    let data_without_executions = data(0, 60, 0, 100);
+    assert_eq!(
+        PlaceOrderInstructionData::try_from(data_without_executions.as_slice()).err(),
+        Some(AmmError::ZeroAmount.into())
+    );
+
+    let parsed = PlaceOrderInstructionData::try_from(data(3, 60, 0, 100).as_slice()).unwrap();
+    assert!(parsed.is_x);
+    assert_eq!(
+        (parsed.amount_in, parsed.min_out, parsed.tip),
+        (1_000, 900, 5)
+    );
+    assert_eq!((parsed.executions, parsed.interval), (3, 60));
+}
+
+// 用户下一个两次执行的定投单 keeper 在开始之后执行第一次
+#[test]
+#[ignore = "需要 cargo test-sbf 编译出的程序"]
+fn keeper_executes_order_from_escrow() {
+    let mut mollusk = mollusk();
+    mollusk.sysvars.clock.unix_timestamp = 100;
+    let keeper = Address::new_from_array([7; 32]);
+    let pool = Pool::new(&mollusk);
+    let (order, _) = Address::find_program_address(
+        &[
+            b"order",
+            pool.config.as_ref(),
+            pool.user.as_ref(),
+            &7u64.to_le_bytes(),
+        ],
+        &ID,
+    );
+    let escrow = ata(&order, &pool.mint_x);
+    let keeper_ata = ata(&keeper, &pool.mint_x);
+    let keeper_account = token_account(&mollusk, &pool.mint_x, &keeper, 0);
+    let pool = pool
+        .with(&order, empty())
+        .with(&escrow, empty())
+        .with(&keeper, wallet())
+        .with(&keeper_ata, keeper_account);
+
+    let mut place_data = vec![22];
+    place_data.extend_from_slice(&data(2, 3_600, 0, i64::MAX));
+    let place = instruction(
+        place_data,
+        vec![
+            meta(&pool.user, true, true),
+            meta(&order, true, false),
+            meta(&escrow, true, false),
+            meta(&pool.user_x, true, false),
+            meta(&pool.config, false, false),
+            meta(&pool.mint_x, false, false),
+            meta(&pinocchio_token::ID, false, false),
+            meta(&pinocchio_system::ID, false, false),
+            meta(&pinocchio_associated_token_account::ID, false, false),
+        ],
+    );
+    let execute = instruction(
+        vec![24],
+        vec![
+            meta(&keeper, true, true),
+            meta(&keeper_ata, true, false),
+            meta(&pool.user, false, false),
+            meta(&pool.user_y, true, false),
+            meta(&order, true, false),
+            meta(&escrow, true, false),
+            meta(&pool.vault_x, true, false),
+            meta(&pool.vault_y, true, false),
+            meta(&pool.config, true, false),
+            meta(&pool.mint_x, false, false),
+            meta(&pool.mint_y, false, false),
+            meta(&pinocchio_token::ID, false, false),
+            meta(&pinocchio_token::ID, false, false),
+        ],
+    );
+
+    let result = mollusk.process_instruction_chain(&[place, execute.clone()], &pool.accounts);
+    assert_eq!(result.program_result, ProgramResult::Success);
+    // 两次执行的输入和小费一次托管 执行一次之后还剩一份
+    assert_eq!(amount(&result, &pool.user_x), BALANCE - 2 * (1_000 + 5));
+    assert_eq!(amount(&result, &escrow), 1_000 + 5);
+    assert_eq!(amount(&result, &keeper_ata), 5);
+    assert_eq!(amount(&result, &pool.vault_x), RESERVE + 1_000);
+    let amount_out = RESERVE - amount(&result, &pool.vault_y);
+    assert!(amount_out >= 900);
+    assert_eq!(amount(&result, &pool.user_y), BALANCE + amount_out);
+    let executed: Order = state(get(&result, &order));
+    assert_eq!(executed.executions_left(), 1);
+    assert_eq!(executed.next_execution(), 3_600);
+
+    // 下一次执行要等到间隔之后
+    let result = mollusk.process_instruction(&execute, &result.resulting_accounts);
+    assert_eq!(
+        result.program_result,
+        ProgramResult::Failure(AmmError::OrderNotReady.into())
+    );
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use blueshift_native_amm::{AllowListEntry, AmmError, ID};
+use common::*;
+use mollusk_svm::result::ProgramResult;
+use pinocchio::{error::ProgramError, Address};
+use solana_instruction::Instruction;
+
+fn set_lp_frozen(pool: &Pool, authority: &Address, frozen: bool) -> Instruction {
+    instruction(
+        vec![36, frozen as u8],
+        vec![
+            meta(authority, false, true),
+            meta(&pool.config, false, false),
+            meta(&pool.mint_lp, false, false),
+            meta(&pool.user_lp, true, false),
+            meta(&pinocchio_token::ID, false, false),
+        ],
+    )
+}
+
+#[test]
+#[ignore = "需要 cargo test-sbf 编译出的程序"]
+fn authority_freezes_and_thaws_lp_account() {
+    let mollusk = mollusk();
+    let pool = Pool::new(&mollusk);
+
+    let result =
+        mollusk.process_instruction(&set_lp_frozen(&pool, &pool.user, true), &pool.accounts);
+    assert_eq!(result.program_result, ProgramResult::Success);
+    assert_eq!(token_state(&result, &pool.user_lp), 2);
+
+    let result = mollusk.process_instruction(
+        &set_lp_frozen(&pool, &pool.user, false),
+        &result.resulting_accounts,
+    );
+    assert_eq!(result.program_result, ProgramResult::Success);
+    assert_eq!(token_state(&result, &pool.user_lp), 1);
+}
+
+#[test]
+#[ignore = "需要 cargo test-sbf 编译出的程序"]
+fn only_authority_freezes_lp_account() {
+    let mollusk = mollusk();
+    let stranger = Address::new_from_array([7; 32]);
+    let pool = Pool::new(&mollusk).with(&stranger, wallet());
+
+    let result =
+        mollusk.process_instruction(&set_lp_frozen(&pool, &stranger, true), &pool.accounts);
+    assert_eq!(
+        result.program_result,
+        ProgramResult::Failure(ProgramError::IncorrectAuthority)
+    );
+}
+
+// 白名单池子 用户先被加入白名单才能注入流动性
+#[test]
+#[ignore = "需要 cargo test-sbf 编译出的程序"]
+fn permissioned_deposit_needs_allow_list_entry() {
+    let mollusk = mollusk();
+    let pool = Pool::new(&mollusk).configure(|config| config.set_permissioned(true));
+    let (entry, _) =
+        Address::find_program_address(&[b"allow", pool.config.as_ref(), pool.user.as_ref()], &ID);
+    let pool = pool.with(&entry, empty());
+
+    let result = mollusk.process_instruction(&pool.deposit(&[]), &pool.accounts);
+    assert_eq!(
+        result.program_result,
+        ProgramResult::Failure(AmmError::NotAllowListed.into())
+    );
+    // 还没有创建的白名单账户也不行
+    let result = mollusk.process_instruction(&pool.deposit(&[entry]), &pool.accounts);
+    assert_eq!(
+        result.program_result,
+        ProgramResult::Failure(AmmError::NotAllowListed.into())
+    );
+
+    let mut data = vec![38];
+    data.extend_from_slice(pool.user.as_ref());
+    let add = instruction(
+        data,
+        vec![
+            meta(&pool.user, true, true),
+            meta(&pool.config, false, false),
+            meta(&entry, true, false),
+            meta(&pinocchio_system::ID, false, false),
+        ],
+    );
+    let result = mollusk.process_instruction_chain(&[add, pool.deposit(&[entry])], &pool.accounts);
+    assert_eq!(result.program_result, ProgramResult::Success);
+    assert_eq!(amount(&result, &pool.user_lp), BALANCE + 1_000_000);
+    let allowed: AllowListEntry = state(get(&result, &entry));
+    assert_eq!(allowed.config(), &pool.config);
+    assert_eq!(allowed.user(), &pool.user);
+}
@@ -0,0 +1,107 @@
+use blueshift_native_amm::{
+    AmmError, Factory, Pair, RegistryPage, SetFactoryInstructionData, POOLS_PER_PAGE,
+};
+use pinocchio::{error::ProgramError, Address};
+
+fn zeroed<T>() -> T {
+    // 注册表账户只由地址和字节数组组成 全零就是一个合法的值
+    unsafe { core::mem::zeroed() }
+}
+
+#[test]
+fn factory_assigns_pools_to_pages_in_order() {
+    let mut factory: Factory = zeroed();
+    factory
+        .set_inner(Address::new_from_array([1; 32]), 30, 1_000, [255])
+        .unwrap();
+    assert_eq!(
+        (factory.default_fee(), factory.default_protocol_fee()),
+        (30, 1_000)
+    );
+
+    for index in 0..POOLS_PER_PAGE as u64 * 2 + 1 {
+        let expected = (
+            index / POOLS_PER_PAGE as u64,
+            (index % POOLS_PER_PAGE as u64) as usize,
+        );
+        assert_eq!(factory.register_pool().unwrap(), expected);
+    }
+    assert_eq!(factory.pool_count(), POOLS_PER_PAGE as u64 * 2 + 1);
+    assert_eq!(Factory::page_of(POOLS_PER_PAGE as u64), (1, 0));
+}
+
+#[test]
+fn factory_pause_and_admin() {
+    let admin = Address::new_from_array([1; 32]);
+    let mut factory: Factory = zeroed();
+    factory.set_inner(admin, 30, 0, [255]).unwrap();
+    assert!(factory.check_admin(&admin).is_ok());
+    assert_eq!(
+        factory.check_admin(&Address::new_from_array([2; 32])),
+        Err(ProgramError::IncorrectAuthority)
+    );
+
+    assert!(factory.check_not_paused().is_ok());
+    factory.set_paused(true);
+    assert_eq!(
+        factory.check_not_paused(),
+        Err(AmmError::FactoryPaused.into())
+    );
+
+    let invalid: ProgramError = AmmError::InvalidFee.into();
+    assert_eq!(factory.set_defaults(10_000, 0), Err(invalid.clone()));
+    assert_eq!(factory.set_defaults(30, 10_001), Err(invalid));
+}
+
+#[test]
+fn pair_keeps_first_pool() {
+    let (first, second) = (
+        Address::new_from_array([3; 32]),
+        Address::new_from_array([4; 32]),
+    );
+    let mut pair: Pair = zeroed();
+    pair.set_inner(
+        Address::new_from_array([1; 32]),
+        Address::new_from_array([2; 32]),
+        [255],
+    );
+    pair.add_pool(&first).unwrap();
+    pair.add_pool(&second).unwrap();
+    assert_eq!(pair.pool_count(), 2);
+    assert_eq!(pair.first_pool(), &first);
+}
+
+#[test]
+fn page_only_accepts_the_next_slot() {
+    let mut page: RegistryPage = zeroed();
+    page.set_inner(3, [255]);
+    let pool = Address::new_from_array([5; 32]);
+    let expected: ProgramError = AmmError::InvalidRegistry.into();
+
+    assert_eq!(page.push(1, &pool), Err(expected.clone()));
+    for slot in 0..POOLS_PER_PAGE {
+        page.push(slot, &Address::new_from_array([slot as u8; 32]))
+            .unwrap();
+    }
+    assert_eq!(page.push(POOLS_PER_PAGE, &pool), Err(expected));
+    assert_eq!(page.page(), 3);
+    assert_eq!(page.pools().len(), POOLS_PER_PAGE);
+    assert_eq!(page.pools()[7], Address::new_from_array([7; 32]));
+}
+
+#[test]
+fn parses_set_factory_data() {
+    let mut data = 25u16.to_le_bytes().to_vec();
+    data.extend_from_slice(&500u16.to_le_bytes());
+    data.push(1);
+    let parsed = SetFactoryInstructionData::try_from(data.as_slice()).unwrap();
+    assert_eq!((parsed.default_fee, parsed.default_protocol_fee), (25, 500));
+    assert!(parsed.paused && parsed.admin.is_none());
+
+    data.extend_from_slice(&[9; 32]);
+    let parsed = SetFactoryInstructionData::try_from(data.as_slice()).unwrap();
+    assert_eq!(parsed.admin, Some(Address::new_from_array([9; 32])));
+
+    data[4] = 2;
+    assert!(SetFactoryInstructionData::try_from(data.as_slice()).is_err());
+}
@@ -0,0 +1,178 @@
+use blueshift_native_amm::{
+    mul_div, Config, ConstantProduct, Curve, CurveType, LiquidityPair, StableSwap, MAX_AMP,
+    MIN_RAMP_DURATION,
+};
+
+// 测试用的伪随机数 不依赖外部库
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        self.0 >> 33
+    }
+}
+
+fn zeroed_config() -> Config {
+    // Config 只由字节数组组成 全零就是一个合法的值
+    unsafe { core::mem::zeroed() }
+}
+
+#[test]
+fn mul_div_matches_u128_arithmetic() {
+    assert_eq!(mul_div(10, 20, 3).unwrap(), 66);
+    assert_eq!(
+        mul_div(u64::MAX as u128, u64::MAX as u128, u64::MAX as u128).unwrap(),
+        u64::MAX as u128
+    );
+    assert!(mul_div(1, 1, 0).is_err());
+}
+
+#[test]
+fn mul_div_handles_256_bit_products() {
+    // (2^127) * 6 / 4 = 3 * 2^126 乘积超出 u128
+    assert_eq!(mul_div(1 << 127, 6, 4).unwrap(), 3 << 126);
+    assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX).unwrap(), u128::MAX);
+    // 商超出 u128
+    assert!(mul_div(u128::MAX, 2, 1).is_err());
+}
+
+#[test]
+fn compute_d_of_balanced_pool_is_sum() {
+    let d = StableSwap::compute_d(100, 1_000_000, 1_000_000).unwrap();
+    assert!(d.abs_diff(2_000_000) <= 1);
+}
+
+#[test]
+fn high_amp_trades_close_to_one_to_one() {
+    let mut curve = StableSwap::init(1_000_000_000, 1_000_000_000, 1_000, 0).unwrap();
+    let (_, _, out) = curve.swap(LiquidityPair::X, 10_000_000, 0).unwrap();
+    // 交换 1% 的储备 价格偏离小于 0.01%
+    assert!(out <= 10_000_000);
+    assert!(out > 9_999_000);
+
+    // 同样的交换在恒定乘积曲线上滑点大得多
+    let mut cp = ConstantProduct::init(1_000_000_000, 1_000_000_000, 0).unwrap();
+    let (_, _, cp_out) = cp.swap(LiquidityPair::X, 10_000_000, 0).unwrap();
+    assert!(cp_out < out);
+}
+
+#[test]
+fn invariant_never_decreases_over_swaps() {
+    let mut rng = Lcg(7);
+    let mut curve = StableSwap::init(5_000_000_000, 3_000_000_000, 200, 4).unwrap();
+    let mut d = curve.d().unwrap();
+    for i in 0..200 {
+        let p = match i % 2 {
+            0 => LiquidityPair::X,
+            _ => LiquidityPair::Y,
+        };
+        let amount = rng.next() % 500_000_000 + 1;
+        match rng.next() % 2 {
+            0 => {
+                curve.swap(p, amount, 0).unwrap();
+            }
+            _ => {
+                let reserve_out = match p {
+                    LiquidityPair::X => curve.y(),
+                    LiquidityPair::Y => curve.x(),
+                };
+                curve
+                    .swap_exact_out(p, amount % (reserve_out / 2) + 1, u64::MAX)
+                    .unwrap();
+            }
+        }
+        let next = curve.d().unwrap();
+        assert!(next + 1 >= d, "D decreased from {d} to {next}");
+        d = next;
+    }
+}
+
+#[test]
+fn exact_out_is_consistent_with_exact_in() {
+    let curve = StableSwap::init(2_000_000, 1_000_000, 50, 30).unwrap();
+    let (mut c1, mut c2, mut c3) = (curve, curve, curve);
+    let (_, _, out) = c1.swap(LiquidityPair::X, 100_000, 0).unwrap();
+    let (a, _, received) = c2.swap_exact_out(LiquidityPair::X, out, u64::MAX).unwrap();
+    assert_eq!(received, out);
+    // 买到同样的数量 需要支付的最多只比 exact in 多出取整误差
+    assert!(a <= 100_000 + 2);
+    // 按 exact out 算出的数量支付 至少能换到同样的数量
+    let (_, _, check) = c3.swap(LiquidityPair::X, a, 0).unwrap();
+    assert!(check >= out);
+}
+
+#[test]
+fn curve_dispatches_price_impact() {
+    let curve = Curve::StableSwap(StableSwap::init(1_000_000, 1_000_000, 100, 0).unwrap());
+    let mut swapped = curve;
+    let (a, fee, out) = swapped.swap(LiquidityPair::X, 1_000, 0).unwrap();
+    // 小额交换在平衡的池子里几乎没有价格影响
+    assert!(curve.price_impact(LiquidityPair::X, a - fee, out).unwrap() <= 2);
+}
+
+#[test]
+fn config_builds_configured_curve() {
+    let mut config = zeroed_config();
+    config
+        .set_curve(CurveType::ConstantProduct as u8, 0)
+        .unwrap();
+    assert!(matches!(
+        config.curve(100, 100, 0).unwrap(),
+        Curve::ConstantProduct(_)
+    ));
+
+    config.set_curve(CurveType::StableSwap as u8, 100).unwrap();
+    assert!(matches!(
+        config.curve(100, 100, 0).unwrap(),
+        Curve::StableSwap(_)
+    ));
+    assert!(config.set_curve(CurveType::StableSwap as u8, 0).is_err());
+    assert!(config
+        .set_curve(CurveType::StableSwap as u8, MAX_AMP + 1)
+        .is_err());
+    assert!(config.set_curve(3, 100).is_err());
+
+    // 集中流动性池没有按储备计算的曲线
+    config.set_curve(CurveType::Concentrated as u8, 100).unwrap();
+    assert_eq!(config.initial_amp(), 0);
+    assert!(config.curve(100, 100, 0).is_err());
+    assert!(config.reserves(100, 100).is_err());
+}
+
+#[test]
+fn amp_ramps_linearly() {
+    let mut config = zeroed_config();
+    config.set_curve(CurveType::StableSwap as u8, 100).unwrap();
+    let now = 1_000;
+    let stop = now + MIN_RAMP_DURATION * 2;
+    config.ramp_amp(500, now, stop).unwrap();
+    assert_eq!(config.amp(now), 100);
+    assert_eq!(config.amp(now + MIN_RAMP_DURATION), 300);
+    assert_eq!(config.amp(stop), 500);
+    assert_eq!(config.amp(stop + 1), 500);
+
+    // 调整中途再次调整 从当前的放大系数开始
+    let mid = now + MIN_RAMP_DURATION;
+    config.ramp_amp(100, mid, mid + MIN_RAMP_DURATION).unwrap();
+    assert_eq!(config.amp(mid), 300);
+    assert_eq!(config.amp(mid + MIN_RAMP_DURATION), 100);
+}
+
+#[test]
+fn amp_ramp_is_bounded() {
+    let mut config = zeroed_config();
+    // 恒定乘积池不能调整放大系数
+    assert!(config.ramp_amp(100, 0, MIN_RAMP_DURATION).is_err());
+
+    config.set_curve(CurveType::StableSwap as u8, 100).unwrap();
+    // 调整时间太短
+    assert!(config.ramp_amp(200, 0, MIN_RAMP_DURATION - 1).is_err());
+    // 一次变化超过 10 倍
+    assert!(config.ramp_amp(1_001, 0, MIN_RAMP_DURATION).is_err());
+    assert!(config.ramp_amp(9, 0, MIN_RAMP_DURATION).is_err());
+    config.ramp_amp(1_000, 0, MIN_RAMP_DURATION).unwrap();
+}